@@ -0,0 +1,518 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map, FnPtr, NativeCallContext};
+
+/// Zip and tar(.gz) archives for Rhai scripts, implemented without external tools
+pub struct ArchiveKit;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// Options accepted by the create/extract functions
+struct ArchiveOptions {
+    /// Only entries whose archive name matches one of these globs (files only)
+    include: Option<GlobSet>,
+    /// Entries whose archive name matches one of these globs are skipped (directories prune their contents)
+    exclude: Option<GlobSet>,
+    /// Called with #{ current, total, name, percent } after every file
+    on_progress: Option<FnPtr>,
+    /// Replace existing files when extracting
+    overwrite: bool,
+}
+
+impl ArchiveOptions {
+    fn from_map(options: Map) -> Result<Self, String> {
+        let mut parsed = Self { include: None, exclude: None, on_progress: None, overwrite: true };
+        for (key, value) in options {
+            match key.as_str() {
+                "include" => parsed.include = Some(Self::glob_set(&key, value)?),
+                "exclude" => parsed.exclude = Some(Self::glob_set(&key, value)?),
+                "on_progress" => parsed.on_progress = Some(value.try_cast::<FnPtr>().ok_or("Archive option 'on_progress' must be a function")?),
+                "overwrite" => parsed.overwrite = value.as_bool().map_err(|_| "Archive option 'overwrite' must be true or false".to_string())?,
+                other => return Err(format!("Unknown archive option '{}'", other)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Accepts a single pattern or an array of patterns; `*` also matches `/`
+    fn glob_set(key: &str, value: Dynamic) -> Result<GlobSet, String> {
+        let patterns = string_list(value).map_err(|_| format!("Archive option '{}' must be a glob string or an array of globs", key))?;
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(&pattern).map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?);
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    fn is_excluded(&self, name: &str) -> bool {
+        self.exclude.as_ref().is_some_and(|set| set.is_match(name))
+    }
+
+    fn accepts_file(&self, name: &str) -> bool {
+        !self.is_excluded(name) && self.include.as_ref().is_none_or(|set| set.is_match(name))
+    }
+
+    /// Explicit directory entries are only kept when no include filter narrows the file set
+    fn accepts_dir(&self, name: &str) -> bool {
+        self.include.is_none() && !self.is_excluded(name)
+    }
+}
+
+/// A file or directory queued for an archive
+struct SourceEntry {
+    path: PathBuf,
+    name: String,
+    is_dir: bool,
+}
+
+/// An entry read from an archive
+struct ArchiveEntry {
+    name: String,
+    size: u64,
+    is_dir: bool,
+    /// Symlinks, hard links and device entries are listed but never extracted
+    is_special: bool,
+}
+
+type Progress<'a> = dyn FnMut(usize, usize, &str) -> Result<(), String> + 'a;
+
+impl ArchiveKit {
+    /// Register all archive functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 ArchiveKit: Registering archive functions");
+
+        engine.register_fn("zip_create", |context: NativeCallContext, dest: &str, paths: Dynamic| -> Result<Map, Box<EvalAltResult>> {
+            Self::create_from_script(&context, ArchiveFormat::Zip, dest, paths, Map::new())
+        });
+        engine.register_fn("zip_create", |context: NativeCallContext, dest: &str, paths: Dynamic, options: Map| -> Result<Map, Box<EvalAltResult>> {
+            Self::create_from_script(&context, ArchiveFormat::Zip, dest, paths, options)
+        });
+        engine.register_fn("zip_extract", |context: NativeCallContext, archive: &str, dest: &str| -> Result<Map, Box<EvalAltResult>> {
+            Self::extract_from_script(&context, Some(ArchiveFormat::Zip), archive, dest, Map::new())
+        });
+        engine.register_fn("zip_extract", |context: NativeCallContext, archive: &str, dest: &str, options: Map| -> Result<Map, Box<EvalAltResult>> {
+            Self::extract_from_script(&context, Some(ArchiveFormat::Zip), archive, dest, options)
+        });
+
+        // tar_create compresses with gzip when the destination ends in .tar.gz or .tgz
+        engine.register_fn("tar_create", |context: NativeCallContext, dest: &str, paths: Dynamic| -> Result<Map, Box<EvalAltResult>> {
+            Self::create_from_script(&context, Self::tar_format_for(dest), dest, paths, Map::new())
+        });
+        engine.register_fn("tar_create", |context: NativeCallContext, dest: &str, paths: Dynamic, options: Map| -> Result<Map, Box<EvalAltResult>> {
+            Self::create_from_script(&context, Self::tar_format_for(dest), dest, paths, options)
+        });
+        engine.register_fn("tar_extract", |context: NativeCallContext, archive: &str, dest: &str| -> Result<Map, Box<EvalAltResult>> {
+            Self::extract_from_script(&context, None, archive, dest, Map::new())
+        });
+        engine.register_fn("tar_extract", |context: NativeCallContext, archive: &str, dest: &str, options: Map| -> Result<Map, Box<EvalAltResult>> {
+            Self::extract_from_script(&context, None, archive, dest, options)
+        });
+
+        engine.register_fn("archive_list", |path: &str| -> Result<Array, Box<EvalAltResult>> {
+            let entries = Self::list_entries(Path::new(path), Self::detect_format(Path::new(path))?)?;
+            Ok(entries.into_iter().map(|entry| {
+                let mut item_map = Map::new();
+                item_map.insert("name".into(), Dynamic::from(entry.name));
+                item_map.insert("is_dir".into(), Dynamic::from(entry.is_dir));
+                item_map.insert("is_file".into(), Dynamic::from(!entry.is_dir && !entry.is_special));
+                item_map.insert("size".into(), Dynamic::from(entry.size as i64));
+                Dynamic::from(item_map)
+            }).collect())
+        });
+
+        println!("🟣 ArchiveKit: All archive functions registered");
+    }
+
+    fn create_from_script(context: &NativeCallContext, format: ArchiveFormat, dest: &str, paths: Dynamic, options: Map) -> Result<Map, Box<EvalAltResult>> {
+        let paths = string_list(paths).map_err(|_| "Archive paths must be a path string or an array of paths".to_string())?;
+        let options = ArchiveOptions::from_map(options)?;
+        let callback = options.on_progress.clone();
+        let mut progress = |current: usize, total: usize, name: &str| Self::report(context, callback.as_ref(), current, total, name);
+        Ok(Self::create(format, Path::new(dest), &paths, &options, &mut progress)?)
+    }
+
+    fn extract_from_script(context: &NativeCallContext, format: Option<ArchiveFormat>, archive: &str, dest: &str, options: Map) -> Result<Map, Box<EvalAltResult>> {
+        let archive = Path::new(archive);
+        let format = match format {
+            Some(format) => format,
+            None => Self::detect_format(archive)?,
+        };
+        let options = ArchiveOptions::from_map(options)?;
+        let callback = options.on_progress.clone();
+        let mut progress = |current: usize, total: usize, name: &str| Self::report(context, callback.as_ref(), current, total, name);
+        Ok(Self::extract(format, archive, Path::new(dest), &options, &mut progress)?)
+    }
+
+    fn report(context: &NativeCallContext, callback: Option<&FnPtr>, current: usize, total: usize, name: &str) -> Result<(), String> {
+        let Some(callback) = callback else { return Ok(()) };
+        let mut event = Map::new();
+        event.insert("current".into(), Dynamic::from(current as i64));
+        event.insert("total".into(), Dynamic::from(total as i64));
+        event.insert("name".into(), Dynamic::from(name.to_string()));
+        event.insert("percent".into(), Dynamic::from(if total == 0 { 100.0 } else { current as f64 * 100.0 / total as f64 }));
+        callback.call_within_context::<Dynamic>(context, (event,))
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn tar_format_for(path: &str) -> ArchiveFormat {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") { ArchiveFormat::TarGz } else { ArchiveFormat::Tar }
+    }
+
+    /// Work out the format from the file contents (zip / gzip magic bytes), falling back to tar
+    fn detect_format(path: &Path) -> Result<ArchiveFormat, String> {
+        let mut magic = [0u8; 4];
+        let read = File::open(path)
+            .and_then(|mut file| file.read(&mut magic))
+            .map_err(|e| format!("Failed to open archive '{}': {}", path.display(), e))?;
+        Ok(match &magic[..read] {
+            [b'P', b'K', 3, 4] | [b'P', b'K', 5, 6] => ArchiveFormat::Zip,
+            [0x1f, 0x8b, ..] => ArchiveFormat::TarGz,
+            _ => ArchiveFormat::Tar,
+        })
+    }
+
+    /// Expand the given files and directories into archive entries, applying the filters
+    fn collect_sources(paths: &[String], options: &ArchiveOptions) -> Result<Vec<SourceEntry>, String> {
+        let mut sources = Vec::new();
+        for path in paths {
+            let root = Path::new(path);
+            let metadata = fs::symlink_metadata(root).map_err(|e| format!("Cannot archive '{}': {}", path, e))?;
+            let base = root.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| format!("Cannot archive '{}': path has no file name", path))?;
+
+            if metadata.is_file() {
+                if options.accepts_file(&base) {
+                    sources.push(SourceEntry { path: root.to_path_buf(), name: base, is_dir: false });
+                }
+                continue;
+            }
+            if !metadata.is_dir() {
+                continue;
+            }
+
+            let walker = walkdir::WalkDir::new(root).follow_links(false).sort_by_file_name().into_iter()
+                .filter_entry(|entry| !options.is_excluded(&Self::entry_name(&base, root, entry.path())));
+            for entry in walker {
+                let entry = entry.map_err(|e| format!("Cannot archive '{}': {}", path, e))?;
+                let name = Self::entry_name(&base, root, entry.path());
+                let file_type = entry.file_type();
+                if file_type.is_dir() && options.accepts_dir(&name) {
+                    sources.push(SourceEntry { path: entry.into_path(), name, is_dir: true });
+                } else if file_type.is_file() && options.accepts_file(&name) {
+                    sources.push(SourceEntry { path: entry.into_path(), name, is_dir: false });
+                }
+                // Symbolic links are not followed or stored
+            }
+        }
+        Ok(sources)
+    }
+
+    /// Archive name for a path inside `root`, always using `/` separators
+    fn entry_name(base: &str, root: &Path, path: &Path) -> String {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        std::iter::once(base.to_string())
+            .chain(relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Create an archive; it is written next to `dest` first and renamed into place when complete
+    fn create(format: ArchiveFormat, dest: &Path, paths: &[String], options: &ArchiveOptions, progress: &mut Progress) -> Result<Map, String> {
+        let sources = Self::collect_sources(paths, options)?;
+        let total = sources.iter().filter(|s| !s.is_dir).count();
+        let partial = PathBuf::from(format!("{}.part", dest.display()));
+        if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory for '{}': {}", dest.display(), e))?;
+        }
+
+        let result = File::create(&partial)
+            .map_err(|e| format!("Failed to create archive '{}': {}", dest.display(), e))
+            .and_then(|file| match format {
+                ArchiveFormat::Zip => Self::write_zip(file, &sources, total, progress),
+                ArchiveFormat::Tar => Self::write_tar(file, &sources, total, progress).map(|(bytes, _)| bytes),
+                ArchiveFormat::TarGz => {
+                    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+                    Self::write_tar(encoder, &sources, total, progress)
+                        .and_then(|(bytes, encoder)| encoder.finish().map(|_| bytes).map_err(|e| e.to_string()))
+                }
+            })
+            .and_then(|bytes| fs::rename(&partial, dest).map(|_| bytes).map_err(|e| e.to_string()));
+
+        let bytes = result.inspect_err(|_| { let _ = fs::remove_file(&partial); })
+            .map_err(|e| format!("Failed to create archive '{}': {}", dest.display(), e))?;
+
+        let mut summary = Map::new();
+        summary.insert("path".into(), Dynamic::from(dest.to_string_lossy().to_string()));
+        summary.insert("files".into(), Dynamic::from(total as i64));
+        summary.insert("bytes".into(), Dynamic::from(bytes as i64));
+        Ok(summary)
+    }
+
+    fn write_zip(file: File, sources: &[SourceEntry], total: usize, progress: &mut Progress) -> Result<u64, String> {
+        let mut writer = zip::ZipWriter::new(file);
+        let file_options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(true);
+        let mut bytes = 0;
+        let mut current = 0;
+
+        for source in sources {
+            if source.is_dir {
+                writer.add_directory(source.name.as_str(), file_options).map_err(|e| e.to_string())?;
+                continue;
+            }
+            writer.start_file(source.name.as_str(), file_options).map_err(|e| e.to_string())?;
+            let mut input = File::open(&source.path).map_err(|e| format!("{}: {}", source.path.display(), e))?;
+            bytes += io::copy(&mut input, &mut writer).map_err(|e| format!("{}: {}", source.path.display(), e))?;
+            current += 1;
+            progress(current, total, &source.name)?;
+        }
+
+        writer.finish().map_err(|e| e.to_string())?;
+        Ok(bytes)
+    }
+
+    fn write_tar<W: Write>(writer: W, sources: &[SourceEntry], total: usize, progress: &mut Progress) -> Result<(u64, W), String> {
+        let mut builder = tar::Builder::new(writer);
+        builder.follow_symlinks(false);
+        let mut bytes = 0;
+        let mut current = 0;
+
+        for source in sources {
+            if source.is_dir {
+                builder.append_dir(&source.name, &source.path).map_err(|e| format!("{}: {}", source.path.display(), e))?;
+                continue;
+            }
+            builder.append_path_with_name(&source.path, &source.name).map_err(|e| format!("{}: {}", source.path.display(), e))?;
+            bytes += fs::metadata(&source.path).map(|m| m.len()).unwrap_or(0);
+            current += 1;
+            progress(current, total, &source.name)?;
+        }
+
+        let writer = builder.into_inner().map_err(|e| e.to_string())?;
+        Ok((bytes, writer))
+    }
+
+    fn open_tar(path: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open archive '{}': {}", path.display(), e))?;
+        let reader: Box<dyn Read> = match format {
+            ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(BufReader::new(file))),
+            _ => Box::new(BufReader::new(file)),
+        };
+        Ok(tar::Archive::new(reader))
+    }
+
+    fn open_zip(path: &Path) -> Result<zip::ZipArchive<BufReader<File>>, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open archive '{}': {}", path.display(), e))?;
+        zip::ZipArchive::new(BufReader::new(file)).map_err(|e| format!("Invalid zip archive '{}': {}", path.display(), e))
+    }
+
+    fn list_entries(path: &Path, format: ArchiveFormat) -> Result<Vec<ArchiveEntry>, String> {
+        let invalid = |e: &dyn std::fmt::Display| format!("Failed to read archive '{}': {}", path.display(), e);
+        let mut entries = Vec::new();
+
+        if format == ArchiveFormat::Zip {
+            let mut archive = Self::open_zip(path)?;
+            for index in 0..archive.len() {
+                let file = archive.by_index_raw(index).map_err(|e| invalid(&e))?;
+                entries.push(ArchiveEntry {
+                    name: file.name().to_string(),
+                    size: file.size(),
+                    is_dir: file.is_dir(),
+                    is_special: file.is_symlink(),
+                });
+            }
+            return Ok(entries);
+        }
+
+        let mut archive = Self::open_tar(path, format)?;
+        for entry in archive.entries().map_err(|e| invalid(&e))? {
+            let entry = entry.map_err(|e| invalid(&e))?;
+            let entry_type = entry.header().entry_type();
+            entries.push(ArchiveEntry {
+                name: entry.path().map_err(|e| invalid(&e))?.to_string_lossy().to_string(),
+                size: entry.header().size().unwrap_or(0),
+                is_dir: entry_type.is_dir(),
+                is_special: !entry_type.is_dir() && !entry_type.is_file(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Relative path an entry may be extracted to; `None` if it would escape the destination (zip-slip)
+    fn safe_relative_path(name: &str) -> Option<PathBuf> {
+        let mut relative = PathBuf::new();
+        for component in Path::new(&name.replace('\\', "/")).components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+            }
+        }
+        Some(relative)
+    }
+
+    /// Extract a zip archive from Rust code, with the same path checks as `zip_extract`
+    pub(crate) fn extract_zip(archive: &Path, dest: &Path) -> Result<(), String> {
+        let options = ArchiveOptions { include: None, exclude: None, on_progress: None, overwrite: true };
+        Self::extract(ArchiveFormat::Zip, archive, dest, &options, &mut |_, _, _| Ok(())).map(|_| ())
+    }
+
+    /// Extract an archive; every entry name is validated before anything is written
+    fn extract(format: ArchiveFormat, archive: &Path, dest: &Path, options: &ArchiveOptions, progress: &mut Progress) -> Result<Map, String> {
+        let entries = Self::list_entries(archive, format)?;
+        for entry in &entries {
+            if Self::safe_relative_path(&entry.name).is_none() {
+                return Err(format!("Refusing to extract '{}': entry '{}' points outside the destination", archive.display(), entry.name));
+            }
+        }
+
+        let selected = |entry_name: &str, is_dir: bool| {
+            let name = entry_name.trim_end_matches('/');
+            if is_dir { !options.is_excluded(name) } else { options.accepts_file(name) }
+        };
+        let total = entries.iter().filter(|e| !e.is_dir && !e.is_special && selected(&e.name, false)).count();
+        fs::create_dir_all(dest).map_err(|e| format!("Failed to create '{}': {}", dest.display(), e))?;
+
+        let mut current = 0;
+        let mut skipped = Array::new();
+        let mut write_entry = |name: &str, is_dir: bool, is_special: bool, reader: &mut dyn Read| -> Result<(), String> {
+            if is_special || !selected(name, is_dir) {
+                if is_special {
+                    skipped.push(Dynamic::from(name.to_string()));
+                }
+                return Ok(());
+            }
+            let target = dest.join(Self::safe_relative_path(name).unwrap_or_default());
+            if is_dir {
+                return fs::create_dir_all(&target).map_err(|e| format!("Failed to create '{}': {}", target.display(), e));
+            }
+            if target.exists() && !options.overwrite {
+                skipped.push(Dynamic::from(name.to_string()));
+                return Ok(());
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+            }
+            let mut output = File::create(&target).map_err(|e| format!("Failed to write '{}': {}", target.display(), e))?;
+            io::copy(reader, &mut output).map_err(|e| format!("Failed to write '{}': {}", target.display(), e))?;
+            current += 1;
+            progress(current, total, name)
+        };
+
+        if format == ArchiveFormat::Zip {
+            let mut zip = Self::open_zip(archive)?;
+            for index in 0..zip.len() {
+                let mut file = zip.by_index(index).map_err(|e| format!("Failed to read archive '{}': {}", archive.display(), e))?;
+                let (name, is_dir, is_special) = (file.name().to_string(), file.is_dir(), file.is_symlink());
+                write_entry(&name, is_dir, is_special, &mut file)?;
+            }
+        } else {
+            let mut tar = Self::open_tar(archive, format)?;
+            for entry in tar.entries().map_err(|e| format!("Failed to read archive '{}': {}", archive.display(), e))? {
+                let mut entry = entry.map_err(|e| format!("Failed to read archive '{}': {}", archive.display(), e))?;
+                let entry_type = entry.header().entry_type();
+                let name = entry.path().map_err(|e| e.to_string())?.to_string_lossy().to_string();
+                write_entry(&name, entry_type.is_dir(), !entry_type.is_dir() && !entry_type.is_file(), &mut entry)?;
+            }
+        }
+
+        let mut summary = Map::new();
+        summary.insert("dest".into(), Dynamic::from(dest.to_string_lossy().to_string()));
+        summary.insert("files".into(), Dynamic::from(current as i64));
+        summary.insert("skipped".into(), Dynamic::from(skipped));
+        Ok(summary)
+    }
+}
+
+/// Accept either a single string or an array of strings
+fn string_list(value: Dynamic) -> Result<Vec<String>, ()> {
+    if value.is_string() {
+        return Ok(vec![value.into_string().map_err(|_| ())?]);
+    }
+    value.try_cast::<Array>()
+        .ok_or(())?
+        .into_iter()
+        .map(|item| item.into_string().map_err(|_| ()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tree() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("snaprun_archive_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("project").join("src")).unwrap();
+        fs::create_dir_all(dir.join("project").join("target")).unwrap();
+        fs::write(dir.join("project").join("README.md"), "# readme").unwrap();
+        fs::write(dir.join("project").join("src").join("main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.join("project").join("target").join("app.bin"), "binary").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_zip_and_tar_gz_round_trip_with_filters() {
+        let dir = sample_tree();
+        let mut engine = Engine::new();
+        ArchiveKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("dir", dir.to_string_lossy().to_string());
+
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let events = [];
+            let made = zip_create(dir + "/out.zip", dir + "/project", #{
+                exclude: "project/target",
+                on_progress: |p| events.push(p.name)
+            });
+            let listed = archive_list(dir + "/out.zip").filter(|e| e.is_file).map(|e| e.name);
+            let unzipped = zip_extract(dir + "/out.zip", dir + "/unzipped", #{ include: "*.rs" });
+
+            tar_create(dir + "/out.tar.gz", [dir + "/project"], #{ include: ["*.md", "*.bin"] });
+            let untarred = tar_extract(dir + "/out.tar.gz", dir + "/untarred");
+            made.files + "|" + events.len() + "|" + listed + "|" + unzipped.files + "|" + untarred.files
+        "#).unwrap();
+
+        assert_eq!(result, "2|2|[\"project/README.md\", \"project/src/main.rs\"]|1|2");
+        assert!(dir.join("unzipped/project/src/main.rs").is_file());
+        assert!(!dir.join("unzipped/project/README.md").exists());
+        assert_eq!(fs::read_to_string(dir.join("untarred/project/target/app.bin")).unwrap(), "binary");
+        assert!(!dir.join("out.zip.part").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_extract_rejects_path_traversal() {
+        let dir = std::env::temp_dir().join(format!("snaprun_zipslip_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("evil.zip");
+        {
+            let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file("ok.txt", options).unwrap();
+            writer.write_all(b"fine").unwrap();
+            writer.start_file("../escaped.txt", options).unwrap();
+            writer.write_all(b"bad").unwrap();
+            writer.finish().unwrap();
+        }
+
+        let options = ArchiveOptions::from_map(Map::new()).unwrap();
+        let result = ArchiveKit::extract(ArchiveFormat::Zip, &archive, &dir.join("out"), &options, &mut |_, _, _| Ok(()));
+        assert!(result.unwrap_err().contains("outside the destination"));
+        assert!(!dir.join("escaped.txt").exists());
+        assert!(!dir.join("out").join("ok.txt").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
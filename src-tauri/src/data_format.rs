@@ -0,0 +1,494 @@
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map};
+
+/// Parsing and serialisation of structured text formats (JSON, CSV, TOML, YAML, INI).
+/// Every format converts through `serde_json::Value`, so all of them map to the same Rhai shapes.
+pub struct DataFormatKit;
+
+/// CSV options accepted as the last argument of `parse_csv` / `to_csv`
+struct CsvOptions {
+    delimiter: u8,
+    quote: u8,
+    /// First row holds column names (parse: rows become maps; write: emit a header row)
+    headers: bool,
+    /// Trim whitespace around fields when parsing
+    trim: bool,
+    /// Column order when writing maps (defaults to the keys in order of appearance)
+    columns: Option<Vec<String>>,
+}
+
+impl CsvOptions {
+    fn from_map(options: Map) -> Result<Self, String> {
+        let mut parsed = Self { delimiter: b',', quote: b'"', headers: true, trim: false, columns: None };
+        for (key, value) in options {
+            match key.as_str() {
+                "delimiter" => parsed.delimiter = Self::single_byte(&key, value)?,
+                "quote" => parsed.quote = Self::single_byte(&key, value)?,
+                "headers" => parsed.headers = value.as_bool().map_err(|_| "CSV option 'headers' must be true or false".to_string())?,
+                "trim" => parsed.trim = value.as_bool().map_err(|_| "CSV option 'trim' must be true or false".to_string())?,
+                "columns" => {
+                    let columns = value.try_cast::<Array>().ok_or("CSV option 'columns' must be an array of names")?;
+                    parsed.columns = Some(columns.into_iter().map(|c| c.to_string()).collect());
+                }
+                other => return Err(format!("Unknown CSV option '{}'", other)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn single_byte(key: &str, value: Dynamic) -> Result<u8, String> {
+        let text = value.into_string().map_err(|_| format!("CSV option '{}' must be a string", key))?;
+        let text = if text == "\\t" { "\t".to_string() } else { text };
+        match text.as_bytes() {
+            [byte] => Ok(*byte),
+            _ => Err(format!("CSV option '{}' must be a single ASCII character", key)),
+        }
+    }
+}
+
+impl DataFormatKit {
+    /// Register all data format functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 DataFormatKit: Registering data format functions");
+
+        // JSON keeps its original lenient behaviour: errors are printed and a default is returned
+        engine.register_fn("parse_json", |json_str: &str| -> Dynamic {
+            match Self::parse_json(json_str) {
+                Ok(data) => data,
+                Err(e) => {
+                    eprintln!("JSON parse error: {}", e);
+                    Dynamic::UNIT
+                }
+            }
+        });
+        engine.register_fn("to_json", |data: Dynamic| -> String {
+            match Self::to_json(data) {
+                Ok(json_str) => json_str,
+                Err(e) => {
+                    eprintln!("JSON serialization error: {}", e);
+                    "{}".to_string()
+                }
+            }
+        });
+
+        engine.register_fn("parse_csv", |text: &str| -> Result<Array, Box<EvalAltResult>> {
+            Ok(Self::parse_csv(text, Map::new())?)
+        });
+        engine.register_fn("parse_csv", |text: &str, options: Map| -> Result<Array, Box<EvalAltResult>> {
+            Ok(Self::parse_csv(text, options)?)
+        });
+        engine.register_fn("to_csv", |rows: Array| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::to_csv(rows, Map::new())?)
+        });
+        engine.register_fn("to_csv", |rows: Array, options: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::to_csv(rows, options)?)
+        });
+
+        engine.register_fn("parse_toml", |text: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            Ok(Self::parse_toml(text)?)
+        });
+        engine.register_fn("to_toml", |data: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::to_toml(data)?)
+        });
+
+        engine.register_fn("parse_yaml", |text: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            Ok(Self::parse_yaml(text)?)
+        });
+        engine.register_fn("to_yaml", |data: Dynamic| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::to_yaml(data)?)
+        });
+
+        engine.register_fn("parse_ini", |text: &str| -> Result<Map, Box<EvalAltResult>> {
+            Ok(Self::parse_ini(text)?)
+        });
+        engine.register_fn("to_ini", |data: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::to_ini(data)?)
+        });
+
+        println!("🟣 DataFormatKit: All data format functions registered");
+    }
+
+    /// Parse JSON string to Rhai map/array
+    pub fn parse_json(json_str: &str) -> Result<Dynamic, String> {
+        serde_json::from_str::<serde_json::Value>(json_str)
+            .map_err(|e| format!("JSON parse error: {}", e))
+            .and_then(json_value_to_rhai)
+    }
+
+    /// Convert Rhai data to JSON string
+    pub fn to_json(data: Dynamic) -> Result<String, String> {
+        let json_value = rhai_to_json_value(data)?;
+        serde_json::to_string_pretty(&json_value)
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }
+
+    /// Parse CSV into an array of maps (with headers) or an array of string arrays
+    pub fn parse_csv(text: &str, options: Map) -> Result<Array, String> {
+        let options = CsvOptions::from_map(options)?;
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .quote(options.quote)
+            .has_headers(options.headers)
+            .trim(if options.trim { csv::Trim::All } else { csv::Trim::None })
+            .from_reader(text.as_bytes());
+
+        let headers: Option<Vec<String>> = if options.headers {
+            let record = reader.headers().map_err(|e| format!("CSV parse error: {}", e))?;
+            Some(record.iter().map(str::to_string).collect())
+        } else {
+            None
+        };
+
+        let mut rows = Array::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("CSV parse error: {}", e))?;
+            let row = match &headers {
+                Some(headers) => {
+                    let mut map = Map::new();
+                    for (name, field) in headers.iter().zip(record.iter()) {
+                        map.insert(name.as_str().into(), Dynamic::from(field.to_string()));
+                    }
+                    Dynamic::from(map)
+                }
+                None => Dynamic::from(record.iter().map(|field| Dynamic::from(field.to_string())).collect::<Array>()),
+            };
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    /// Write an array of maps or arrays as CSV
+    pub fn to_csv(rows: Array, options: Map) -> Result<String, String> {
+        let options = CsvOptions::from_map(options)?;
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .quote(options.quote)
+            .from_writer(Vec::new());
+
+        let columns = options.columns.clone().unwrap_or_else(|| {
+            let mut columns: Vec<String> = Vec::new();
+            for row in rows.iter().filter_map(|row| row.read_lock::<Map>()) {
+                for key in row.keys() {
+                    if !columns.iter().any(|c| c == key.as_str()) {
+                        columns.push(key.to_string());
+                    }
+                }
+            }
+            columns
+        });
+
+        let has_maps = rows.iter().any(|row| row.is_map());
+        if options.headers && has_maps {
+            writer.write_record(&columns).map_err(|e| format!("CSV write error: {}", e))?;
+        }
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let fields: Vec<String> = if let Some(map) = row.read_lock::<Map>() {
+                columns.iter().map(|c| map.get(c.as_str()).map(Self::csv_field).unwrap_or_default()).collect()
+            } else if let Some(array) = row.read_lock::<Array>() {
+                array.iter().map(Self::csv_field).collect()
+            } else {
+                return Err(format!("CSV row {} must be a map or an array", index + 1));
+            };
+            writer.write_record(&fields).map_err(|e| format!("CSV write error: {}", e))?;
+        }
+
+        let bytes = writer.into_inner().map_err(|e| format!("CSV write error: {}", e))?;
+        String::from_utf8(bytes).map_err(|e| format!("CSV write error: {}", e))
+    }
+
+    fn csv_field(value: &Dynamic) -> String {
+        if value.is_unit() { String::new() } else { value.to_string() }
+    }
+
+    /// Parse a TOML document into a Rhai map (dates become ISO strings)
+    pub fn parse_toml(text: &str) -> Result<Dynamic, String> {
+        let value: toml::Value = text.parse::<toml::Table>()
+            .map(toml::Value::Table)
+            .map_err(|e| format!("TOML parse error: {}", e))?;
+        json_value_to_rhai(Self::toml_to_json(value))
+    }
+
+    /// Serialise a Rhai map as TOML; `()` values are left out since TOML has no null
+    pub fn to_toml(data: Map) -> Result<String, String> {
+        let json = Self::strip_nulls(rhai_to_json_value(Dynamic::from(data))?);
+        toml::to_string_pretty(&json).map_err(|e| format!("TOML serialization error: {}", e))
+    }
+
+    fn toml_to_json(value: toml::Value) -> serde_json::Value {
+        match value {
+            toml::Value::String(s) => serde_json::Value::String(s),
+            toml::Value::Integer(i) => serde_json::Value::from(i),
+            toml::Value::Float(f) => serde_json::Number::from_f64(f)
+                .map(serde_json::Value::Number)
+                .unwrap_or_else(|| serde_json::Value::String(f.to_string())),
+            toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+            toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+            toml::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(Self::toml_to_json).collect()),
+            toml::Value::Table(table) => serde_json::Value::Object(
+                table.into_iter().map(|(k, v)| (k, Self::toml_to_json(v))).collect()
+            ),
+        }
+    }
+
+    fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.into_iter().filter(|(_, v)| !v.is_null()).map(|(k, v)| (k, Self::strip_nulls(v))).collect()
+            ),
+            serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(Self::strip_nulls).collect()),
+            other => other,
+        }
+    }
+
+    /// Parse a YAML document into Rhai values (non-string keys are converted to strings)
+    pub fn parse_yaml(text: &str) -> Result<Dynamic, String> {
+        let value: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| format!("YAML parse error: {}", e))?;
+        json_value_to_rhai(Self::yaml_to_json(value)?)
+    }
+
+    /// Serialise Rhai values as YAML
+    pub fn to_yaml(data: Dynamic) -> Result<String, String> {
+        let json = rhai_to_json_value(data)?;
+        serde_yaml::to_string(&json).map_err(|e| format!("YAML serialization error: {}", e))
+    }
+
+    fn yaml_to_json(value: serde_yaml::Value) -> Result<serde_json::Value, String> {
+        Ok(match value {
+            serde_yaml::Value::Null => serde_json::Value::Null,
+            serde_yaml::Value::Bool(b) => serde_json::Value::Bool(b),
+            serde_yaml::Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    serde_json::Value::from(i)
+                } else {
+                    n.as_f64()
+                        .and_then(serde_json::Number::from_f64)
+                        .map(serde_json::Value::Number)
+                        .unwrap_or_else(|| serde_json::Value::String(n.to_string()))
+                }
+            }
+            serde_yaml::Value::String(s) => serde_json::Value::String(s),
+            serde_yaml::Value::Sequence(items) => serde_json::Value::Array(
+                items.into_iter().map(Self::yaml_to_json).collect::<Result<_, _>>()?
+            ),
+            serde_yaml::Value::Mapping(mapping) => {
+                let mut object = serde_json::Map::new();
+                for (key, value) in mapping {
+                    let key = match Self::yaml_to_json(key)? {
+                        serde_json::Value::String(s) => s,
+                        serde_json::Value::Null => "null".to_string(),
+                        scalar @ (serde_json::Value::Bool(_) | serde_json::Value::Number(_)) => scalar.to_string(),
+                        _ => return Err("YAML parse error: only scalar mapping keys are supported".to_string()),
+                    };
+                    object.insert(key, Self::yaml_to_json(value)?);
+                }
+                serde_json::Value::Object(object)
+            }
+            serde_yaml::Value::Tagged(tagged) => Self::yaml_to_json(tagged.value)?,
+        })
+    }
+
+    /// Parse INI text: keys before the first section go at the top level, each `[section]` becomes a nested map
+    pub fn parse_ini(text: &str) -> Result<Map, String> {
+        let mut root = Map::new();
+        let mut section: Option<(String, Map)> = None;
+
+        for (index, raw_line) in text.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some((name, entries)) = section.take() {
+                    root.insert(name.into(), Dynamic::from(entries));
+                }
+                let name = name.trim().to_string();
+                let existing = root.remove(name.as_str()).and_then(|v| v.try_cast::<Map>()).unwrap_or_default();
+                section = Some((name, existing));
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("INI parse error on line {}: expected 'key = value'", index + 1))?;
+            let value = value.trim();
+            let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+
+            let target = match section.as_mut() {
+                Some((_, entries)) => entries,
+                None => &mut root,
+            };
+            target.insert(key.trim().into(), Dynamic::from(value.to_string()));
+        }
+
+        if let Some((name, entries)) = section {
+            root.insert(name.into(), Dynamic::from(entries));
+        }
+        Ok(root)
+    }
+
+    /// Write a map as INI: scalar values first, then one `[section]` per nested map
+    pub fn to_ini(data: Map) -> Result<String, String> {
+        let mut output = String::new();
+        let mut sections = Vec::new();
+
+        for (key, value) in data {
+            if value.is_map() {
+                sections.push((key, value.cast::<Map>()));
+            } else {
+                output.push_str(&Self::ini_line(&key, &value)?);
+            }
+        }
+
+        for (name, entries) in sections {
+            if !output.is_empty() {
+                output.push('\n');
+            }
+            output.push_str(&format!("[{}]\n", name));
+            for (key, value) in entries {
+                if value.is_map() || value.is_array() {
+                    return Err(format!("INI value '{}.{}' must be a scalar; INI supports only one level of sections", name, key));
+                }
+                output.push_str(&Self::ini_line(&key, &value)?);
+            }
+        }
+        Ok(output)
+    }
+
+    fn ini_line(key: &str, value: &Dynamic) -> Result<String, String> {
+        if value.is_array() {
+            return Err(format!("INI value '{}' must be a scalar", key));
+        }
+        let text = if value.is_unit() { String::new() } else { value.to_string() };
+        let needs_quotes = text != text.trim() || text.contains(';') || text.contains('#');
+        Ok(if needs_quotes { format!("{} = \"{}\"\n", key, text) } else { format!("{} = {}\n", key, text) })
+    }
+}
+
+/// Convert a JSON value into the equivalent Rhai value
+pub fn json_value_to_rhai(value: serde_json::Value) -> Result<Dynamic, String> {
+    match value {
+        serde_json::Value::Null => Ok(Dynamic::UNIT),
+        serde_json::Value::Bool(b) => Ok(Dynamic::from(b)),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Ok(Dynamic::from(i))
+            } else if let Some(f) = n.as_f64() {
+                Ok(Dynamic::from(f))
+            } else {
+                Ok(Dynamic::from(n.to_string()))
+            }
+        },
+        serde_json::Value::String(s) => Ok(Dynamic::from(s)),
+        serde_json::Value::Array(arr) => {
+            let mut rhai_arr = Array::new();
+            for item in arr {
+                rhai_arr.push(json_value_to_rhai(item)?);
+            }
+            Ok(Dynamic::from(rhai_arr))
+        },
+        serde_json::Value::Object(obj) => {
+            let mut rhai_map = Map::new();
+            for (k, v) in obj {
+                rhai_map.insert(k.into(), json_value_to_rhai(v)?);
+            }
+            Ok(Dynamic::from(rhai_map))
+        }
+    }
+}
+
+/// Convert a Rhai value into the equivalent JSON value
+pub fn rhai_to_json_value(data: Dynamic) -> Result<serde_json::Value, String> {
+    if data.is::<()>() {
+        Ok(serde_json::Value::Null)
+    } else if let Some(b) = data.clone().try_cast::<bool>() {
+        Ok(serde_json::Value::Bool(b))
+    } else if let Some(i) = data.clone().try_cast::<i64>() {
+        Ok(serde_json::Value::Number(serde_json::Number::from(i)))
+    } else if let Some(f) = data.clone().try_cast::<f64>() {
+        serde_json::Number::from_f64(f)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| "Invalid float number".to_string())
+    } else if let Some(s) = data.clone().try_cast::<String>() {
+        Ok(serde_json::Value::String(s))
+    } else if let Some(arr) = data.clone().try_cast::<Array>() {
+        let mut json_arr = Vec::new();
+        for item in arr {
+            json_arr.push(rhai_to_json_value(item)?);
+        }
+        Ok(serde_json::Value::Array(json_arr))
+    } else if let Some(map) = data.clone().try_cast::<Map>() {
+        let mut json_obj = serde_json::Map::new();
+        for (k, v) in map {
+            json_obj.insert(k.to_string(), rhai_to_json_value(v)?);
+        }
+        Ok(serde_json::Value::Object(json_obj))
+    } else {
+        // Fallback: convert to string
+        Ok(serde_json::Value::String(format!("{}", data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        DataFormatKit::register_functions(&mut engine);
+        engine
+    }
+
+    #[test]
+    fn test_csv_round_trip_with_options() {
+        let engine = engine();
+        let rows = engine.eval::<Array>(r#"parse_csv("name;note\nAda;\"a;b\"\nBob; x ", #{ delimiter: ";", trim: true })"#).unwrap();
+        assert_eq!(rows.len(), 2);
+        let first = rows[0].clone().cast::<Map>();
+        assert_eq!(first["note"].clone().into_string().unwrap(), "a;b");
+        assert_eq!(rows[1].clone().cast::<Map>()["note"].clone().into_string().unwrap(), "x");
+
+        let csv = engine.eval::<String>(r#"to_csv([#{ b: 2, a: "x,y" }, #{ a: 1 }], #{ columns: ["a", "b"] })"#).unwrap();
+        assert_eq!(csv, "a,b\n\"x,y\",2\n1,\n");
+
+        let raw = engine.eval::<Array>(r#"parse_csv("1,2\n3,4", #{ headers: false })"#).unwrap();
+        assert_eq!(raw[1].clone().cast::<Array>()[0].clone().into_string().unwrap(), "3");
+        assert!(engine.eval::<Array>(r#"parse_csv("a,b\n1,2,3")"#).is_err());
+    }
+
+    #[test]
+    fn test_toml_and_yaml() {
+        let engine = engine();
+        let port = engine.eval::<i64>(r#"
+            let cfg = parse_toml("title = \"app\"\n[server]\nport = 8080\nstarted = 2024-01-31T10:00:00Z\n");
+            cfg.server.port
+        "#).unwrap();
+        assert_eq!(port, 8080);
+        let toml = engine.eval::<String>(r#"to_toml(#{ name: "x", skip: (), server: #{ port: 1 } })"#).unwrap();
+        assert!(toml.contains("name = \"x\"") && toml.contains("[server]") && !toml.contains("skip"));
+
+        let yaml_value = engine.eval::<String>(r#"
+            let doc = parse_yaml("items:\n  - name: a\n    tags: [x, y]\n1: one\n");
+            doc.items[0].tags[1] + doc["1"]
+        "#).unwrap();
+        assert_eq!(yaml_value, "yone");
+        let yaml = engine.eval::<String>(r#"to_yaml(#{ list: [1, 2] })"#).unwrap();
+        assert_eq!(yaml, "list:\n- 1\n- 2\n");
+    }
+
+    #[test]
+    fn test_ini_and_json() {
+        let engine = engine();
+        let ini = engine.eval::<Map>("parse_ini(\"; comment\\nroot = 1\\n[db]\\nhost = localhost\\nname = \\\" spaced \\\"\\n\")").unwrap();
+        assert_eq!(ini["root"].clone().into_string().unwrap(), "1");
+        let db = ini["db"].clone().cast::<Map>();
+        assert_eq!(db["host"].clone().into_string().unwrap(), "localhost");
+        assert_eq!(db["name"].clone().into_string().unwrap(), " spaced ");
+
+        let text = engine.eval::<String>(r#"to_ini(#{ verbose: true, db: #{ host: "h", note: "a;b" } })"#).unwrap();
+        assert_eq!(text, "verbose = true\n\n[db]\nhost = h\nnote = \"a;b\"\n");
+        assert!(engine.eval::<String>(r#"to_ini(#{ a: #{ b: #{ c: 1 } } })"#).is_err());
+
+        let json = engine.eval::<String>(r#"to_json(parse_json("{\"a\": [1, 2.5, null]}"))"#).unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&json).unwrap(), serde_json::json!({"a": [1, 2.5, null]}));
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, NaiveDateTime,
+    Offset, TimeZone, Timelike, Utc,
+};
+use rhai::{Engine, EvalAltResult};
+
+/// Date and time value exposed to Rhai as `DateTime`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScriptDateTime(pub DateTime<FixedOffset>);
+
+/// Time span exposed to Rhai as `Duration`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScriptDuration(pub Duration);
+
+/// Formats tried by `parse_date(str)` when no format is given
+const NAIVE_DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%d.%m.%Y %H:%M:%S",
+];
+const NAIVE_DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y"];
+
+/// Date, time, duration and time zone functions for Rhai scripts
+pub struct DateTimeKit;
+
+impl DateTimeKit {
+    /// Register all date/time functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 DateTimeKit: Registering date and time functions");
+
+        engine.register_type_with_name::<ScriptDateTime>("DateTime");
+        engine.register_type_with_name::<ScriptDuration>("Duration");
+
+        // Construction
+        engine.register_fn("now", || ScriptDateTime(Local::now().fixed_offset()));
+        engine.register_fn("utc_now", || ScriptDateTime(Utc::now().fixed_offset()));
+        engine.register_fn("date", |year: i64, month: i64, day: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::from_parts(year, month, day, 0, 0, 0)?)
+        });
+        engine.register_fn("date", |year: i64, month: i64, day: i64, hour: i64, minute: i64, second: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::from_parts(year, month, day, hour, minute, second)?)
+        });
+        engine.register_fn("from_timestamp", |seconds: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            DateTime::from_timestamp(seconds, 0)
+                .map(|dt| ScriptDateTime(dt.with_timezone(&Local).fixed_offset()))
+                .ok_or_else(|| format!("Timestamp {} is out of range", seconds).into())
+        });
+
+        // Parsing and formatting
+        engine.register_fn("parse_date", |text: &str| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::parse_auto(text)?)
+        });
+        engine.register_fn("parse_date", |text: &str, format: &str| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::parse_with_format(text, format, "local")?)
+        });
+        engine.register_fn("parse_date", |text: &str, format: &str, tz: &str| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::parse_with_format(text, format, tz)?)
+        });
+        engine.register_fn("format_date", |dt: ScriptDateTime, format: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::format(dt, format)?)
+        });
+        engine.register_fn("format", |dt: &mut ScriptDateTime, format: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::format(*dt, format)?)
+        });
+        engine.register_fn("to_iso", |dt: &mut ScriptDateTime| dt.0.to_rfc3339());
+        engine.register_fn("to_rfc2822", |dt: &mut ScriptDateTime| dt.0.to_rfc2822());
+        engine.register_fn("to_string", |dt: &mut ScriptDateTime| dt.0.to_rfc3339());
+        engine.register_fn("to_debug", |dt: &mut ScriptDateTime| format!("DateTime({})", dt.0.to_rfc3339()));
+
+        // Components
+        engine.register_get("year", |dt: &mut ScriptDateTime| dt.0.year() as i64);
+        engine.register_get("month", |dt: &mut ScriptDateTime| dt.0.month() as i64);
+        engine.register_get("day", |dt: &mut ScriptDateTime| dt.0.day() as i64);
+        engine.register_get("hour", |dt: &mut ScriptDateTime| dt.0.hour() as i64);
+        engine.register_get("minute", |dt: &mut ScriptDateTime| dt.0.minute() as i64);
+        engine.register_get("second", |dt: &mut ScriptDateTime| dt.0.second() as i64);
+        engine.register_get("millisecond", |dt: &mut ScriptDateTime| (dt.0.nanosecond() / 1_000_000) as i64);
+        engine.register_get("timestamp", |dt: &mut ScriptDateTime| dt.0.timestamp());
+        engine.register_get("timestamp_millis", |dt: &mut ScriptDateTime| dt.0.timestamp_millis());
+        engine.register_get("offset", |dt: &mut ScriptDateTime| dt.0.offset().to_string());
+
+        // Weekday and calendar helpers (weekday: Monday = 1 ... Sunday = 7)
+        engine.register_get("weekday", |dt: &mut ScriptDateTime| dt.0.weekday().number_from_monday() as i64);
+        engine.register_get("weekday_name", |dt: &mut ScriptDateTime| dt.0.format("%A").to_string());
+        engine.register_get("week_of_year", |dt: &mut ScriptDateTime| dt.0.iso_week().week() as i64);
+        engine.register_get("iso_week_year", |dt: &mut ScriptDateTime| dt.0.iso_week().year() as i64);
+        engine.register_get("day_of_year", |dt: &mut ScriptDateTime| dt.0.ordinal() as i64);
+        engine.register_fn("is_weekend", |dt: &mut ScriptDateTime| dt.0.weekday().number_from_monday() >= 6);
+        engine.register_fn("start_of_day", |dt: &mut ScriptDateTime| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            let naive = dt.0.date_naive().and_hms_opt(0, 0, 0).unwrap();
+            Ok(ScriptDateTime(Self::resolve_naive(naive, dt.0.offset().fix())?))
+        });
+        engine.register_fn("days_in_month", |dt: &mut ScriptDateTime| -> i64 {
+            let (year, month) = (dt.0.year(), dt.0.month());
+            let next = if month == 12 { NaiveDate::from_ymd_opt(year + 1, 1, 1) } else { NaiveDate::from_ymd_opt(year, month + 1, 1) };
+            let first = NaiveDate::from_ymd_opt(year, month, 1);
+            match (first, next) {
+                (Some(first), Some(next)) => (next - first).num_days(),
+                _ => 0,
+            }
+        });
+
+        // Arithmetic
+        engine.register_fn("add_seconds", |dt: ScriptDateTime, n: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::add(dt, Duration::try_seconds(n))?)
+        });
+        engine.register_fn("add_minutes", |dt: ScriptDateTime, n: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::add(dt, Duration::try_minutes(n))?)
+        });
+        engine.register_fn("add_hours", |dt: ScriptDateTime, n: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::add(dt, Duration::try_hours(n))?)
+        });
+        engine.register_fn("add_days", |dt: ScriptDateTime, n: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::add(dt, Duration::try_days(n))?)
+        });
+        engine.register_fn("add_weeks", |dt: ScriptDateTime, n: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::add(dt, Duration::try_weeks(n))?)
+        });
+        engine.register_fn("add_months", |dt: ScriptDateTime, n: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            let months = Months::new(n.unsigned_abs().min(u32::MAX as u64) as u32);
+            let result = if n >= 0 { dt.0.checked_add_months(months) } else { dt.0.checked_sub_months(months) };
+            result.map(ScriptDateTime).ok_or_else(|| "Date out of range".into())
+        });
+        engine.register_fn("add_years", |dt: ScriptDateTime, n: i64| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            let months = Months::new((n.unsigned_abs() * 12).min(u32::MAX as u64) as u32);
+            let result = if n >= 0 { dt.0.checked_add_months(months) } else { dt.0.checked_sub_months(months) };
+            result.map(ScriptDateTime).ok_or_else(|| "Date out of range".into())
+        });
+        engine.register_fn("+", |dt: ScriptDateTime, d: ScriptDuration| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::add(dt, Some(d.0))?)
+        });
+        engine.register_fn("-", |dt: ScriptDateTime, d: ScriptDuration| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            Ok(Self::add(dt, Some(-d.0))?)
+        });
+        engine.register_fn("-", |a: ScriptDateTime, b: ScriptDateTime| ScriptDuration(a.0 - b.0));
+        engine.register_fn("diff", |a: ScriptDateTime, b: ScriptDateTime| ScriptDuration(a.0 - b.0));
+
+        // Comparison
+        engine.register_fn("==", |a: ScriptDateTime, b: ScriptDateTime| a == b);
+        engine.register_fn("!=", |a: ScriptDateTime, b: ScriptDateTime| a != b);
+        engine.register_fn("<", |a: ScriptDateTime, b: ScriptDateTime| a < b);
+        engine.register_fn("<=", |a: ScriptDateTime, b: ScriptDateTime| a <= b);
+        engine.register_fn(">", |a: ScriptDateTime, b: ScriptDateTime| a > b);
+        engine.register_fn(">=", |a: ScriptDateTime, b: ScriptDateTime| a >= b);
+
+        // Time zones: "UTC", "local", "+05:30" or IANA names like "Europe/Paris"
+        engine.register_fn("to_timezone", |dt: ScriptDateTime, tz: &str| -> Result<ScriptDateTime, Box<EvalAltResult>> {
+            let offset = Self::resolve_timezone(tz, dt.0.naive_utc())?;
+            Ok(ScriptDateTime(dt.0.with_timezone(&offset)))
+        });
+        engine.register_fn("to_utc", |dt: ScriptDateTime| ScriptDateTime(dt.0.with_timezone(&Utc).fixed_offset()));
+        engine.register_fn("to_local", |dt: ScriptDateTime| ScriptDateTime(dt.0.with_timezone(&Local).fixed_offset()));
+
+        // Durations
+        engine.register_fn("millis", |n: i64| -> Result<ScriptDuration, Box<EvalAltResult>> { Ok(Self::duration(Duration::try_milliseconds(n))?) });
+        engine.register_fn("seconds", |n: i64| -> Result<ScriptDuration, Box<EvalAltResult>> { Ok(Self::duration(Duration::try_seconds(n))?) });
+        engine.register_fn("minutes", |n: i64| -> Result<ScriptDuration, Box<EvalAltResult>> { Ok(Self::duration(Duration::try_minutes(n))?) });
+        engine.register_fn("hours", |n: i64| -> Result<ScriptDuration, Box<EvalAltResult>> { Ok(Self::duration(Duration::try_hours(n))?) });
+        engine.register_fn("days", |n: i64| -> Result<ScriptDuration, Box<EvalAltResult>> { Ok(Self::duration(Duration::try_days(n))?) });
+        engine.register_get("total_millis", |d: &mut ScriptDuration| d.0.num_milliseconds());
+        engine.register_get("total_seconds", |d: &mut ScriptDuration| d.0.num_seconds());
+        engine.register_get("total_minutes", |d: &mut ScriptDuration| d.0.num_seconds() as f64 / 60.0);
+        engine.register_get("total_hours", |d: &mut ScriptDuration| d.0.num_seconds() as f64 / 3600.0);
+        engine.register_get("total_days", |d: &mut ScriptDuration| d.0.num_seconds() as f64 / 86400.0);
+        engine.register_fn("+", |a: ScriptDuration, b: ScriptDuration| ScriptDuration(a.0 + b.0));
+        engine.register_fn("-", |a: ScriptDuration, b: ScriptDuration| ScriptDuration(a.0 - b.0));
+        engine.register_fn("==", |a: ScriptDuration, b: ScriptDuration| a == b);
+        engine.register_fn("<", |a: ScriptDuration, b: ScriptDuration| a < b);
+        engine.register_fn(">", |a: ScriptDuration, b: ScriptDuration| a > b);
+        engine.register_fn("to_string", |d: &mut ScriptDuration| Self::format_duration(d.0));
+        engine.register_fn("to_debug", |d: &mut ScriptDuration| format!("Duration({})", Self::format_duration(d.0)));
+
+        // Sleeping
+        engine.register_fn("sleep", |ms: i64| {
+            std::thread::sleep(std::time::Duration::from_millis(ms.max(0) as u64));
+        });
+
+        println!("🟣 DateTimeKit: All date and time functions registered");
+    }
+
+    fn from_parts(year: i64, month: i64, day: i64, hour: i64, minute: i64, second: i64) -> Result<ScriptDateTime, String> {
+        let naive = NaiveDate::from_ymd_opt(year as i32, month as u32, day as u32)
+            .and_then(|d| d.and_hms_opt(hour as u32, minute as u32, second as u32))
+            .ok_or_else(|| format!("Invalid date {}-{}-{} {}:{}:{}", year, month, day, hour, minute, second))?;
+        Ok(ScriptDateTime(Self::resolve_naive_local(naive)?))
+    }
+
+    /// Parse RFC 3339 / ISO-8601, RFC 2822 and a few common local formats
+    pub fn parse_auto(text: &str) -> Result<ScriptDateTime, String> {
+        let text = text.trim();
+        if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+            return Ok(ScriptDateTime(dt));
+        }
+        if let Ok(dt) = DateTime::parse_from_rfc2822(text) {
+            return Ok(ScriptDateTime(dt));
+        }
+        for format in NAIVE_DATETIME_FORMATS {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(text, format) {
+                return Ok(ScriptDateTime(Self::resolve_naive_local(naive)?));
+            }
+        }
+        for format in NAIVE_DATE_FORMATS {
+            if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+                return Ok(ScriptDateTime(Self::resolve_naive_local(date.and_hms_opt(0, 0, 0).unwrap())?));
+            }
+        }
+        Err(format!("Could not parse date '{}'", text))
+    }
+
+    /// Parse with a strftime-style format. Values without an offset are read in time zone `tz`.
+    pub fn parse_with_format(text: &str, format: &str, tz: &str) -> Result<ScriptDateTime, String> {
+        if let Ok(dt) = DateTime::parse_from_str(text, format) {
+            return Ok(ScriptDateTime(dt));
+        }
+        let naive = NaiveDateTime::parse_from_str(text, format)
+            .or_else(|_| NaiveDate::parse_from_str(text, format).map(|d| d.and_hms_opt(0, 0, 0).unwrap()))
+            .map_err(|e| format!("Could not parse date '{}' with format '{}': {}", text, format, e))?;
+
+        // Resolve the offset at (approximately) that wall-clock time
+        let offset = Self::resolve_timezone(tz, naive)?;
+        Ok(ScriptDateTime(Self::resolve_naive(naive, offset)?))
+    }
+
+    fn resolve_naive_local(naive: NaiveDateTime) -> Result<DateTime<FixedOffset>, String> {
+        Local.from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| dt.fixed_offset())
+            .ok_or_else(|| format!("{} does not exist in the local time zone", naive))
+    }
+
+    fn resolve_naive(naive: NaiveDateTime, offset: FixedOffset) -> Result<DateTime<FixedOffset>, String> {
+        offset.from_local_datetime(&naive)
+            .single()
+            .ok_or_else(|| format!("Invalid local time {}", naive))
+    }
+
+    /// Resolve a time zone name to its UTC offset at the given instant
+    pub fn resolve_timezone(tz: &str, at_utc: NaiveDateTime) -> Result<FixedOffset, String> {
+        let tz = tz.trim();
+        match tz.to_lowercase().as_str() {
+            "utc" | "z" | "gmt" => return Ok(Utc.fix()),
+            "local" => return Ok(Local.offset_from_utc_datetime(&at_utc).fix()),
+            _ => {}
+        }
+
+        if tz.starts_with('+') || tz.starts_with('-') {
+            let sign = if tz.starts_with('-') { -1 } else { 1 };
+            let digits: String = tz[1..].chars().filter(|c| c.is_ascii_digit()).collect();
+            let (hours, minutes) = match digits.len() {
+                1 | 2 => (digits.parse::<i32>().ok(), Some(0)),
+                4 => (digits[..2].parse::<i32>().ok(), digits[2..].parse::<i32>().ok()),
+                _ => (None, None),
+            };
+            if let (Some(hours), Some(minutes)) = (hours, minutes) {
+                if let Some(offset) = FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)) {
+                    return Ok(offset);
+                }
+            }
+            return Err(format!("Invalid UTC offset '{}'", tz));
+        }
+
+        chrono_tz::Tz::from_str(tz)
+            .map(|zone| zone.offset_from_utc_datetime(&at_utc).fix())
+            .map_err(|_| format!("Unknown time zone '{}'", tz))
+    }
+
+    fn add(dt: ScriptDateTime, duration: Option<Duration>) -> Result<ScriptDateTime, String> {
+        duration
+            .and_then(|d| dt.0.checked_add_signed(d))
+            .map(ScriptDateTime)
+            .ok_or_else(|| "Date out of range".to_string())
+    }
+
+    fn duration(duration: Option<Duration>) -> Result<ScriptDuration, String> {
+        duration.map(ScriptDuration).ok_or_else(|| "Duration out of range".to_string())
+    }
+
+    /// Format with a strftime-style format; unknown specifiers are an error instead of a panic
+    pub fn format(dt: ScriptDateTime, format: &str) -> Result<String, String> {
+        let mut out = String::new();
+        write!(out, "{}", dt.0.format(format))
+            .map_err(|_| format!("Invalid date format '{}'", format))?;
+        Ok(out)
+    }
+
+    /// Human-readable duration such as "2d 3h 4m 5s"
+    fn format_duration(duration: Duration) -> String {
+        let sign = if duration < Duration::zero() { "-" } else { "" };
+        let total = duration.num_seconds().abs();
+        let (days, hours, minutes, seconds) = (total / 86400, total % 86400 / 3600, total % 3600 / 60, total % 60);
+
+        let mut parts = Vec::new();
+        if days > 0 { parts.push(format!("{}d", days)); }
+        if hours > 0 { parts.push(format!("{}h", hours)); }
+        if minutes > 0 { parts.push(format!("{}m", minutes)); }
+        if seconds > 0 || parts.is_empty() {
+            let millis = duration.num_milliseconds().abs() % 1000;
+            if millis > 0 && days == 0 && hours == 0 {
+                parts.push(format!("{}.{:03}s", seconds, millis));
+            } else {
+                parts.push(format!("{}s", seconds));
+            }
+        }
+        format!("{}{}", sign, parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        DateTimeKit::register_functions(&mut engine);
+        engine
+    }
+
+    #[test]
+    fn test_iso_and_rfc2822_round_trip() {
+        let engine = engine();
+        let iso = engine.eval::<String>(r#"parse_date("2024-02-29T13:45:00+02:00").to_iso()"#).unwrap();
+        assert_eq!(iso, "2024-02-29T13:45:00+02:00");
+
+        let rfc = engine.eval::<String>(r#"parse_date("Thu, 29 Feb 2024 13:45:00 +0200").to_rfc2822()"#).unwrap();
+        assert_eq!(rfc, "Thu, 29 Feb 2024 13:45:00 +0200");
+    }
+
+    #[test]
+    fn test_arithmetic_and_durations() {
+        let engine = engine();
+        let result = engine.eval::<String>(r#"
+            let start = parse_date("2024-01-31 08:00", "%Y-%m-%d %H:%M", "UTC");
+            let end = start.add_days(1).add_hours(2) + minutes(30);
+            let span = end - start;
+            format_date(start.add_months(1), "%Y-%m-%d") + " " + span.total_minutes + " " + span
+        "#).unwrap();
+        assert_eq!(result, "2024-02-29 1590.0 1d 2h 30m");
+    }
+
+    #[test]
+    fn test_weekday_and_timezones() {
+        let engine = engine();
+        let result = engine.eval::<String>(r#"
+            let dt = parse_date("2024-12-30T23:30:00Z");
+            let paris = dt.to_timezone("Europe/Paris");
+            `${dt.weekday} ${dt.weekday_name} ${dt.week_of_year} ${dt.iso_week_year} ${paris.day} ${paris.offset} ${dt.to_timezone("+05:30").hour}`
+        "#).unwrap();
+        assert_eq!(result, "1 Monday 1 2025 31 +01:00 5");
+    }
+
+    #[test]
+    fn test_invalid_input_errors() {
+        let engine = engine();
+        assert!(engine.eval::<ScriptDateTime>(r#"parse_date("not a date")"#).is_err());
+        assert!(engine.eval::<ScriptDateTime>(r#"now().to_timezone("Mars/Olympus")"#).is_err());
+        assert!(engine.eval::<ScriptDateTime>("date(2023, 2, 30)").is_err());
+        assert!(engine.eval::<String>(r#"format_date(now(), "%Y %Q")"#).is_err());
+        assert!(engine.eval::<String>(r#"now().format("%")"#).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use rhai::{Engine, Array, Blob, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::Connection;
+use crate::script_manager::ScriptManager;
+
+/// Handle to an open SQLite database, shared between copies in a script
+#[derive(Clone)]
+pub struct Database {
+    connection: Arc<Mutex<Option<Connection>>>,
+    path: String,
+}
+
+/// Embedded SQLite database access for Rhai scripts
+pub struct DbKit;
+
+impl Database {
+    /// Open (or create) a database file
+    pub fn open(path: &str) -> Result<Self, String> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create database directory: {}", e))?;
+            }
+        }
+
+        let connection = Connection::open(path)
+            .map_err(|e| format!("Failed to open database '{}': {}", path, e))?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(Some(connection))),
+            path: path.to_string(),
+        })
+    }
+
+    fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+        let guard = self.connection.lock().unwrap();
+        let connection = guard.as_ref()
+            .ok_or_else(|| format!("Database '{}' is closed", self.path))?;
+        f(connection).map_err(|e| format!("SQLite error: {}", e))
+    }
+
+    /// Execute a single statement and return the number of changed rows
+    pub fn exec(&self, sql: &str, params: Dynamic) -> Result<i64, String> {
+        let params = DbKit::bind_params(params)?;
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(sql)?;
+            let changed = match &params {
+                BoundParams::Positional(values) => statement.execute(rusqlite::params_from_iter(values.iter()))?,
+                BoundParams::Named(values) => {
+                    let named: Vec<(&str, &dyn rusqlite::ToSql)> = values.iter()
+                        .map(|(name, value)| (name.as_str(), value as &dyn rusqlite::ToSql))
+                        .collect();
+                    statement.execute(named.as_slice())?
+                }
+            };
+            Ok(changed as i64)
+        })
+    }
+
+    /// Execute several `;`-separated statements without parameters
+    pub fn exec_batch(&self, sql: &str) -> Result<(), String> {
+        self.with_connection(|conn| conn.execute_batch(sql))
+    }
+
+    /// Run a query and return every row as a map of column name to value
+    pub fn query(&self, sql: &str, params: Dynamic) -> Result<Array, String> {
+        let params = DbKit::bind_params(params)?;
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(sql)?;
+            let columns: Vec<String> = statement.column_names().iter().map(|c| c.to_string()).collect();
+
+            let mut rows = match &params {
+                BoundParams::Positional(values) => statement.query(rusqlite::params_from_iter(values.iter()))?,
+                BoundParams::Named(values) => {
+                    let named: Vec<(&str, &dyn rusqlite::ToSql)> = values.iter()
+                        .map(|(name, value)| (name.as_str(), value as &dyn rusqlite::ToSql))
+                        .collect();
+                    statement.query(named.as_slice())?
+                }
+            };
+
+            let mut result = Array::new();
+            while let Some(row) = rows.next()? {
+                let mut item = Map::new();
+                for (index, column) in columns.iter().enumerate() {
+                    item.insert(column.as_str().into(), DbKit::value_to_rhai(row.get_ref(index)?));
+                }
+                result.push(Dynamic::from(item));
+            }
+            Ok(result)
+        })
+    }
+
+    /// Row ID of the most recent successful INSERT
+    pub fn last_insert_id(&self) -> Result<i64, String> {
+        self.with_connection(|conn| Ok(conn.last_insert_rowid()))
+    }
+
+    /// Run `callback(db)` inside a transaction, committing on success and rolling back on error
+    pub fn transaction(&self, context: &NativeCallContext, callback: FnPtr) -> Result<Dynamic, Box<EvalAltResult>> {
+        self.exec_batch("BEGIN")?;
+        match callback.call_within_context::<Dynamic>(context, (self.clone(),)) {
+            Ok(result) => {
+                self.exec_batch("COMMIT")?;
+                Ok(result)
+            }
+            Err(e) => {
+                if let Err(rollback_error) = self.exec_batch("ROLLBACK") {
+                    println!("❌ Failed to roll back transaction on '{}': {}", self.path, rollback_error);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Close the connection; later calls on this handle fail
+    pub fn close(&self) {
+        self.connection.lock().unwrap().take();
+    }
+}
+
+enum BoundParams {
+    Positional(Vec<Value>),
+    Named(Vec<(String, Value)>),
+}
+
+impl DbKit {
+    /// Register all database functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine, script_id: Arc<Mutex<String>>) {
+        println!("🟣 DbKit: Registering SQLite database functions");
+
+        engine.register_type_with_name::<Database>("Database");
+
+        // Explicitly opened databases
+        engine.register_fn("db_open", |path: &str| -> Result<Database, Box<EvalAltResult>> {
+            Ok(Database::open(path)?)
+        });
+        engine.register_fn("exec", |db: &mut Database, sql: &str| -> Result<i64, Box<EvalAltResult>> {
+            Ok(db.exec(sql, Dynamic::UNIT)?)
+        });
+        engine.register_fn("exec", |db: &mut Database, sql: &str, params: Dynamic| -> Result<i64, Box<EvalAltResult>> {
+            Ok(db.exec(sql, params)?)
+        });
+        engine.register_fn("exec_batch", |db: &mut Database, sql: &str| -> Result<(), Box<EvalAltResult>> {
+            Ok(db.exec_batch(sql)?)
+        });
+        engine.register_fn("query", |db: &mut Database, sql: &str| -> Result<Array, Box<EvalAltResult>> {
+            Ok(db.query(sql, Dynamic::UNIT)?)
+        });
+        engine.register_fn("query", |db: &mut Database, sql: &str, params: Dynamic| -> Result<Array, Box<EvalAltResult>> {
+            Ok(db.query(sql, params)?)
+        });
+        engine.register_fn("last_insert_id", |db: &mut Database| -> Result<i64, Box<EvalAltResult>> {
+            Ok(db.last_insert_id()?)
+        });
+        engine.register_fn("transaction", |context: NativeCallContext, db: &mut Database, callback: FnPtr| -> Result<Dynamic, Box<EvalAltResult>> {
+            db.transaction(&context, callback)
+        });
+        engine.register_fn("close", |db: &mut Database| db.close());
+        engine.register_get("path", |db: &mut Database| -> String { db.path.clone() });
+
+        // Default per-script database, opened on first use and closed when the runner is dropped
+        let defaults: Arc<Mutex<HashMap<String, Database>>> = Arc::new(Mutex::new(HashMap::new()));
+        let default_db = move || -> Result<Database, Box<EvalAltResult>> {
+            let id = script_id.lock().unwrap().clone();
+            let mut defaults = defaults.lock().unwrap();
+            if let Some(db) = defaults.get(&id) {
+                return Ok(db.clone());
+            }
+            let path = ScriptManager::get_script_data_path(&id).join("script.db");
+            let db = Database::open(&path.to_string_lossy())?;
+            defaults.insert(id, db.clone());
+            Ok(db)
+        };
+
+        {
+            let default_db = default_db.clone();
+            engine.register_fn("db_open", move || -> Result<Database, Box<EvalAltResult>> {
+                default_db()
+            });
+        }
+        {
+            let default_db = default_db.clone();
+            engine.register_fn("db_exec", move |sql: &str| -> Result<i64, Box<EvalAltResult>> {
+                Ok(default_db()?.exec(sql, Dynamic::UNIT)?)
+            });
+        }
+        {
+            let default_db = default_db.clone();
+            engine.register_fn("db_exec", move |sql: &str, params: Dynamic| -> Result<i64, Box<EvalAltResult>> {
+                Ok(default_db()?.exec(sql, params)?)
+            });
+        }
+        {
+            let default_db = default_db.clone();
+            engine.register_fn("db_exec_batch", move |sql: &str| -> Result<(), Box<EvalAltResult>> {
+                Ok(default_db()?.exec_batch(sql)?)
+            });
+        }
+        {
+            let default_db = default_db.clone();
+            engine.register_fn("db_query", move |sql: &str| -> Result<Array, Box<EvalAltResult>> {
+                Ok(default_db()?.query(sql, Dynamic::UNIT)?)
+            });
+        }
+        {
+            let default_db = default_db.clone();
+            engine.register_fn("db_query", move |sql: &str, params: Dynamic| -> Result<Array, Box<EvalAltResult>> {
+                Ok(default_db()?.query(sql, params)?)
+            });
+        }
+        {
+            let default_db = default_db.clone();
+            engine.register_fn("db_transaction", move |context: NativeCallContext, callback: FnPtr| -> Result<Dynamic, Box<EvalAltResult>> {
+                default_db()?.transaction(&context, callback)
+            });
+        }
+
+        println!("🟣 DbKit: All SQLite database functions registered");
+    }
+
+    /// Convert Rhai parameters (unit, array or map) into SQLite values
+    fn bind_params(params: Dynamic) -> Result<BoundParams, String> {
+        if params.is_unit() {
+            Ok(BoundParams::Positional(Vec::new()))
+        } else if params.is_array() {
+            let values = params.cast::<Array>()
+                .into_iter()
+                .map(Self::rhai_to_value)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(BoundParams::Positional(values))
+        } else if params.is_map() {
+            let values = params.cast::<Map>()
+                .into_iter()
+                .map(|(name, value)| {
+                    let name = if name.starts_with([':', '@', '$']) {
+                        name.to_string()
+                    } else {
+                        format!(":{}", name)
+                    };
+                    Self::rhai_to_value(value).map(|value| (name, value))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(BoundParams::Named(values))
+        } else {
+            // A single scalar is treated as one positional parameter
+            Ok(BoundParams::Positional(vec![Self::rhai_to_value(params)?]))
+        }
+    }
+
+    fn rhai_to_value(value: Dynamic) -> Result<Value, String> {
+        if value.is_unit() {
+            Ok(Value::Null)
+        } else if let Some(b) = value.clone().try_cast::<bool>() {
+            Ok(Value::Integer(b as i64))
+        } else if let Some(i) = value.clone().try_cast::<i64>() {
+            Ok(Value::Integer(i))
+        } else if let Some(f) = value.clone().try_cast::<f64>() {
+            Ok(Value::Real(f))
+        } else if let Some(s) = value.clone().try_cast::<String>() {
+            Ok(Value::Text(s))
+        } else if let Some(blob) = value.clone().try_cast::<Blob>() {
+            Ok(Value::Blob(blob))
+        } else if value.is_array() || value.is_map() {
+            Err(format!("Cannot bind {} as an SQL parameter", value.type_name()))
+        } else {
+            Ok(Value::Text(value.to_string()))
+        }
+    }
+
+    fn value_to_rhai(value: ValueRef) -> Dynamic {
+        match value {
+            ValueRef::Null => Dynamic::UNIT,
+            ValueRef::Integer(i) => Dynamic::from(i),
+            ValueRef::Real(f) => Dynamic::from(f),
+            ValueRef::Text(t) => Dynamic::from(String::from_utf8_lossy(t).to_string()),
+            ValueRef::Blob(b) => Dynamic::from_blob(b.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine_with_db() -> Engine {
+        let mut engine = Engine::new();
+        DbKit::register_functions(&mut engine, Arc::new(Mutex::new("db_kit_test".to_string())));
+        engine
+    }
+
+    #[test]
+    fn test_exec_and_query_with_params() {
+        let engine = engine_with_db();
+        let rows = engine.eval::<Array>(r#"
+            let db = db_open(":memory:");
+            db.exec("CREATE TABLE todo (id INTEGER PRIMARY KEY, title TEXT, done INTEGER)");
+            db.exec("INSERT INTO todo (title, done) VALUES (?, ?)", ["write tests", false]);
+            db.exec("INSERT INTO todo (title, done) VALUES (:title, :done)", #{ title: "ship", done: true });
+            db.query("SELECT title, done FROM todo WHERE done = ?", [1])
+        "#).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        let row = rows[0].clone().cast::<Map>();
+        assert_eq!(row["title"].clone().into_string().unwrap(), "ship");
+        assert_eq!(row["done"].as_int().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_error() {
+        let engine = engine_with_db();
+        let count = engine.eval::<i64>(r#"
+            let db = db_open(":memory:");
+            db.exec("CREATE TABLE items (name TEXT)");
+            try {
+                db.transaction(|tx| {
+                    tx.exec("INSERT INTO items VALUES ('a')");
+                    throw "abort";
+                });
+            } catch {}
+            db.transaction(|tx| tx.exec("INSERT INTO items VALUES ('b')"));
+            db.query("SELECT COUNT(*) AS n FROM items")[0].n
+        "#).unwrap();
+
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_closed_database_errors() {
+        let engine = engine_with_db();
+        let result = engine.eval::<Array>(r#"
+            let db = db_open(":memory:");
+            db.close();
+            db.query("SELECT 1")
+        "#);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+
+/// A mutating call that was recorded instead of executed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunAction {
+    pub function: String,
+    pub detail: String,
+}
+
+thread_local! {
+    // Run-scoped state lives in thread locals: each run executes synchronously on its own
+    // thread, so state set up for one run is never seen by another. The env and journal kits
+    // follow the same pattern.
+    static RECORDED: RefCell<Option<Vec<DryRunAction>>> = const { RefCell::new(None) };
+}
+
+/// Start recording mutating calls on this thread instead of executing them
+pub fn begin() {
+    RECORDED.with(|recorded| *recorded.borrow_mut() = Some(Vec::new()));
+}
+
+/// Stop the dry run and return everything that would have happened
+pub fn end() -> Vec<DryRunAction> {
+    RECORDED.with(|recorded| recorded.borrow_mut().take()).unwrap_or_default()
+}
+
+pub fn is_active() -> bool {
+    RECORDED.with(|recorded| recorded.borrow().is_some())
+}
+
+/// Record `function` if a dry run is active. Returns true when the caller must skip the real work.
+pub fn intercept(function: &str, detail: impl FnOnce() -> String) -> bool {
+    RECORDED.with(|recorded| match recorded.borrow_mut().as_mut() {
+        Some(actions) => {
+            let action = DryRunAction { function: function.to_string(), detail: detail() };
+            println!("🟡 Dry run: {} {}", action.function, action.detail);
+            actions.push(action);
+            true
+        }
+        None => false,
+    })
+}
+
+/// HTML report of the intended actions, shown by the runner when the script ends
+pub fn report_html(script_name: &str, actions: &[DryRunAction], outcome: Result<(), String>) -> String {
+    let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let status = match outcome {
+        Ok(()) => format!("<p>{} would make {} change(s). Nothing was modified.</p>", escape(script_name), actions.len()),
+        Err(e) => format!("<p>{} stopped with an error after {} recorded change(s): <code>{}</code></p>", escape(script_name), actions.len(), escape(&e)),
+    };
+    if actions.is_empty() {
+        return status;
+    }
+    let rows: String = actions.iter().enumerate().map(|(i, action)| {
+        format!("<tr><td>{}</td><td><code>{}</code></td><td>{}</td></tr>", i + 1, escape(&action.function), escape(&action.detail))
+    }).collect();
+    format!("{}<table><thead><tr><th>#</th><th>Function</th><th>Details</th></tr></thead><tbody>{}</tbody></table>", status, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rhai::Engine;
+    use crate::fs_kit::FileSystemKit;
+    use crate::process_kit::ProcessKit;
+
+    #[test]
+    fn test_dry_run_records_instead_of_executing() {
+        let dir = std::env::temp_dir().join(format!("snaprun_dry_run_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("keep.txt"), "keep").unwrap();
+
+        let mut engine = Engine::new();
+        FileSystemKit::register_functions(&mut engine);
+        ProcessKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("dir", dir.to_string_lossy().to_string());
+
+        begin();
+        let result = engine.eval_with_scope::<bool>(&mut scope, r#"
+            let ok = write_file(path_join(dir, "new.txt"), "hello") && remove_file(path_join(dir, "keep.txt"));
+            let r = exec_command("definitely-not-run");
+            ok && r.success && shell_command("touch " + path_join(dir, "touched")) == "" && file_exists(path_join(dir, "keep.txt"))
+        "#).unwrap();
+        let actions = end();
+
+        assert!(result);
+        let functions: Vec<&str> = actions.iter().map(|a| a.function.as_str()).collect();
+        assert_eq!(functions, vec!["write_file", "remove_file", "exec_command", "shell_command"]);
+        assert!(!dir.join("new.txt").exists() && !dir.join("touched").exists());
+        assert!(!is_active());
+        assert!(report_html("demo", &actions, Ok(())).contains("would make 4 change(s)"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,206 @@
+use base64::Engine as _;
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use encoding_rs::{Encoding, WINDOWS_1252};
+use rhai::{Engine, Blob, Dynamic, EvalAltResult};
+
+/// Text encodings, base64/hex and line-ending helpers for Rhai scripts
+pub struct EncodingKit;
+
+impl EncodingKit {
+    /// Register the encoding functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 EncodingKit: Registering encoding functions");
+
+        // base64 / hex (strings are encoded as UTF-8)
+        engine.register_fn("base64_encode", |text: &str| STANDARD.encode(text));
+        engine.register_fn("base64_encode", |data: Blob| STANDARD.encode(data));
+        engine.register_fn("base64_url_encode", |text: &str| URL_SAFE_NO_PAD.encode(text));
+        engine.register_fn("base64_url_encode", |data: Blob| URL_SAFE_NO_PAD.encode(data));
+        engine.register_fn("base64_decode", |text: &str| -> Result<Blob, Box<EvalAltResult>> { Ok(Self::base64_decode(text)?) });
+        engine.register_fn("hex_encode", |text: &str| Self::hex_encode(text.as_bytes()));
+        engine.register_fn("hex_encode", |data: Blob| Self::hex_encode(&data));
+        engine.register_fn("hex_decode", |text: &str| -> Result<Blob, Box<EvalAltResult>> { Ok(Self::hex_decode(text)?) });
+
+        // Text <-> bytes in a named encoding ("utf-8", "utf-16le", "utf-16be", "latin1", "windows-1252", "shift_jis", ...)
+        engine.register_fn("decode_text", |data: Blob, encoding: &str| -> Result<String, Box<EvalAltResult>> { Ok(Self::decode(&data, encoding)?) });
+        engine.register_fn("encode_text", |text: &str, encoding: &str| -> Result<Blob, Box<EvalAltResult>> { Ok(Self::encode(text, encoding, false)?) });
+        engine.register_fn("encode_text", |text: &str, encoding: &str, bom: bool| -> Result<Blob, Box<EvalAltResult>> { Ok(Self::encode(text, encoding, bom)?) });
+        engine.register_fn("detect_bom", |data: Blob| -> Dynamic {
+            Self::detect_bom(&data).map(|(name, _)| Dynamic::from(name.to_string())).unwrap_or(Dynamic::UNIT)
+        });
+
+        // Line endings
+        engine.register_fn("normalize_line_endings", |text: &str| Self::normalize_line_endings(text, "lf"));
+        engine.register_fn("normalize_line_endings", |text: &str, style: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::normalize_line_endings(text, style)?)
+        });
+        engine.register_fn("detect_line_ending", Self::detect_line_ending);
+
+        println!("🟣 EncodingKit: All encoding functions registered");
+    }
+
+    /// Accepts standard and URL-safe alphabets, with or without padding; whitespace is ignored
+    pub fn base64_decode(text: &str) -> Result<Blob, String> {
+        let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+        [STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD]
+            .iter()
+            .find_map(|engine| engine.decode(&compact).ok())
+            .ok_or_else(|| "Invalid base64 input".to_string())
+    }
+
+    pub fn hex_encode(data: &[u8]) -> String {
+        data.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Whitespace between digits is ignored
+    pub fn hex_decode(text: &str) -> Result<Blob, String> {
+        let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err("Invalid hex input: odd number of digits".to_string());
+        }
+        digits.chunks(2).map(|pair| {
+            let pair = std::str::from_utf8(pair).map_err(|_| "Invalid hex input".to_string())?;
+            u8::from_str_radix(pair, 16).map_err(|_| format!("Invalid hex digits '{}'", pair))
+        }).collect()
+    }
+
+    /// Encoding named by a byte-order mark, and the BOM length
+    pub fn detect_bom(data: &[u8]) -> Option<(&'static str, usize)> {
+        Encoding::for_bom(data).map(|(encoding, length)| (Self::canonical_name(encoding), length))
+    }
+
+    fn canonical_name(encoding: &'static Encoding) -> &'static str {
+        match encoding.name() {
+            "UTF-8" => "utf-8",
+            "UTF-16LE" => "utf-16le",
+            "UTF-16BE" => "utf-16be",
+            other => other,
+        }
+    }
+
+    fn is_latin1(encoding: &str) -> bool {
+        matches!(encoding.to_ascii_lowercase().as_str(), "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1")
+    }
+
+    fn lookup(encoding: &str) -> Result<&'static Encoding, String> {
+        Encoding::for_label(encoding.trim().as_bytes()).ok_or_else(|| format!("Unknown encoding '{}'", encoding))
+    }
+
+    /// Decode bytes; a byte-order mark overrides `encoding`. "auto" tries UTF-8, then Windows-1252.
+    /// Invalid sequences become U+FFFD.
+    pub fn decode(data: &[u8], encoding: &str) -> Result<String, String> {
+        if let Some((found, length)) = Encoding::for_bom(data) {
+            return Ok(found.decode_without_bom_handling(&data[length..]).0.into_owned());
+        }
+        if encoding.eq_ignore_ascii_case("auto") {
+            return Ok(match std::str::from_utf8(data) {
+                Ok(text) => text.to_string(),
+                Err(_) => WINDOWS_1252.decode_without_bom_handling(data).0.into_owned(),
+            });
+        }
+        // encoding_rs treats "latin1" as Windows-1252, which differs in 0x80-0x9F
+        if Self::is_latin1(encoding) {
+            return Ok(data.iter().map(|&byte| byte as char).collect());
+        }
+        Ok(Self::lookup(encoding)?.decode_without_bom_handling(data).0.into_owned())
+    }
+
+    /// Encode text, optionally starting with a byte-order mark (UTF-8 and UTF-16 only).
+    /// Fails if a character has no representation in the target encoding.
+    pub fn encode(text: &str, encoding: &str, bom: bool) -> Result<Blob, String> {
+        if Self::is_latin1(encoding) {
+            return text.chars().map(|c| u8::try_from(c as u32).map_err(|_| format!("Character '{}' cannot be encoded as latin1", c))).collect();
+        }
+        let target = Self::lookup(encoding)?;
+        let mut bytes = Vec::new();
+        if target == encoding_rs::UTF_16LE || target == encoding_rs::UTF_16BE {
+            // encoding_rs only decodes UTF-16
+            let little_endian = target == encoding_rs::UTF_16LE;
+            if bom {
+                bytes.extend_from_slice(if little_endian { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] });
+            }
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() });
+            }
+            return Ok(bytes);
+        }
+        if bom && target == encoding_rs::UTF_8 {
+            bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+        }
+        let (encoded, used, had_errors) = target.encode(text);
+        if had_errors || used != target {
+            return Err(format!("Text contains characters that cannot be encoded as {}", target.name()));
+        }
+        bytes.extend_from_slice(&encoded);
+        Ok(bytes)
+    }
+
+    /// Convert every CRLF, CR and LF to one style: "lf", "crlf", "cr" or "native"
+    pub fn normalize_line_endings(text: &str, style: &str) -> Result<String, String> {
+        let ending = match style.to_ascii_lowercase().as_str() {
+            "lf" | "unix" => "\n",
+            "crlf" | "windows" => "\r\n",
+            "cr" => "\r",
+            "native" => if cfg!(windows) { "\r\n" } else { "\n" },
+            other => return Err(format!("Unknown line ending style '{}' (use \"lf\", \"crlf\", \"cr\" or \"native\")", other)),
+        };
+        let unified = text.replace("\r\n", "\n").replace('\r', "\n");
+        Ok(if ending == "\n" { unified } else { unified.replace('\n', ending) })
+    }
+
+    /// "lf", "crlf", "cr", "mixed", or "none" when the text has no line breaks
+    pub fn detect_line_ending(text: &str) -> String {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+        let cr = text.matches('\r').count() - crlf;
+        match (lf > 0, crlf > 0, cr > 0) {
+            (false, false, false) => "none",
+            (true, false, false) => "lf",
+            (false, true, false) => "crlf",
+            (false, false, true) => "cr",
+            _ => "mixed",
+        }.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_encodings_and_bom() {
+        let utf16 = EncodingKit::encode("héllo €", "utf-16le", true).unwrap();
+        assert_eq!(&utf16[..4], &[0xFF, 0xFE, b'h', 0x00]);
+        assert_eq!(EncodingKit::detect_bom(&utf16), Some(("utf-16le", 2)));
+        // The BOM wins over the requested encoding
+        assert_eq!(EncodingKit::decode(&utf16, "latin1").unwrap(), "héllo €");
+
+        let latin1 = EncodingKit::encode("café", "latin1", false).unwrap();
+        assert_eq!(latin1, vec![b'c', b'a', b'f', 0xE9]);
+        assert_eq!(EncodingKit::decode(&[0x80], "latin1").unwrap(), "\u{80}");
+        assert_eq!(EncodingKit::decode(&[0x80], "windows-1252").unwrap(), "€");
+        assert_eq!(EncodingKit::decode(&latin1, "auto").unwrap(), "café");
+        assert!(EncodingKit::encode("€", "latin1", false).is_err());
+        assert!(EncodingKit::encode("日本", "windows-1252", false).is_err());
+        assert!(EncodingKit::decode(b"x", "klingon").is_err());
+    }
+
+    #[test]
+    fn test_base64_hex_and_line_endings() {
+        let mut engine = Engine::new();
+        EncodingKit::register_functions(&mut engine);
+        let result = engine.eval::<String>(r#"
+            let b = base64_encode("hi?>");
+            let u = base64_url_encode("hi?>");
+            let h = hex_encode(base64_decode(u));
+            b + "|" + u + "|" + h + "|" + hex_decode("68 69").len() + "|" + detect_line_ending("a\r\nb\n")
+        "#).unwrap();
+        assert_eq!(result, "aGk/Pg==|aGk_Pg|68693f3e|2|mixed");
+        assert!(engine.eval::<Blob>(r#"hex_decode("abc")"#).is_err());
+        assert!(engine.eval::<Blob>(r#"base64_decode("***")"#).is_err());
+
+        assert_eq!(EncodingKit::normalize_line_endings("a\r\nb\rc\n", "crlf").unwrap(), "a\r\nb\r\nc\r\n");
+        assert_eq!(EncodingKit::normalize_line_endings("a\r\nb\rc", "lf").unwrap(), "a\nb\nc");
+        assert!(EncodingKit::normalize_line_endings("a", "mac").is_err());
+    }
+}
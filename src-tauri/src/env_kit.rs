@@ -0,0 +1,304 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use rhai::{Engine, Dynamic, EvalAltResult, Map};
+
+thread_local! {
+    // Variables set or removed for the current run, layered over the process environment.
+    // `None` means the variable was unset for this run. Never written to the real process
+    // environment, which every thread shares.
+    static RUN_ENV: RefCell<BTreeMap<String, Option<String>>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Environment variables for Rhai scripts. Changes last for one run and are passed to child processes.
+pub struct EnvKit;
+
+impl EnvKit {
+    /// Register the environment functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 EnvKit: Registering environment functions");
+
+        engine.register_fn("env_get", |name: &str| -> Dynamic {
+            get(name).map(Dynamic::from).unwrap_or(Dynamic::UNIT)
+        });
+        engine.register_fn("env_get", |name: &str, default: &str| -> String {
+            get(name).unwrap_or_else(|| default.to_string())
+        });
+        engine.register_fn("env_set", |name: &str, value: &str| -> Result<(), Box<EvalAltResult>> {
+            Ok(set(name, value)?)
+        });
+        engine.register_fn("env_unset", |name: &str| -> Result<(), Box<EvalAltResult>> {
+            Ok(unset(name)?)
+        });
+        engine.register_fn("env_all", || -> Map {
+            all().into_iter().map(|(name, value)| (name.into(), Dynamic::from(value))).collect()
+        });
+        engine.register_fn("load_env_file", |path: &str| -> Result<i64, Box<EvalAltResult>> {
+            Ok(load_file(Path::new(path))? as i64)
+        });
+
+        println!("🟣 EnvKit: All environment functions registered");
+    }
+}
+
+/// Start a run: apply `overrides` (the `env` settings), then the `.env` file next to the script
+pub fn begin(script_dir: Option<&Path>, overrides: BTreeMap<String, String>) {
+    RUN_ENV.with(|env| {
+        let mut env = env.borrow_mut();
+        env.clear();
+        env.extend(overrides.into_iter().map(|(name, value)| (name, Some(value))));
+    });
+    if let Some(dotenv) = script_dir.map(|dir| dir.join(".env")).filter(|path| path.is_file()) {
+        match load_file(&dotenv) {
+            Ok(count) => println!("🟣 EnvKit: Loaded {} variable(s) from {}", count, dotenv.display()),
+            Err(e) => println!("⚠️ EnvKit: {}", e),
+        }
+    }
+}
+
+/// Drop everything the run set
+pub fn end() {
+    RUN_ENV.with(|env| env.borrow_mut().clear());
+}
+
+/// The run's value if it set or unset `name`, otherwise the process environment
+pub fn get(name: &str) -> Option<String> {
+    RUN_ENV.with(|env| env.borrow().get(name).cloned())
+        .unwrap_or_else(|| std::env::var(name).ok())
+}
+
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    validate_name(name)?;
+    if value.contains('\0') {
+        return Err(format!("Value of '{}' contains a NUL character", name));
+    }
+    RUN_ENV.with(|env| env.borrow_mut().insert(name.to_string(), Some(value.to_string())));
+    Ok(())
+}
+
+pub fn unset(name: &str) -> Result<(), String> {
+    validate_name(name)?;
+    RUN_ENV.with(|env| env.borrow_mut().insert(name.to_string(), None));
+    Ok(())
+}
+
+/// The effective environment of the run
+pub fn all() -> BTreeMap<String, String> {
+    let mut vars: BTreeMap<String, String> = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .collect();
+    RUN_ENV.with(|env| {
+        for (name, value) in env.borrow().iter() {
+            match value {
+                Some(value) => vars.insert(name.clone(), value.clone()),
+                None => vars.remove(name),
+            };
+        }
+    });
+    vars
+}
+
+/// Pass the run's changes on to a child process
+pub fn apply(cmd: &mut Command) {
+    RUN_ENV.with(|env| {
+        for (name, value) in env.borrow().iter() {
+            match value {
+                Some(value) => cmd.env(name, value),
+                None => cmd.env_remove(name),
+            };
+        }
+    });
+}
+
+/// Set every variable from a dotenv file for this run; returns how many were set
+pub fn load_file(path: &Path) -> Result<usize, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let vars = parse_dotenv(&text, get).map_err(|e| format!("{}: {}", path.display(), e))?;
+    for (name, value) in &vars {
+        set(name, value)?;
+    }
+    Ok(vars.len())
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains('=') || name.contains('\0') {
+        return Err(format!("Invalid environment variable name '{}'", name));
+    }
+    Ok(())
+}
+
+/// Parse `KEY=value` lines. Supports `export`, `#` comments, single quotes (literal),
+/// double quotes (escapes, multi-line) and `${VAR}` / `$VAR` expansion outside single quotes.
+/// `lookup` resolves variables not defined earlier in the same file.
+pub fn parse_dotenv(text: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<Vec<(String, String)>, String> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let resolve = |name: &str, vars: &[(String, String)]| {
+        vars.iter().rev().find(|(key, _)| key == name).map(|(_, value)| value.clone())
+            .or_else(|| lookup(name))
+            .unwrap_or_default()
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut index = 0;
+    while index < lines.len() {
+        let line_no = index + 1;
+        let line = lines[index].trim();
+        index += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map(str::trim_start).unwrap_or(line);
+        let (name, raw) = line.split_once('=').ok_or_else(|| format!("line {}: expected KEY=value", line_no))?;
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+            return Err(format!("line {}: invalid variable name '{}'", line_no, name));
+        }
+        let raw = raw.trim_start();
+
+        let value = if let Some(rest) = raw.strip_prefix('\'') {
+            let end = rest.find('\'').ok_or_else(|| format!("line {}: unterminated single quote", line_no))?;
+            rest[..end].to_string()
+        } else if let Some(rest) = raw.strip_prefix('"') {
+            // Double-quoted values may span lines
+            let mut body = rest.to_string();
+            let end = loop {
+                if let Some(end) = closing_quote(&body) {
+                    break end;
+                }
+                let next = lines.get(index).ok_or_else(|| format!("line {}: unterminated double quote", line_no))?;
+                body.push('\n');
+                body.push_str(next);
+                index += 1;
+            };
+            expand(&unescape(&body[..end]), |var| resolve(var, &vars))
+        } else {
+            // Unquoted: an inline comment starts at " #"
+            let value = raw.find(" #").map(|at| &raw[..at]).unwrap_or(raw).trim_end();
+            expand(value, |var| resolve(var, &vars))
+        };
+        vars.push((name.to_string(), value));
+    }
+    Ok(vars)
+}
+
+fn closing_quote(body: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (at, c) in body.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(at),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            // Keep "\$" escaped until expansion has run
+            Some('$') => out.push_str("\\$"),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn expand(text: &str, resolve: impl Fn(&str) -> String) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(['$', '\\']) {
+        out.push_str(&rest[..at]);
+        let tail = &rest[at..];
+        if let Some(after) = tail.strip_prefix("\\$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(braced) = tail.strip_prefix("${") {
+            match braced.find('}') {
+                Some(end) => {
+                    out.push_str(&resolve(&braced[..end]));
+                    rest = &braced[end + 1..];
+                }
+                None => {
+                    out.push_str(tail);
+                    rest = "";
+                }
+            }
+        } else if let Some(after) = tail.strip_prefix('$') {
+            let name_len = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+            if name_len == 0 {
+                out.push('$');
+            } else {
+                out.push_str(&resolve(&after[..name_len]));
+            }
+            rest = &after[name_len..];
+        } else {
+            out.push('\\');
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dotenv() {
+        let text = "# comment\nexport NAME=snap  # trailing\nGREETING=\"hi ${NAME}\\n\"\nRAW='${NAME} $HOME'\nMULTI=\"a\nb\"\nPRICE=\\$5 $MISSING.\nEMPTY=\n";
+        let vars = parse_dotenv(text, |name| (name == "HOME").then(|| "/home/me".to_string())).unwrap();
+        let map: BTreeMap<String, String> = vars.into_iter().collect();
+        assert_eq!(map["NAME"], "snap");
+        assert_eq!(map["GREETING"], "hi snap\n");
+        assert_eq!(map["RAW"], "${NAME} $HOME");
+        assert_eq!(map["MULTI"], "a\nb");
+        assert_eq!(map["PRICE"], "$5 .");
+        assert_eq!(map["EMPTY"], "");
+        assert!(parse_dotenv("JUST_A_WORD\n", |_| None).unwrap_err().contains("line 1"));
+        assert!(parse_dotenv("A=\"open\n", |_| None).is_err());
+    }
+
+    #[test]
+    fn test_run_scoped_env_reaches_children() {
+        let dir = std::env::temp_dir().join(format!("snaprun_env_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".env"), "SNAPRUN_TEST_FROM_DOTENV=dotenv\n").unwrap();
+
+        let mut engine = Engine::new();
+        EnvKit::register_functions(&mut engine);
+        begin(Some(&dir), BTreeMap::new());
+        let result = engine.eval::<String>(r#"
+            env_set("SNAPRUN_TEST_SET", "run value");
+            env_unset("PATH_THAT_DOES_NOT_EXIST");
+            env_get("SNAPRUN_TEST_FROM_DOTENV") + "|" + env_get("SNAPRUN_TEST_SET") + "|" + env_get("SNAPRUN_NOPE", "fallback") + "|" + env_all().contains("SNAPRUN_TEST_SET")
+        "#).unwrap();
+        assert_eq!(result, "dotenv|run value|fallback|true");
+
+        #[cfg(unix)]
+        {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", "printf %s \"$SNAPRUN_TEST_SET\""]);
+            apply(&mut cmd);
+            assert_eq!(String::from_utf8(cmd.output().unwrap().stdout).unwrap(), "run value");
+        }
+
+        end();
+        assert!(get("SNAPRUN_TEST_SET").is_none());
+        assert!(std::env::var("SNAPRUN_TEST_SET").is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
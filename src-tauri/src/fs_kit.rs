@@ -1,8 +1,12 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fs;
-use std::io::Write;
-use rhai::{Engine, Array, Map, Dynamic};
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use rhai::{Engine, Array, Blob, Map, Dynamic, EvalAltResult, FnPtr, NativeCallContext};
 use dirs;
+use crate::{dry_run, journal, trash};
+use crate::encoding_kit::EncodingKit;
 
 /// File system operations for Rhai scripts
 pub struct FileSystemKit;
@@ -17,13 +21,27 @@ impl FileSystemKit {
         engine.register_fn("file_exists", Self::file_exists);
 
         // File writing functions
-        engine.register_fn("write_file", Self::write_file);
+        engine.register_fn("write_file", |path: &str, content: &str| Self::write_file(path, content));
+        engine.register_fn("write_file", Self::write_file_with_options);
         engine.register_fn("append_file", Self::append_file);
 
+        // Binary files and other text encodings (a byte-order mark overrides the given encoding)
+        engine.register_fn("read_bytes", Self::read_bytes);
+        engine.register_fn("write_bytes", Self::write_bytes);
+        engine.register_fn("read_file_encoding", |path: &str| Self::read_file_encoding(path, "auto"));
+        engine.register_fn("read_file_encoding", Self::read_file_encoding);
+        engine.register_fn("write_file_encoding", |path: &str, content: &str, encoding: &str| {
+            // Windows tools expect UTF-16 files to start with a BOM
+            let bom = encoding.to_ascii_lowercase().starts_with("utf-16");
+            Self::write_file_encoding(path, content, encoding, bom)
+        });
+        engine.register_fn("write_file_encoding", Self::write_file_encoding);
+
         // File operations
         engine.register_fn("copy_file", Self::copy_file);
         engine.register_fn("move_file", Self::move_file);
         engine.register_fn("remove_file", Self::remove_file);
+        engine.register_fn("trash", Self::trash);
         engine.register_fn("file_size", Self::file_size);
 
         // Directory operations
@@ -34,6 +52,53 @@ impl FileSystemKit {
         engine.register_fn("list_dir", Self::list_dir);
         engine.register_fn("dir_exists", Self::dir_exists);
 
+        // Metadata, permissions and links
+        engine.register_fn("file_info", Self::file_info);
+        engine.register_fn("set_modified_time", Self::set_modified_time);
+        engine.register_fn("set_readonly", Self::set_readonly);
+        engine.register_fn("chmod", |path: &str, mode: i64| Self::chmod(path, mode));
+        engine.register_fn("chmod", |path: &str, mode: &str| match i64::from_str_radix(mode, 8) {
+            Ok(mode) => Self::chmod(path, mode),
+            Err(_) => {
+                println!("❌ Invalid mode '{}': expected an octal string like \"755\"", mode);
+                false
+            }
+        });
+        engine.register_fn("create_symlink", Self::create_symlink);
+        engine.register_fn("read_link", Self::read_link);
+
+        // Advisory locks shared by every script and process using the same path
+        engine.register_type_with_name::<FileLock>("FileLock");
+        engine.register_get("path", |lock: &mut FileLock| lock.path.to_string_lossy().to_string());
+        engine.register_get("locked", |lock: &mut FileLock| lock.file.lock().unwrap().is_some());
+        engine.register_fn("unlock", |lock: &mut FileLock| lock.unlock());
+        engine.register_fn("to_string", |lock: &mut FileLock| lock.to_string());
+        engine.register_fn("to_debug", |lock: &mut FileLock| lock.to_string());
+        engine.register_fn("lock_file", |path: &str| -> Result<FileLock, Box<EvalAltResult>> {
+            Ok(FileLock::acquire(Path::new(path), None)?)
+        });
+        engine.register_fn("lock_file", |path: &str, timeout_ms: i64| -> Result<FileLock, Box<EvalAltResult>> {
+            Ok(FileLock::acquire(Path::new(path), Some(Duration::from_millis(timeout_ms.max(0) as u64)))?)
+        });
+        engine.register_fn("with_lock", |context: NativeCallContext, path: &str, callback: FnPtr| {
+            Self::with_lock(&context, path, None, callback)
+        });
+        engine.register_fn("with_lock", |context: NativeCallContext, path: &str, timeout_ms: i64, callback: FnPtr| {
+            Self::with_lock(&context, path, Some(Duration::from_millis(timeout_ms.max(0) as u64)), callback)
+        });
+
+        // Recursive listing and search (results stream lazily in `for` loops)
+        engine.register_type_with_name::<FileWalk>("FileWalk");
+        engine.register_iterator::<FileWalk>();
+        engine.register_fn("to_array", |walk: &mut FileWalk| walk.clone().into_iter().collect::<Array>());
+        engine.register_fn("to_string", |walk: &mut FileWalk| walk.to_string());
+        engine.register_fn("to_debug", |walk: &mut FileWalk| walk.to_string());
+        engine.register_fn("glob", Self::glob);
+        engine.register_fn("walk_dir", |path: &str| Self::walk_dir(path, Map::new()));
+        engine.register_fn("walk_dir", Self::walk_dir);
+        engine.register_fn("find_files", |root: &str| Self::find_files(root, Map::new()));
+        engine.register_fn("find_files", Self::find_files);
+
         // Path helpers
         engine.register_fn("path_join", Self::path_join);
         engine.register_fn("path_parent", Self::path_parent);
@@ -65,13 +130,38 @@ impl FileSystemKit {
         }
     }
 
+    /// Run `callback` while holding the lock for `path`; the lock is released even if it throws
+    fn with_lock(context: &NativeCallContext, path: &str, timeout: Option<Duration>, callback: FnPtr) -> Result<Dynamic, Box<EvalAltResult>> {
+        let lock = FileLock::acquire(Path::new(path), timeout)?;
+        let result = callback.call_within_context::<Dynamic>(context, ());
+        lock.unlock();
+        result
+    }
+
     fn file_exists(path: &str) -> bool {
         Path::new(path).exists() && Path::new(path).is_file()
     }
 
     // File writing functions
     fn write_file(path: &str, content: &str) -> bool {
-        match fs::write(path, content) {
+        Self::write_file_synced(path, content, false)
+    }
+
+    fn read_bytes(path: &str) -> Blob {
+        match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                println!("❌ Failed to read file '{}': {}", path, e);
+                Blob::new()
+            }
+        }
+    }
+
+    fn write_bytes(path: &str, data: Blob) -> bool {
+        if dry_run::intercept("write_bytes", || format!("{} ({} bytes)", path, data.len())) {
+            return true;
+        }
+        match journal::write(Path::new(path), || Self::write_atomic(Path::new(path), &data, false)) {
             Ok(_) => true,
             Err(e) => {
                 println!("❌ Failed to write file '{}': {}", path, e);
@@ -80,24 +170,124 @@ impl FileSystemKit {
         }
     }
 
-    fn append_file(path: &str, content: &str) -> bool {
-        use std::fs::OpenOptions;
-        
-        let mut file = match OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(path) {
-            Ok(file) => file,
+    /// Read text in the given encoding ("auto" tries UTF-8, then Windows-1252)
+    fn read_file_encoding(path: &str, encoding: &str) -> Result<String, Box<EvalAltResult>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
             Err(e) => {
-                println!("❌ Failed to open file for appending '{}': {}", path, e);
-                return false;
+                println!("❌ Failed to read file '{}': {}", path, e);
+                return Ok(String::new());
             }
         };
+        Ok(EncodingKit::decode(&bytes, encoding)?)
+    }
+
+    fn write_file_encoding(path: &str, content: &str, encoding: &str, bom: bool) -> Result<bool, Box<EvalAltResult>> {
+        let bytes = EncodingKit::encode(content, encoding, bom)?;
+        if dry_run::intercept("write_file_encoding", || format!("{} ({}, {} bytes)", path, encoding, bytes.len())) {
+            return Ok(true);
+        }
+        match journal::write(Path::new(path), || Self::write_atomic(Path::new(path), &bytes, false)) {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                println!("❌ Failed to write file '{}': {}", path, e);
+                Ok(false)
+            }
+        }
+    }
 
-        match file.write_all(content.as_bytes()) {
+    /// `write_file` with options: `fsync` flushes the new content to disk before returning
+    fn write_file_with_options(path: &str, content: &str, options: Map) -> bool {
+        let mut fsync = false;
+        for (key, value) in options {
+            match (key.as_str(), value.as_bool()) {
+                ("fsync", Ok(flag)) => fsync = flag,
+                ("fsync", Err(_)) => {
+                    println!("❌ write_file option 'fsync' must be true or false");
+                    return false;
+                }
+                (other, _) => {
+                    println!("❌ Unknown write_file option '{}'", other);
+                    return false;
+                }
+            }
+        }
+        Self::write_file_synced(path, content, fsync)
+    }
+
+    fn write_file_synced(path: &str, content: &str, fsync: bool) -> bool {
+        if dry_run::intercept("write_file", || format!("{} ({} bytes)", path, content.len())) {
+            return true;
+        }
+        match journal::write(Path::new(path), || Self::write_atomic(Path::new(path), content.as_bytes(), fsync)) {
             Ok(_) => true,
             Err(e) => {
-                println!("❌ Failed to append to file '{}': {}", path, e);
+                println!("❌ Failed to write file '{}': {}", path, e);
+                false
+            }
+        }
+    }
+
+    /// Write through a temporary file in the same folder and rename it into place,
+    /// so readers never see a half-written file. `fsync` also flushes data and folder to disk.
+    pub(crate) fn write_atomic(path: &Path, data: &[u8], fsync: bool) -> io::Result<()> {
+        // Write through symlinks instead of replacing them
+        let path = match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => fs::canonicalize(path)?,
+            _ => path.to_path_buf(),
+        };
+        let name = path.file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?
+            .to_string_lossy()
+            .to_string();
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let temp_path = dir.join(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()));
+
+        let result = fs::File::create(&temp_path).and_then(|mut file| {
+            file.write_all(data)?;
+            // Keep the permissions of the file being replaced
+            if let Ok(metadata) = fs::metadata(&path) {
+                file.set_permissions(metadata.permissions())?;
+            }
+            if fsync {
+                file.sync_all()?;
+            }
+            drop(file);
+            fs::rename(&temp_path, &path)?;
+            #[cfg(unix)]
+            if fsync {
+                fs::File::open(&dir)?.sync_all()?;
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    fn append_file(path: &str, content: &str) -> bool {
+        if dry_run::intercept("append_file", || format!("{} ({} bytes)", path, content.len())) {
+            return true;
+        }
+        use std::fs::OpenOptions;
+        
+        let appended = journal::write(Path::new(path), || {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("Failed to open file for appending '{}': {}", path, e))?;
+            file.write_all(content.as_bytes()).map_err(|e| format!("Failed to append to file '{}': {}", path, e))
+        });
+
+        match appended {
+            Ok(_) => true,
+            Err(e) => {
+                println!("❌ {}", e);
                 false
             }
         }
@@ -105,7 +295,10 @@ impl FileSystemKit {
 
     // File operations
     fn copy_file(src: &str, dst: &str) -> bool {
-        match fs::copy(src, dst) {
+        if dry_run::intercept("copy_file", || format!("{} → {}", src, dst)) {
+            return true;
+        }
+        match journal::write(Path::new(dst), || fs::copy(src, dst)) {
             Ok(_) => true,
             Err(e) => {
                 println!("❌ Failed to copy file from '{}' to '{}': {}", src, dst, e);
@@ -115,7 +308,10 @@ impl FileSystemKit {
     }
 
     fn move_file(src: &str, dst: &str) -> bool {
-        match fs::rename(src, dst) {
+        if dry_run::intercept("move_file", || format!("{} → {}", src, dst)) {
+            return true;
+        }
+        match journal::move_file(Path::new(src), Path::new(dst), || fs::rename(src, dst)) {
             Ok(_) => true,
             Err(e) => {
                 println!("❌ Failed to move file from '{}' to '{}': {}", src, dst, e);
@@ -125,7 +321,12 @@ impl FileSystemKit {
     }
 
     fn remove_file(path: &str) -> bool {
-        match fs::remove_file(path) {
+        if dry_run::intercept("remove_file", || path.to_string()) {
+            return true;
+        }
+        // With the journal on, the file is kept for undo_run instead of deleted
+        let result = journal::remove(Path::new(path), false).unwrap_or_else(|| fs::remove_file(path));
+        match result {
             Ok(_) => true,
             Err(e) => {
                 println!("❌ Failed to remove file '{}': {}", path, e);
@@ -134,6 +335,23 @@ impl FileSystemKit {
         }
     }
 
+    fn trash(path: &str) -> bool {
+        if dry_run::intercept("trash", || path.to_string()) {
+            return true;
+        }
+        let path = Self::expand_home(path);
+        match trash::move_to_trash(Path::new(&path)) {
+            Ok(trashed) => {
+                journal::trashed(Path::new(&path), &trashed);
+                true
+            },
+            Err(e) => {
+                println!("❌ Failed to move '{}' to the trash: {}", path, e);
+                false
+            }
+        }
+    }
+
     fn file_size(path: &str) -> i64 {
         match fs::metadata(path) {
             Ok(metadata) => metadata.len() as i64,
@@ -146,6 +364,9 @@ impl FileSystemKit {
 
     // Directory operations
     fn create_dir(path: &str) -> bool {
+        if dry_run::intercept("create_dir", || path.to_string()) {
+            return true;
+        }
         match fs::create_dir(path) {
             Ok(_) => true,
             Err(e) => {
@@ -156,6 +377,9 @@ impl FileSystemKit {
     }
 
     fn create_dir_all(path: &str) -> bool {
+        if dry_run::intercept("create_dir_all", || path.to_string()) {
+            return true;
+        }
         match fs::create_dir_all(path) {
             Ok(_) => true,
             Err(e) => {
@@ -166,8 +390,14 @@ impl FileSystemKit {
     }
 
     fn remove_dir(path: &str) -> bool {
+        if dry_run::intercept("remove_dir", || path.to_string()) {
+            return true;
+        }
         match fs::remove_dir(path) {
-            Ok(_) => true,
+            Ok(_) => {
+                journal::removed_empty_dir(Path::new(path));
+                true
+            },
             Err(e) => {
                 println!("❌ Failed to remove directory '{}': {}", path, e);
                 false
@@ -176,7 +406,11 @@ impl FileSystemKit {
     }
 
     fn remove_dir_all(path: &str) -> bool {
-        match fs::remove_dir_all(path) {
+        if dry_run::intercept("remove_dir_all", || path.to_string()) {
+            return true;
+        }
+        let result = journal::remove(Path::new(path), true).unwrap_or_else(|| fs::remove_dir_all(path));
+        match result {
             Ok(_) => true,
             Err(e) => {
                 println!("❌ Failed to remove directory tree '{}': {}", path, e);
@@ -205,34 +439,7 @@ impl FileSystemKit {
                 }
             };
             
-            let path = entry.path();
-            let mut item_map = Map::new();
-            
-            item_map.insert("name".into(), Dynamic::from(
-                path.file_name()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("")
-                    .to_string()
-            ));
-            
-            item_map.insert("path".into(), Dynamic::from(
-                path.to_string_lossy().to_string()
-            ));
-            
-            item_map.insert("is_dir".into(), Dynamic::from(path.is_dir()));
-            item_map.insert("is_file".into(), Dynamic::from(path.is_file()));
-            
-            // Get file size if it's a file
-            if path.is_file() {
-                if let Ok(metadata) = fs::metadata(&path) {
-                    item_map.insert("size".into(), Dynamic::from(metadata.len() as i64));
-                } else {
-                    item_map.insert("size".into(), Dynamic::from(0i64));
-                }
-            } else {
-                item_map.insert("size".into(), Dynamic::from(0i64));
-            }
-            
+            let item_map = Self::entry_map(&entry.path());
             result.push(Dynamic::from(item_map));
         }
 
@@ -243,6 +450,272 @@ impl FileSystemKit {
         Path::new(path).exists() && Path::new(path).is_dir()
     }
 
+    /// Entry map shared by list_dir and the recursive functions
+    fn entry_map(path: &Path) -> Map {
+        let mut item_map = Map::new();
+        let metadata = fs::metadata(path).ok();
+        let is_file = metadata.as_ref().is_some_and(|m| m.is_file());
+
+        item_map.insert("name".into(), Dynamic::from(
+            path.file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string()
+        ));
+        item_map.insert("path".into(), Dynamic::from(path.to_string_lossy().to_string()));
+        item_map.insert("is_dir".into(), Dynamic::from(metadata.as_ref().is_some_and(|m| m.is_dir())));
+        item_map.insert("is_file".into(), Dynamic::from(is_file));
+        item_map.insert("is_symlink".into(), Dynamic::from(path.is_symlink()));
+        item_map.insert("size".into(), Dynamic::from(
+            metadata.as_ref().filter(|_| is_file).map(|m| m.len() as i64).unwrap_or(0)
+        ));
+
+        // Timestamps are Unix seconds (same unit as timestamp()); () when the platform doesn't provide them
+        let seconds = |time: std::io::Result<SystemTime>| time.ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| Dynamic::from(d.as_secs() as i64))
+            .unwrap_or(Dynamic::UNIT);
+        item_map.insert("modified".into(), metadata.as_ref().map(|m| seconds(m.modified())).unwrap_or(Dynamic::UNIT));
+        item_map.insert("created".into(), metadata.as_ref().map(|m| seconds(m.created())).unwrap_or(Dynamic::UNIT));
+
+        let readonly = metadata.as_ref().is_some_and(|m| m.permissions().readonly());
+        item_map.insert("readonly".into(), Dynamic::from(readonly));
+        item_map.insert("permissions".into(), Dynamic::from(Self::permissions_string(metadata.as_ref(), readonly)));
+        item_map
+    }
+
+    /// `rwxr-xr-x` style permissions on Unix; `r--` / `rw-` elsewhere
+    #[cfg(unix)]
+    fn permissions_string(metadata: Option<&fs::Metadata>, _readonly: bool) -> String {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.map(|m| m.permissions().mode()).unwrap_or(0);
+        (0..9).rev()
+            .map(|bit| if mode & (1 << bit) == 0 { '-' } else { ['x', 'w', 'r'][bit % 3] })
+            .collect()
+    }
+
+    #[cfg(not(unix))]
+    fn permissions_string(_metadata: Option<&fs::Metadata>, readonly: bool) -> String {
+        if readonly { "r--".to_string() } else { "rw-".to_string() }
+    }
+
+    /// Expand a leading `~` to the home directory
+    fn expand_home(path: &str) -> String {
+        match path.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+                format!("{}{}", Self::home_dir(), rest)
+            }
+            _ => path.to_string(),
+        }
+    }
+
+    // Metadata, permissions and links
+    fn file_info(path: &str) -> Map {
+        let link_metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                println!("❌ Failed to read metadata for '{}': {}", path, e);
+                return Map::new();
+            }
+        };
+
+        let mut info = Self::entry_map(Path::new(path));
+        let metadata = fs::metadata(path).ok();
+        info.insert("accessed".into(), metadata.as_ref()
+            .and_then(|m| m.accessed().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| Dynamic::from(d.as_secs() as i64))
+            .unwrap_or(Dynamic::UNIT));
+        info.insert("symlink_target".into(), if link_metadata.file_type().is_symlink() {
+            fs::read_link(path).map(|t| Dynamic::from(t.to_string_lossy().to_string())).unwrap_or(Dynamic::UNIT)
+        } else {
+            Dynamic::UNIT
+        });
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let unix = metadata.as_ref().unwrap_or(&link_metadata);
+            info.insert("owner".into(), owner_name(unix.uid()).map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+            info.insert("mode".into(), Dynamic::from((unix.mode() & 0o7777) as i64));
+            info.insert("uid".into(), Dynamic::from(unix.uid() as i64));
+            info.insert("gid".into(), Dynamic::from(unix.gid() as i64));
+        }
+        #[cfg(not(unix))]
+        {
+            #[cfg(windows)]
+            info.insert("owner".into(), owner_name(Path::new(path)).map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+            #[cfg(not(windows))]
+            info.insert("owner".into(), Dynamic::UNIT);
+            info.insert("mode".into(), Dynamic::UNIT);
+            info.insert("uid".into(), Dynamic::UNIT);
+            info.insert("gid".into(), Dynamic::UNIT);
+        }
+
+        let is_file = metadata.as_ref().is_some_and(|m| m.is_file());
+        info.insert("mime".into(), Dynamic::from(if is_file { Self::detect_mime(Path::new(path)) } else { String::new() }));
+        info
+    }
+
+    /// MIME type from the file's leading bytes; text files without a signature fall back to the extension
+    fn detect_mime(path: &Path) -> String {
+        let mut header = Vec::with_capacity(8192);
+        if let Ok(file) = fs::File::open(path) {
+            let _ = std::io::Read::read_to_end(&mut std::io::Read::take(file, 8192), &mut header);
+        }
+        if let Some(kind) = infer::get(&header) {
+            return kind.mime_type().to_string();
+        }
+        if header.is_empty() || std::str::from_utf8(&header).is_ok() || header.len() == 8192 && !header.contains(&0) {
+            let by_extension = match Self::path_extension(&path.to_string_lossy()).to_lowercase().as_str() {
+                "html" | "htm" => "text/html",
+                "css" => "text/css",
+                "js" | "mjs" => "text/javascript",
+                "json" => "application/json",
+                "xml" => "application/xml",
+                "csv" => "text/csv",
+                "md" => "text/markdown",
+                "svg" => "image/svg+xml",
+                "yaml" | "yml" => "application/yaml",
+                "toml" => "application/toml",
+                _ => "text/plain",
+            };
+            return by_extension.to_string();
+        }
+        "application/octet-stream".to_string()
+    }
+
+    fn set_modified_time(path: &str, timestamp: i64) -> bool {
+        if dry_run::intercept("set_modified_time", || format!("{} = {}", path, timestamp)) {
+            return true;
+        }
+        match filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(timestamp, 0)) {
+            Ok(_) => true,
+            Err(e) => {
+                println!("❌ Failed to set modified time of '{}': {}", path, e);
+                false
+            }
+        }
+    }
+
+    fn set_readonly(path: &str, readonly: bool) -> bool {
+        if dry_run::intercept("set_readonly", || format!("{} = {}", path, readonly)) {
+            return true;
+        }
+        let mut permissions = match fs::metadata(path) {
+            Ok(metadata) => metadata.permissions(),
+            Err(e) => {
+                println!("❌ Failed to read permissions of '{}': {}", path, e);
+                return false;
+            }
+        };
+
+        // On Unix only the owner's write bit is restored, rather than making the file writable by everyone
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = permissions.mode();
+            permissions.set_mode(if readonly { mode & !0o222 } else { mode | 0o200 });
+        }
+        #[cfg(not(unix))]
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(readonly);
+
+        match fs::set_permissions(path, permissions) {
+            Ok(_) => true,
+            Err(e) => {
+                println!("❌ Failed to change permissions of '{}': {}", path, e);
+                false
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn chmod(path: &str, mode: i64) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        if dry_run::intercept("chmod", || format!("{} = {:o}", path, mode)) {
+            return true;
+        }
+        if !(0..=0o7777).contains(&mode) {
+            println!("❌ Invalid mode {:o} for '{}'", mode, path);
+            return false;
+        }
+        match fs::set_permissions(path, fs::Permissions::from_mode(mode as u32)) {
+            Ok(_) => true,
+            Err(e) => {
+                println!("❌ Failed to chmod '{}': {}", path, e);
+                false
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn chmod(path: &str, _mode: i64) -> bool {
+        println!("❌ chmod is only supported on Unix (use set_readonly for '{}')", path);
+        false
+    }
+
+    fn create_symlink(target: &str, link: &str) -> bool {
+        if dry_run::intercept("create_symlink", || format!("{} → {}", link, target)) {
+            return true;
+        }
+        #[cfg(unix)]
+        let result = std::os::unix::fs::symlink(target, link);
+        #[cfg(windows)]
+        let result = if Path::new(target).is_dir() {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        };
+
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                println!("❌ Failed to create symlink '{}' -> '{}': {}", link, target, e);
+                false
+            }
+        }
+    }
+
+    fn read_link(path: &str) -> String {
+        match fs::read_link(path) {
+            Ok(target) => target.to_string_lossy().to_string(),
+            Err(e) => {
+                println!("❌ Failed to read link '{}': {}", path, e);
+                String::new()
+            }
+        }
+    }
+
+    /// Lazily walk everything below `root` (not including it); hidden entries are pruned unless requested
+    pub(crate) fn walk_paths(root: &Path, max_depth: Option<usize>, follow_links: bool, include_hidden: bool) -> Box<dyn Iterator<Item = PathBuf>> {
+        let mut walker = walkdir::WalkDir::new(root).min_depth(1).follow_links(follow_links);
+        if let Some(depth) = max_depth {
+            walker = walker.max_depth(depth);
+        }
+        Box::new(walker.into_iter()
+            .filter_entry(move |entry| include_hidden || !entry.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|entry| entry
+                .inspect_err(|e| println!("❌ Failed to read directory entry: {}", e))
+                .ok())
+            .map(|entry| entry.into_path()))
+    }
+
+    // Recursive listing and search
+    fn glob(pattern: &str) -> FileWalk {
+        FileWalk { source: WalkSource::Glob(Self::expand_home(pattern)), filter: Arc::new(FileFilter::default()) }
+    }
+
+    fn walk_dir(path: &str, options: Map) -> Result<FileWalk, Box<EvalAltResult>> {
+        let (walk, filter) = WalkOptions::from_map(path, options, false)?;
+        Ok(FileWalk { source: WalkSource::Walk(walk), filter: Arc::new(filter) })
+    }
+
+    fn find_files(root: &str, options: Map) -> Result<FileWalk, Box<EvalAltResult>> {
+        let (walk, filter) = WalkOptions::from_map(root, options, true)?;
+        Ok(FileWalk { source: WalkSource::Walk(walk), filter: Arc::new(filter) })
+    }
+
     // Path helpers
     fn path_join(path1: &str, path2: &str) -> String {
         Path::new(path1).join(path2).to_string_lossy().to_string()
@@ -296,3 +769,465 @@ impl FileSystemKit {
         }
     }
 }
+
+/// How a recursive walk traverses the tree
+#[derive(Clone)]
+struct WalkOptions {
+    root: PathBuf,
+    max_depth: Option<usize>,
+    follow_links: bool,
+    include_hidden: bool,
+}
+
+impl WalkOptions {
+    /// Parse walk_dir options, plus the find_files filters when `search` is set
+    fn from_map(root: &str, options: Map, search: bool) -> Result<(Self, FileFilter), String> {
+        let mut walk = Self { root: PathBuf::from(FileSystemKit::expand_home(root)), max_depth: None, follow_links: false, include_hidden: false };
+        let mut filter = FileFilter { files_only: search, ..FileFilter::default() };
+        let invalid = |key: &str, expected: &str| format!("Option '{}' must be {}", key, expected);
+
+        for (key, value) in options {
+            let key = key.as_str();
+            match key {
+                "max_depth" => walk.max_depth = Some(value.as_int().ok().filter(|d| *d >= 0).ok_or_else(|| invalid(key, "a non-negative integer"))? as usize),
+                "follow_links" => walk.follow_links = value.as_bool().map_err(|_| invalid(key, "true or false"))?,
+                "include_hidden" => walk.include_hidden = value.as_bool().map_err(|_| invalid(key, "true or false"))?,
+                "name" if search => {
+                    let pattern = value.into_string().map_err(|_| invalid(key, "a glob string"))?;
+                    let glob = globset::Glob::new(&pattern).map_err(|e| format!("Invalid name pattern '{}': {}", pattern, e))?;
+                    filter.name = Some(glob.compile_matcher());
+                }
+                "ext" if search => {
+                    let extensions = if value.is_string() { vec![value] } else { value.try_cast::<Array>().ok_or_else(|| invalid(key, "a string or an array of strings"))? };
+                    filter.extensions = extensions.into_iter()
+                        .map(|e| e.into_string().map(|e| e.trim_start_matches('.').to_lowercase()).map_err(|_| invalid(key, "a string or an array of strings")))
+                        .collect::<Result<_, _>>()?;
+                }
+                "min_size" if search => filter.min_size = Some(value.as_int().map_err(|_| invalid(key, "an integer"))?),
+                "max_size" if search => filter.max_size = Some(value.as_int().map_err(|_| invalid(key, "an integer"))?),
+                "modified_after" if search => filter.modified_after = Some(value.as_int().map_err(|_| invalid(key, "a Unix timestamp"))?),
+                "modified_before" if search => filter.modified_before = Some(value.as_int().map_err(|_| invalid(key, "a Unix timestamp"))?),
+                "contains" if search => filter.contains = Some(value.into_string().map_err(|_| invalid(key, "a string"))?),
+                other => return Err(format!("Unknown option '{}'", other)),
+            }
+        }
+        Ok((walk, filter))
+    }
+}
+
+#[derive(Clone)]
+enum WalkSource {
+    Glob(String),
+    Walk(WalkOptions),
+}
+
+/// Conditions an entry must meet to be yielded (all of them must match)
+#[derive(Default)]
+struct FileFilter {
+    files_only: bool,
+    name: Option<globset::GlobMatcher>,
+    extensions: Vec<String>,
+    min_size: Option<i64>,
+    max_size: Option<i64>,
+    modified_after: Option<i64>,
+    modified_before: Option<i64>,
+    contains: Option<String>,
+}
+
+impl FileFilter {
+    fn matches(&self, path: &Path, entry: &Map) -> bool {
+        let is_file = entry.get("is_file").and_then(|v| v.as_bool().ok()).unwrap_or(false);
+        let number = |key: &str| entry.get(key).and_then(|v| v.as_int().ok());
+
+        if self.files_only && !is_file {
+            return false;
+        }
+        if let Some(name) = &self.name {
+            if !path.file_name().is_some_and(|n| name.is_match(n)) {
+                return false;
+            }
+        }
+        if !self.extensions.is_empty() {
+            let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if !self.extensions.contains(&extension) {
+                return false;
+            }
+        }
+        let size = number("size").unwrap_or(0);
+        if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+            return false;
+        }
+        let modified = number("modified");
+        if self.modified_after.is_some_and(|after| modified.is_none_or(|m| m <= after))
+            || self.modified_before.is_some_and(|before| modified.is_none_or(|m| m >= before)) {
+            return false;
+        }
+        if let Some(needle) = &self.contains {
+            // Checked last since it reads the file
+            if !file_contains(path, needle.as_bytes()).unwrap_or(false) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Search a file for `needle` in fixed-size chunks, so large files are never loaded whole.
+/// The last `needle.len() - 1` bytes of each chunk are kept to catch matches across chunks.
+fn file_contains(path: &Path, needle: &[u8]) -> io::Result<bool> {
+    const CHUNK: usize = 64 * 1024;
+    if needle.is_empty() {
+        return Ok(true);
+    }
+    let mut file = fs::File::open(path)?;
+    let mut window: Vec<u8> = Vec::with_capacity(CHUNK + needle.len());
+    let mut chunk = vec![0u8; CHUNK];
+    loop {
+        let read = match file.read(&mut chunk) {
+            Ok(0) => return Ok(false),
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        window.extend_from_slice(&chunk[..read]);
+        if window.windows(needle.len()).any(|candidate| candidate == needle) {
+            return Ok(true);
+        }
+        let keep = window.len().min(needle.len() - 1);
+        window.drain(..window.len() - keep);
+    }
+}
+
+/// Account name for a user ID, from the system user database
+#[cfg(unix)]
+fn owner_name(uid: u32) -> Option<String> {
+    let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        let mut entry: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut found: *mut libc::passwd = std::ptr::null_mut();
+        let status = unsafe { libc::getpwuid_r(uid, &mut entry, buffer.as_mut_ptr(), buffer.len(), &mut found) };
+        if status == libc::ERANGE && buffer.len() < 1 << 20 {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if status != 0 || found.is_null() || entry.pw_name.is_null() {
+            return None;
+        }
+        let name = unsafe { std::ffi::CStr::from_ptr(entry.pw_name) };
+        return Some(name.to_string_lossy().into_owned());
+    }
+}
+
+/// `DOMAIN\name` of the account that owns `path`, looked up from its owner SID
+#[cfg(windows)]
+fn owner_name(path: &Path) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr::{null, null_mut};
+    use windows_sys::Win32::Foundation::{LocalFree, ERROR_SUCCESS};
+    use windows_sys::Win32::Security::Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT};
+    use windows_sys::Win32::Security::{LookupAccountSidW, OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR, PSID, SID_NAME_USE};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut owner: PSID = null_mut();
+    let mut descriptor: PSECURITY_DESCRIPTOR = null_mut();
+    let status = unsafe {
+        GetNamedSecurityInfoW(wide.as_ptr(), SE_FILE_OBJECT, OWNER_SECURITY_INFORMATION, &mut owner, null_mut(), null_mut(), null_mut(), &mut descriptor)
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let (mut name, mut domain) = ([0u16; 256], [0u16; 256]);
+    let (mut name_len, mut domain_len) = (name.len() as u32, domain.len() as u32);
+    let mut kind: SID_NAME_USE = 0;
+    let found = unsafe {
+        LookupAccountSidW(null(), owner, name.as_mut_ptr(), &mut name_len, domain.as_mut_ptr(), &mut domain_len, &mut kind)
+    } != 0;
+    // The owner SID points into the descriptor, so it is only freed after the lookup
+    unsafe { LocalFree(descriptor) };
+    if !found {
+        return None;
+    }
+    let name = String::from_utf16_lossy(&name[..name_len as usize]);
+    let domain = String::from_utf16_lossy(&domain[..domain_len as usize]);
+    Some(if domain.is_empty() { name } else { format!("{}\\{}", domain, name) })
+}
+
+/// Copy a file, or a directory with everything in it
+pub(crate) fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    if !fs::symlink_metadata(from)?.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry.map_err(io::Error::other)?;
+        let target = to.join(entry.path().strip_prefix(from).unwrap_or(entry.path()));
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// `name`, then `name_2`, `name_3`, ... keeping the extension at the end
+pub(crate) fn candidate_name(name: &str, attempt: usize) -> String {
+    if attempt == 1 {
+        return name.to_string();
+    }
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}_{}.{}", stem, attempt, ext),
+        _ => format!("{}_{}", name, attempt),
+    }
+}
+
+/// Lazily evaluated file listing returned by glob, walk_dir and find_files.
+/// Iterating it with `for` visits the tree as it goes; `to_array()` collects everything.
+#[derive(Clone)]
+pub struct FileWalk {
+    source: WalkSource,
+    filter: Arc<FileFilter>,
+}
+
+impl std::fmt::Display for FileWalk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            WalkSource::Glob(pattern) => write!(f, "FileWalk(glob \"{}\")", pattern),
+            WalkSource::Walk(walk) => write!(f, "FileWalk(\"{}\")", walk.root.display()),
+        }
+    }
+}
+
+impl IntoIterator for FileWalk {
+    type Item = Dynamic;
+    type IntoIter = Box<dyn Iterator<Item = Dynamic>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let paths: Box<dyn Iterator<Item = PathBuf>> = match self.source {
+            WalkSource::Glob(pattern) => match glob::glob(&pattern) {
+                Ok(paths) => Box::new(paths.filter_map(|entry| entry
+                    .inspect_err(|e| println!("❌ Failed to read '{}': {}", e.path().display(), e.error()))
+                    .ok())),
+                Err(e) => {
+                    println!("❌ Invalid glob pattern '{}': {}", pattern, e);
+                    Box::new(std::iter::empty())
+                }
+            },
+            WalkSource::Walk(walk) => FileSystemKit::walk_paths(&walk.root, walk.max_depth, walk.follow_links, walk.include_hidden),
+        };
+
+        let filter = self.filter;
+        Box::new(paths.filter_map(move |path| {
+            let entry = FileSystemKit::entry_map(&path);
+            filter.matches(&path, &entry).then(|| Dynamic::from(entry))
+        }))
+    }
+}
+
+/// Advisory lock on `<path>.lock`, returned by `lock_file`. The lock lives beside the file
+/// because atomic writes replace the file itself. Released by `unlock()` or when the last copy is dropped.
+#[derive(Clone)]
+pub struct FileLock {
+    path: PathBuf,
+    file: Arc<Mutex<Option<fs::File>>>,
+}
+
+impl FileLock {
+    fn acquire(path: &Path, timeout: Option<Duration>) -> Result<Self, String> {
+        let mut lock_name = path.as_os_str().to_owned();
+        lock_name.push(".lock");
+        let lock_path = PathBuf::from(lock_name);
+        if let Some(parent) = lock_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)
+            .map_err(|e| format!("Failed to open lock file '{}': {}", lock_path.display(), e))?;
+
+        match timeout {
+            None => file.lock().map_err(|e| format!("Failed to lock '{}': {}", path.display(), e))?,
+            Some(timeout) => {
+                let started = Instant::now();
+                loop {
+                    match file.try_lock() {
+                        Ok(()) => break,
+                        Err(fs::TryLockError::WouldBlock) if started.elapsed() < timeout => std::thread::sleep(Duration::from_millis(25)),
+                        Err(fs::TryLockError::WouldBlock) => {
+                            return Err(format!("Timed out after {}ms waiting for the lock on '{}'", timeout.as_millis(), path.display()));
+                        }
+                        Err(fs::TryLockError::Error(e)) => return Err(format!("Failed to lock '{}': {}", path.display(), e)),
+                    }
+                }
+            }
+        }
+        Ok(Self { path: path.to_path_buf(), file: Arc::new(Mutex::new(Some(file))) })
+    }
+
+    /// Release the lock; returns false if it was already released
+    fn unlock(&self) -> bool {
+        match self.file.lock().unwrap().take() {
+            Some(file) => file.unlock().is_ok(),
+            None => false,
+        }
+    }
+}
+
+impl std::fmt::Display for FileLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if self.file.lock().unwrap().is_some() { "locked" } else { "unlocked" };
+        write!(f, "FileLock(\"{}\", {})", self.path.display(), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_glob_and_find() {
+        let dir = std::env::temp_dir().join(format!("snaprun_walk_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("src").join("nested")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("src").join("main.rs"), "fn main() { todo!() }").unwrap();
+        fs::write(dir.join("src").join("nested").join("lib.RS"), "pub fn lib() {}").unwrap();
+        fs::write(dir.join("notes.txt"), "todo: write docs").unwrap();
+        fs::write(dir.join(".git").join("HEAD"), "ref").unwrap();
+
+        let mut engine = Engine::new();
+        FileSystemKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("dir", dir.to_string_lossy().to_string());
+
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let walked = walk_dir(dir).to_array().len();
+            let shallow = walk_dir(dir, #{ max_depth: 1, include_hidden: true }).to_array().len();
+            let globbed = glob(dir + "/**/*.rs").to_array().map(|f| f.name);
+            let found = [];
+            for f in find_files(dir, #{ ext: "rs", contains: "todo" }) { found.push(f.name); }
+            let sized = find_files(dir, #{ name: "*.txt", min_size: 5 }).to_array();
+            walked + "|" + shallow + "|" + globbed + "|" + found + "|" + sized[0].is_file + "|" + (sized[0].modified > 0)
+        "#).unwrap();
+        assert_eq!(result, "5|3|[\"main.rs\"]|[\"main.rs\"]|true|true");
+
+        assert!(engine.eval_with_scope::<FileWalk>(&mut scope, r#"find_files(dir, #{ size: 1 })"#).is_err());
+
+        // A match that straddles two read chunks is still found
+        let mut big = vec![b'x'; 64 * 1024 - 2];
+        big.extend_from_slice(b"needle");
+        fs::write(dir.join("big.log"), &big).unwrap();
+        assert!(file_contains(&dir.join("big.log"), b"needle").unwrap());
+        assert!(!file_contains(&dir.join("big.log"), b"needles").unwrap());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_info_and_permissions() {
+        let dir = std::env::temp_dir().join(format!("snaprun_info_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("image.png"), [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0]).unwrap();
+        fs::write(dir.join("data.json"), "{}").unwrap();
+
+        let mut engine = Engine::new();
+        FileSystemKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("dir", dir.to_string_lossy().to_string());
+
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let png = path_join(dir, "image.png");
+            set_modified_time(png, 1700000000);
+            set_readonly(png, true);
+            let locked = file_info(png);
+            set_readonly(png, false);
+            create_symlink(png, path_join(dir, "link.png"));
+            let link = file_info(path_join(dir, "link.png"));
+            locked.mime + "|" + locked.modified + "|" + locked.readonly + "|" + file_info(png).readonly + "|" +
+                file_info(path_join(dir, "data.json")).mime + "|" + link.is_symlink + "|" + (read_link(path_join(dir, "link.png")) == png) + "|" +
+                file_info(path_join(dir, "missing")).len()
+        "#).unwrap();
+        assert_eq!(result, "image/png|1700000000|true|false|application/json|true|true|0");
+
+        #[cfg(unix)]
+        {
+            let mode = engine.eval_with_scope::<i64>(&mut scope, r#"chmod(path_join(dir, "data.json"), "640"); file_info(path_join(dir, "data.json")).mode"#).unwrap();
+            assert_eq!(mode, 0o640);
+            let owner = engine.eval_with_scope::<Dynamic>(&mut scope, r#"file_info(path_join(dir, "data.json")).owner"#).unwrap();
+            assert_eq!(owner.into_string().ok(), owner_name(unsafe { libc::geteuid() }));
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_atomic_write_and_locks() {
+        let dir = std::env::temp_dir().join(format!("snaprun_lock_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let data = dir.join("data.json");
+        fs::write(&data, "old").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&data, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        FileSystemKit::write_atomic(&data, b"{\"v\": 2}", true).unwrap();
+        assert_eq!(fs::read_to_string(&data).unwrap(), "{\"v\": 2}");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&data).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let held = FileLock::acquire(&data, None).unwrap();
+        let waited = FileLock::acquire(&data, Some(Duration::from_millis(60)));
+        assert!(waited.err().unwrap().contains("Timed out"));
+        assert!(held.unlock() && !held.unlock());
+
+        let mut engine = Engine::new();
+        FileSystemKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("data", data.to_string_lossy().to_string());
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let n = with_lock(data, || {
+                write_file(data, "locked write", #{ fsync: true });
+                42
+            });
+            let lock = lock_file(data, 100);
+            let state = lock.locked;
+            lock.unlock();
+            n + "|" + state + "|" + lock.locked + "|" + read_file(data) + "|" + write_file(data, "x", #{ flush: true })
+        "#).unwrap();
+        assert_eq!(result, "42|true|false|locked write|false");
+
+        // A throwing callback still releases the lock
+        assert!(engine.eval_with_scope::<()>(&mut scope, r#"with_lock(data, || throw "boom")"#).is_err());
+        assert!(FileLock::acquire(&data, Some(Duration::ZERO)).is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bytes_and_encodings() {
+        let dir = std::env::temp_dir().join(format!("snaprun_bytes_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        // "Größe" saved by a Windows tool as UTF-16LE with a BOM
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("Größe\r\n".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        fs::write(dir.join("win.txt"), &utf16).unwrap();
+        fs::write(dir.join("latin1.txt"), [b'n', 0xE4, b'h']).unwrap();
+
+        let mut engine = Engine::new();
+        FileSystemKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("dir", dir.to_string_lossy().to_string());
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let text = read_file_encoding(path_join(dir, "win.txt"), "utf-8");
+            let bytes = read_bytes(path_join(dir, "latin1.txt"));
+            bytes[1] = 0x6F;
+            write_bytes(path_join(dir, "copy.bin"), bytes);
+            write_file_encoding(path_join(dir, "out.txt"), "Größe", "utf-16le");
+            text + "|" + read_file_encoding(path_join(dir, "latin1.txt"), "latin1") + "|" + read_file(path_join(dir, "copy.bin"))
+        "#).unwrap();
+        assert_eq!(result, "Größe\r\n|näh|noh");
+        assert_eq!(fs::read(dir.join("out.txt")).unwrap(), utf16[..utf16.len() - 4]);
+        assert!(engine.eval_with_scope::<String>(&mut scope, r#"read_file_encoding(path_join(dir, "win.txt"), "nope")"#).is_ok());
+        assert!(engine.eval_with_scope::<String>(&mut scope, r#"read_file_encoding(path_join(dir, "latin1.txt"), "nope")"#).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map, Blob, FnPtr, NativeCallContext};
+use sha2::Digest;
+use crate::fs_kit::FileSystemKit;
+use crate::kits::ui_kit::Kit;
+
+/// Content hashing and duplicate detection for Rhai scripts
+pub struct HashKit;
+
+/// Minimum time between progress updates in the UI
+const UI_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+type ScanProgress<'a> = dyn FnMut(&str, usize, usize, &Path) -> Result<(), String> + 'a;
+
+/// Incremental hasher exposed to scripts as the `Hasher` type
+#[derive(Clone)]
+pub enum Hasher {
+    Sha256(sha2::Sha256),
+    Sha1(sha1::Sha1),
+    Md5(md5::Md5),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    /// Algorithm names accepted by every hashing function
+    pub fn new(algorithm: &str) -> Result<Self, String> {
+        match algorithm.to_lowercase().replace('-', "").as_str() {
+            "sha256" => Ok(Self::Sha256(sha2::Sha256::new())),
+            "sha1" => Ok(Self::Sha1(sha1::Sha1::new())),
+            "md5" => Ok(Self::Md5(md5::Md5::new())),
+            "blake3" => Ok(Self::Blake3(Box::new(blake3::Hasher::new()))),
+            _ => Err(format!("Unknown hash algorithm '{}' (expected sha256, sha1, md5 or blake3)", algorithm)),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Md5(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => { hasher.update(data); }
+        }
+    }
+
+    /// Feed a file through the hasher in fixed-size chunks
+    pub fn update_file(&mut self, path: &Path) -> Result<u64, String> {
+        let mut file = File::open(path).map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+        let mut buffer = vec![0u8; 64 * 1024];
+        let mut total = 0;
+        loop {
+            let read = file.read(&mut buffer).map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+            if read == 0 {
+                return Ok(total);
+            }
+            self.update(&buffer[..read]);
+            total += read as u64;
+        }
+    }
+
+    /// Lowercase hex digest of everything fed so far (the hasher can keep being updated)
+    pub fn hex(&self) -> String {
+        let digest: Vec<u8> = match self.clone() {
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Sha1(hasher) => hasher.finalize().to_vec(),
+            Self::Md5(hasher) => hasher.finalize().to_vec(),
+            Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        };
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256(_) => "sha256",
+            Self::Sha1(_) => "sha1",
+            Self::Md5(_) => "md5",
+            Self::Blake3(_) => "blake3",
+        }
+    }
+}
+
+/// Options accepted by `find_duplicates`
+struct DuplicateOptions {
+    algorithm: String,
+    /// Files smaller than this are ignored (empty files are skipped by default)
+    min_size: u64,
+    include_hidden: bool,
+    follow_links: bool,
+    /// Called with #{ phase, current, total, path }; replaces the UI progress view when given
+    on_progress: Option<FnPtr>,
+}
+
+impl DuplicateOptions {
+    fn from_map(options: Map) -> Result<Self, String> {
+        let mut parsed = Self { algorithm: "blake3".to_string(), min_size: 1, include_hidden: false, follow_links: false, on_progress: None };
+        for (key, value) in options {
+            match key.as_str() {
+                "algo" => parsed.algorithm = value.into_string().map_err(|_| "Option 'algo' must be a string".to_string())?,
+                "min_size" => parsed.min_size = value.as_int().ok().filter(|s| *s >= 0).ok_or("Option 'min_size' must be a non-negative integer")? as u64,
+                "include_hidden" => parsed.include_hidden = value.as_bool().map_err(|_| "Option 'include_hidden' must be true or false".to_string())?,
+                "follow_links" => parsed.follow_links = value.as_bool().map_err(|_| "Option 'follow_links' must be true or false".to_string())?,
+                "on_progress" => parsed.on_progress = Some(value.try_cast::<FnPtr>().ok_or("Option 'on_progress' must be a function")?),
+                other => return Err(format!("Unknown find_duplicates option '{}'", other)),
+            }
+        }
+        Hasher::new(&parsed.algorithm)?;
+        Ok(parsed)
+    }
+}
+
+impl HashKit {
+    /// Register all hashing functions; `ui` is used to show progress of long scans
+    pub fn register_functions(engine: &mut Engine, ui: Option<Arc<Mutex<Kit>>>) {
+        println!("🟣 HashKit: Registering hashing functions");
+
+        engine.register_fn("hash_string", |text: &str| -> String {
+            Self::hash_bytes(text.as_bytes(), "sha256").unwrap_or_default()
+        });
+        engine.register_fn("hash_string", |text: &str, algorithm: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::hash_bytes(text.as_bytes(), algorithm)?)
+        });
+        engine.register_fn("hash_bytes", |data: Blob, algorithm: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::hash_bytes(&data, algorithm)?)
+        });
+        engine.register_fn("hash_file", |path: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::hash_file(Path::new(path), "sha256")?)
+        });
+        engine.register_fn("hash_file", |path: &str, algorithm: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::hash_file(Path::new(path), algorithm)?)
+        });
+
+        // Streaming hasher: let h = hasher("sha256"); h.update(chunk); h.update_file(path); h.hex()
+        engine.register_type_with_name::<Hasher>("Hasher");
+        engine.register_fn("hasher", |algorithm: &str| -> Result<Hasher, Box<EvalAltResult>> {
+            Ok(Hasher::new(algorithm)?)
+        });
+        engine.register_fn("update", |hasher: &mut Hasher, text: &str| hasher.update(text.as_bytes()));
+        engine.register_fn("update", |hasher: &mut Hasher, data: Blob| hasher.update(&data));
+        engine.register_fn("update_file", |hasher: &mut Hasher, path: &str| -> Result<i64, Box<EvalAltResult>> {
+            Ok(hasher.update_file(Path::new(path))? as i64)
+        });
+        engine.register_fn("hex", |hasher: &mut Hasher| hasher.hex());
+        engine.register_get("algorithm", |hasher: &mut Hasher| hasher.name().to_string());
+        engine.register_fn("to_string", |hasher: &mut Hasher| format!("Hasher({})", hasher.name()));
+        engine.register_fn("to_debug", |hasher: &mut Hasher| format!("Hasher({})", hasher.name()));
+
+        {
+            let ui = ui.clone();
+            engine.register_fn("find_duplicates", move |context: NativeCallContext, root: &str| -> Result<Array, Box<EvalAltResult>> {
+                Self::find_duplicates_from_script(&context, root, Map::new(), ui.as_ref())
+            });
+        }
+        engine.register_fn("find_duplicates", move |context: NativeCallContext, root: &str, options: Map| -> Result<Array, Box<EvalAltResult>> {
+            Self::find_duplicates_from_script(&context, root, options, ui.as_ref())
+        });
+
+        println!("🟣 HashKit: All hashing functions registered");
+    }
+
+    pub fn hash_bytes(data: &[u8], algorithm: &str) -> Result<String, String> {
+        let mut hasher = Hasher::new(algorithm)?;
+        hasher.update(data);
+        Ok(hasher.hex())
+    }
+
+    /// Hash a file without loading it into memory
+    pub fn hash_file(path: &Path, algorithm: &str) -> Result<String, String> {
+        let mut hasher = Hasher::new(algorithm)?;
+        hasher.update_file(path)?;
+        Ok(hasher.hex())
+    }
+
+    fn find_duplicates_from_script(context: &NativeCallContext, root: &str, options: Map, ui: Option<&Arc<Mutex<Kit>>>) -> Result<Array, Box<EvalAltResult>> {
+        let options = DuplicateOptions::from_map(options)?;
+        let callback = options.on_progress.clone();
+        let mut last_ui_update: Option<Instant> = None;
+
+        let mut progress = |phase: &str, current: usize, total: usize, path: &Path| -> Result<(), String> {
+            if let Some(callback) = &callback {
+                let mut event = Map::new();
+                event.insert("phase".into(), Dynamic::from(phase.to_string()));
+                event.insert("current".into(), Dynamic::from(current as i64));
+                event.insert("total".into(), Dynamic::from(total as i64));
+                event.insert("path".into(), Dynamic::from(path.to_string_lossy().to_string()));
+                return callback.call_within_context::<Dynamic>(context, (event,)).map(|_| ()).map_err(|e| e.to_string());
+            }
+
+            // Without a callback, keep the user informed through the UI (throttled)
+            let Some(ui) = ui else { return Ok(()) };
+            let finished = total > 0 && current == total;
+            if !finished && last_ui_update.is_some_and(|t| t.elapsed() < UI_PROGRESS_INTERVAL) {
+                return Ok(());
+            }
+            last_ui_update = Some(Instant::now());
+            let title = if phase == "scan" { "Scanning for duplicates" } else { "Hashing candidate files" };
+            if let Ok(kit) = ui.lock() {
+                let _ = kit.show_progress(title, current as i64, total as i64, &path.to_string_lossy());
+            }
+            Ok(())
+        };
+
+        Ok(Self::find_duplicates(Path::new(root), &options, &mut progress)?)
+    }
+
+    /// Group files by size, then hash only the sizes that occur more than once
+    fn find_duplicates(root: &Path, options: &DuplicateOptions, progress: &mut ScanProgress) -> Result<Array, String> {
+        if !root.is_dir() {
+            return Err(format!("'{}' is not a directory", root.display()));
+        }
+
+        let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        let mut scanned = 0;
+        for path in FileSystemKit::walk_paths(root, None, options.follow_links, options.include_hidden) {
+            let Ok(metadata) = fs::metadata(&path) else { continue };
+            if !metadata.is_file() || metadata.len() < options.min_size {
+                continue;
+            }
+            scanned += 1;
+            progress("scan", scanned, 0, &path)?;
+            by_size.entry(metadata.len()).or_default().push(path);
+        }
+
+        let candidates: Vec<(u64, Vec<PathBuf>)> = by_size.into_iter().filter(|(_, paths)| paths.len() > 1).collect();
+        let total: usize = candidates.iter().map(|(_, paths)| paths.len()).sum();
+        let mut hashed = 0;
+        let mut groups = Vec::new();
+
+        for (size, paths) in candidates {
+            let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+            for path in paths {
+                hashed += 1;
+                match Self::hash_file(&path, &options.algorithm) {
+                    Ok(hash) => by_hash.entry(hash).or_default().push(path.clone()),
+                    Err(e) => println!("❌ {}", e),
+                }
+                progress("hash", hashed, total, &path)?;
+            }
+            for (hash, mut files) in by_hash.into_iter().filter(|(_, files)| files.len() > 1) {
+                files.sort();
+                groups.push((size, hash, files));
+            }
+        }
+
+        // Biggest savings first; ties broken by hash so the output is stable
+        groups.sort_by(|a, b| (b.0 * (b.2.len() as u64 - 1)).cmp(&(a.0 * (a.2.len() as u64 - 1))).then_with(|| a.1.cmp(&b.1)));
+        Ok(groups.into_iter().map(|(size, hash, files)| {
+            let mut group = Map::new();
+            group.insert("hash".into(), Dynamic::from(hash));
+            group.insert("size".into(), Dynamic::from(size as i64));
+            group.insert("wasted".into(), Dynamic::from((size * (files.len() as u64 - 1)) as i64));
+            group.insert("files".into(), Dynamic::from(files.into_iter()
+                .map(|f| Dynamic::from(f.to_string_lossy().to_string()))
+                .collect::<Array>()));
+            Dynamic::from(group)
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_digests_and_streaming() {
+        assert_eq!(HashKit::hash_bytes(b"abc", "sha256").unwrap(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(HashKit::hash_bytes(b"abc", "SHA-1").unwrap(), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(HashKit::hash_bytes(b"abc", "md5").unwrap(), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(HashKit::hash_bytes(b"abc", "blake3").unwrap(), "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85");
+        assert!(HashKit::hash_bytes(b"abc", "crc32").is_err());
+
+        let mut engine = Engine::new();
+        HashKit::register_functions(&mut engine, None);
+        let streamed = engine.eval::<String>(r#"let h = hasher("sha256"); h.update("a"); h.update("bc"); h.hex()"#).unwrap();
+        assert_eq!(streamed, HashKit::hash_bytes(b"abc", "sha256").unwrap());
+    }
+
+    #[test]
+    fn test_find_duplicates_groups_by_content() {
+        let dir = std::env::temp_dir().join(format!("snaprun_dupes_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("one.txt"), "same content").unwrap();
+        fs::write(dir.join("a").join("two.txt"), "same content").unwrap();
+        fs::write(dir.join("a").join("other.txt"), "diff content").unwrap();
+        fs::write(dir.join("empty1"), "").unwrap();
+        fs::write(dir.join("empty2"), "").unwrap();
+
+        let mut engine = Engine::new();
+        HashKit::register_functions(&mut engine, None);
+        let mut scope = rhai::Scope::new();
+        scope.push("dir", dir.to_string_lossy().to_string());
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let hashed = 0;
+            let groups = find_duplicates(dir, #{ algo: "sha1", on_progress: |p| if p.phase == "hash" { hashed += 1 } });
+            groups.len() + "|" + groups[0].files.len() + "|" + groups[0].wasted + "|" + hashed + "|" +
+                (groups[0].hash == hash_file(dir + "/one.txt", "sha1"))
+        "#).unwrap();
+        assert_eq!(result, "1|2|12|3|true");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,421 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use rhai::{Engine, Blob, Dynamic, EvalAltResult, FnPtr, Map, NativeCallContext};
+use crate::data_format::{json_value_to_rhai, rhai_to_json_value};
+
+/// Connect and per-read timeout. There is no overall deadline unless a request sets
+/// `timeout_ms`, so large downloads keep going as long as data keeps arriving.
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+/// Chunk size used when streaming downloads to disk
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// HTTP client for Rhai scripts. Each runner gets its own agent, so cookies last for one run.
+pub struct HttpKit;
+
+/// Per-run HTTP client state
+struct HttpState {
+    agent: ureq::Agent,
+    proxy: Option<String>,
+}
+
+/// Request options parsed from the Rhai options map
+#[derive(Default)]
+struct RequestOptions {
+    method: String,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+    on_progress: Option<FnPtr>,
+}
+
+impl HttpState {
+    fn new(proxy: Option<String>) -> Result<Self, String> {
+        let mut builder = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_millis(DEFAULT_TIMEOUT_MS))
+            .timeout_read(Duration::from_millis(DEFAULT_TIMEOUT_MS))
+            .try_proxy_from_env(true);
+
+        if let Some(proxy_url) = &proxy {
+            let proxy = ureq::Proxy::new(proxy_url)
+                .map_err(|e| format!("Invalid proxy '{}': {}", proxy_url, e))?;
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(Self {
+            agent: builder.build(),
+            proxy,
+        })
+    }
+}
+
+impl HttpKit {
+    /// Register all HTTP functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 HttpKit: Registering HTTP client functions");
+
+        let state = Arc::new(Mutex::new(
+            HttpState::new(None).expect("Failed to create HTTP client"),
+        ));
+
+        // Requests
+        {
+            let state = state.clone();
+            engine.register_fn("fetch", move |url: &str| -> Result<Map, Box<EvalAltResult>> {
+                let agent = state.lock().unwrap().agent.clone();
+                Ok(Self::fetch(&agent, url, Self::parse_options(Map::new())?)?)
+            });
+        }
+        {
+            let state = state.clone();
+            engine.register_fn("fetch", move |url: &str, options: Map| -> Result<Map, Box<EvalAltResult>> {
+                let agent = state.lock().unwrap().agent.clone();
+                Ok(Self::fetch(&agent, url, Self::parse_options(options)?)?)
+            });
+        }
+
+        // Downloads
+        {
+            let state = state.clone();
+            engine.register_fn("download", move |context: NativeCallContext, url: &str, path: &str| -> Result<Map, Box<EvalAltResult>> {
+                let agent = state.lock().unwrap().agent.clone();
+                Self::download(&context, &agent, url, path, Self::parse_options(Map::new())?)
+            });
+        }
+        {
+            let state = state.clone();
+            engine.register_fn("download", move |context: NativeCallContext, url: &str, path: &str, options: Map| -> Result<Map, Box<EvalAltResult>> {
+                let agent = state.lock().unwrap().agent.clone();
+                Self::download(&context, &agent, url, path, Self::parse_options(options)?)
+            });
+        }
+
+        // Proxy settings (replacing the agent also starts a fresh cookie jar)
+        {
+            let state = state.clone();
+            engine.register_fn("http_set_proxy", move |proxy_url: &str| -> Result<(), Box<EvalAltResult>> {
+                *state.lock().unwrap() = HttpState::new(Some(proxy_url.to_string()))?;
+                Ok(())
+            });
+        }
+        {
+            let state = state.clone();
+            engine.register_fn("http_clear_proxy", move || -> Result<(), Box<EvalAltResult>> {
+                *state.lock().unwrap() = HttpState::new(None)?;
+                Ok(())
+            });
+        }
+        {
+            let state = state.clone();
+            engine.register_fn("http_proxy", move || -> Dynamic {
+                match &state.lock().unwrap().proxy {
+                    Some(proxy) => Dynamic::from(proxy.clone()),
+                    None => Dynamic::UNIT,
+                }
+            });
+        }
+
+        println!("🟣 HttpKit: All HTTP client functions registered");
+    }
+
+    /// Perform a request and return `#{ status, ok, headers, body, json }`.
+    /// `json` is the parsed body when the response is declared as JSON, otherwise `()`.
+    fn fetch(agent: &ureq::Agent, url: &str, options: RequestOptions) -> Result<Map, String> {
+        let response = Self::send(agent, url, &options)?;
+
+        let status = response.status() as i64;
+        let headers = Self::response_headers(&response);
+        let is_json = response.content_type().contains("json");
+
+        let mut body = Vec::new();
+        response.into_reader()
+            .read_to_end(&mut body)
+            .map_err(|e| format!("Failed to read response from '{}': {}", url, e))?;
+        let body = String::from_utf8_lossy(&body).to_string();
+
+        let json = match serde_json::from_str::<serde_json::Value>(&body) {
+            Ok(value) if is_json => json_value_to_rhai(value)?,
+            _ => Dynamic::UNIT,
+        };
+
+        let mut result = Map::new();
+        result.insert("status".into(), Dynamic::from(status));
+        result.insert("ok".into(), Dynamic::from((200..300).contains(&status)));
+        result.insert("headers".into(), Dynamic::from(headers));
+        result.insert("body".into(), Dynamic::from(body));
+        result.insert("json".into(), json);
+        Ok(result)
+    }
+
+    /// Stream a response body to `path`, calling `on_progress(#{ received, total, percent })` per chunk
+    fn download(context: &NativeCallContext, agent: &ureq::Agent, url: &str, path: &str, options: RequestOptions) -> Result<Map, Box<EvalAltResult>> {
+        let response = Self::send(agent, url, &options)?;
+        let status = response.status() as i64;
+        if !(200..300).contains(&status) {
+            return Err(format!("Download of '{}' failed with HTTP status {}", url, status).into());
+        }
+
+        let total: Option<i64> = response.header("Content-Length").and_then(|v| v.parse().ok());
+
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory for '{}': {}", path, e))?;
+            }
+        }
+
+        // Write to a partial file first so an interrupted download never looks complete
+        let partial_path = format!("{}.part", path);
+        let mut file = fs::File::create(&partial_path)
+            .map_err(|e| format!("Failed to create '{}': {}", partial_path, e))?;
+
+        let mut reader = response.into_reader();
+        let mut buffer = vec![0u8; DOWNLOAD_CHUNK_SIZE];
+        let mut received: i64 = 0;
+
+        let result = loop {
+            let read = match reader.read(&mut buffer) {
+                Ok(0) => break Ok(()),
+                Ok(read) => read,
+                Err(e) => break Err(format!("Failed to read response from '{}': {}", url, e).into()),
+            };
+            if let Err(e) = file.write_all(&buffer[..read]) {
+                break Err(format!("Failed to write '{}': {}", partial_path, e).into());
+            }
+            received += read as i64;
+
+            if let Some(callback) = &options.on_progress {
+                let mut progress = Map::new();
+                progress.insert("received".into(), Dynamic::from(received));
+                progress.insert("total".into(), total.map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+                progress.insert("percent".into(), match total {
+                    Some(total) if total > 0 => Dynamic::from(received as f64 * 100.0 / total as f64),
+                    _ => Dynamic::UNIT,
+                });
+                if let Err(e) = callback.call_within_context::<Dynamic>(context, (progress,)) {
+                    break Err(e);
+                }
+            }
+        };
+
+        drop(file);
+        if let Err(e) = result.and_then(|_| {
+            fs::rename(&partial_path, path)
+                .map_err(|e| format!("Failed to move download to '{}': {}", path, e).into())
+        }) {
+            let _ = fs::remove_file(&partial_path);
+            return Err(e);
+        }
+
+        let mut map = Map::new();
+        map.insert("status".into(), Dynamic::from(status));
+        map.insert("path".into(), Dynamic::from(path.to_string()));
+        map.insert("bytes".into(), Dynamic::from(received));
+        Ok(map)
+    }
+
+    /// Build and send a request. HTTP error statuses are returned as responses, not errors.
+    fn send(agent: &ureq::Agent, url: &str, options: &RequestOptions) -> Result<ureq::Response, String> {
+        let mut request = agent.request(&options.method, url);
+        for (name, value) in &options.query {
+            request = request.query(name, value);
+        }
+        for (name, value) in &options.headers {
+            request = request.set(name, value);
+        }
+        if let Some(timeout) = options.timeout {
+            request = request.timeout(timeout);
+        }
+
+        let result = match &options.body {
+            Some(body) => request.send_bytes(body),
+            None => request.call(),
+        };
+
+        match result {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(_, response)) => Ok(response),
+            Err(e) => Err(format!("Request to '{}' failed: {}", url, e)),
+        }
+    }
+
+    fn response_headers(response: &ureq::Response) -> Map {
+        let mut headers = Map::new();
+        for name in response.headers_names() {
+            if let Some(value) = response.header(&name) {
+                headers.insert(name.to_lowercase().into(), Dynamic::from(value.to_string()));
+            }
+        }
+        headers
+    }
+
+    /// Parse `#{ method, headers, body, json, timeout_ms, query, on_progress }`
+    fn parse_options(options: Map) -> Result<RequestOptions, String> {
+        let mut parsed = RequestOptions {
+            method: "GET".to_string(),
+            ..Default::default()
+        };
+
+        for (key, value) in options {
+            match key.as_str() {
+                "method" => parsed.method = value.to_string().to_uppercase(),
+                "headers" => parsed.headers.extend(Self::string_pairs(value, "headers")?),
+                "query" => parsed.query.extend(Self::string_pairs(value, "query")?),
+                "body" => {
+                    parsed.body = Some(match value.clone().try_cast::<Blob>() {
+                        Some(bytes) => bytes,
+                        None => value.to_string().into_bytes(),
+                    });
+                }
+                "json" => {
+                    let json = rhai_to_json_value(value)?;
+                    parsed.body = Some(json.to_string().into_bytes());
+                    parsed.headers.push(("Content-Type".to_string(), "application/json".to_string()));
+                }
+                "timeout_ms" => {
+                    let ms = value.as_int().map_err(|_| "timeout_ms must be an integer".to_string())?;
+                    parsed.timeout = Some(Duration::from_millis(ms.max(0) as u64));
+                }
+                "on_progress" => {
+                    parsed.on_progress = Some(value.try_cast::<FnPtr>()
+                        .ok_or_else(|| "on_progress must be a function".to_string())?);
+                }
+                other => return Err(format!("Unknown fetch option '{}'", other)),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn string_pairs(value: Dynamic, option: &str) -> Result<Vec<(String, String)>, String> {
+        let map = value.try_cast::<Map>()
+            .ok_or_else(|| format!("{} must be a map", option))?;
+        Ok(map.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve canned responses on a local port, one per incoming connection.
+    /// Each request line (e.g. "POST /items?id=1 HTTP/1.1") and body are sent back to the test.
+    fn mock_server(responses: Vec<String>) -> (String, std::sync::mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+                tx.send((request_line.trim().to_string(), String::from_utf8(body).unwrap())).unwrap();
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (address, rx)
+    }
+
+    fn http_response(status: &str, content_type: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nX-Test: yes\r\nConnection: close\r\n\r\n{}",
+            status, content_type, body.len(), body
+        )
+    }
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        HttpKit::register_functions(&mut engine);
+        engine
+    }
+
+    #[test]
+    fn test_fetch_json_post() {
+        let (address, requests) = mock_server(vec![
+            http_response("201 Created", "application/json", r#"{"id":7,"tags":["a","b"]}"#),
+        ]);
+
+        let mut scope = rhai::Scope::new();
+        scope.push("url", format!("{}/items", address));
+        let response = engine().eval_with_scope::<Map>(&mut scope, r#"
+            fetch(url, #{ method: "post", json: #{ name: "x" }, query: #{ id: 1 }, headers: #{ "X-Token": "t" } })
+        "#).unwrap();
+
+        assert_eq!(response["status"].as_int().unwrap(), 201);
+        assert!(response["ok"].as_bool().unwrap());
+        let headers = response["headers"].clone().cast::<Map>();
+        assert_eq!(headers["x-test"].clone().into_string().unwrap(), "yes");
+        let json = response["json"].clone().cast::<Map>();
+        assert_eq!(json["id"].as_int().unwrap(), 7);
+
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /items?id=1 HTTP/1.1");
+        assert_eq!(body, r#"{"name":"x"}"#);
+    }
+
+    #[test]
+    fn test_fetch_returns_error_status() {
+        let (address, _requests) = mock_server(vec![
+            http_response("404 Not Found", "text/plain", "404"),
+        ]);
+
+        let mut scope = rhai::Scope::new();
+        scope.push("url", address);
+        let response = engine().eval_with_scope::<Map>(&mut scope, "fetch(url)").unwrap();
+
+        assert_eq!(response["status"].as_int().unwrap(), 404);
+        assert!(!response["ok"].as_bool().unwrap());
+        assert_eq!(response["body"].clone().into_string().unwrap(), "404");
+        // Plain text is never parsed, even when it happens to be valid JSON
+        assert!(response["json"].is_unit());
+    }
+
+    #[test]
+    fn test_download_with_progress() {
+        let content = "x".repeat(DOWNLOAD_CHUNK_SIZE * 2 + 10);
+        let (address, _requests) = mock_server(vec![
+            http_response("200 OK", "application/octet-stream", &content),
+        ]);
+        let target = std::env::temp_dir()
+            .join(format!("snaprun_http_{}", uuid::Uuid::new_v4()))
+            .join("file.bin");
+
+        let mut scope = rhai::Scope::new();
+        scope.push("url", address);
+        scope.push("path", target.to_string_lossy().to_string());
+        let calls = engine().eval_with_scope::<i64>(&mut scope, r#"
+            let calls = 0;
+            let result = download(url, path, #{ on_progress: |p| { calls += 1; } });
+            if result.bytes != 131082 { throw "wrong size"; }
+            calls
+        "#).unwrap();
+
+        assert!(calls >= 1);
+        assert_eq!(fs::read_to_string(&target).unwrap(), content);
+        let _ = fs::remove_dir_all(target.parent().unwrap());
+    }
+}
@@ -0,0 +1,441 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map};
+use serde::{Deserialize, Serialize};
+use crate::fs_kit::copy_tree;
+use crate::trash::restore_from_trash;
+
+/// Opt-in journal of file system effects, so a script run can be undone with `undo_run`.
+///
+/// A run starts journaling with `start_journal()`. From then until the script finishes,
+/// FileSystemKit writes, moves and removals on the same thread are recorded; removed
+/// files are kept in the journal folder instead of being deleted.
+pub struct JournalKit;
+
+/// One recorded effect. `backup` points at the previous content kept in the run folder.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Effect {
+    /// A file was written; no backup means it did not exist before
+    Write { path: PathBuf, backup: Option<PathBuf> },
+    /// A file was moved, possibly replacing `to`
+    Move { from: PathBuf, to: PathBuf, backup: Option<PathBuf> },
+    /// A file or directory was removed; empty directories have no backup
+    Remove { path: PathBuf, backup: Option<PathBuf> },
+    /// An item was moved to the platform trash
+    Trash { path: PathBuf, trashed: PathBuf },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RunInfo {
+    run_id: String,
+    script: String,
+    started: String,
+    #[serde(default)]
+    finished: Option<String>,
+    #[serde(default)]
+    undone: bool,
+}
+
+struct ActiveJournal {
+    run_dir: PathBuf,
+    next_backup: usize,
+}
+
+thread_local! {
+    // The journal opened by start_journal() for the current run; empty when journaling is off
+    static ACTIVE: RefCell<Option<ActiveJournal>> = const { RefCell::new(None) };
+}
+
+impl JournalKit {
+    /// Register the journal functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine, script_name: Arc<Mutex<String>>) {
+        println!("🟣 JournalKit: Registering operation journal functions");
+
+        engine.register_fn("start_journal", move || -> Result<String, Box<EvalAltResult>> {
+            Ok(start(&journal_dir(), &script_name.lock().unwrap())?)
+        });
+        engine.register_fn("journal_active", is_active);
+        engine.register_fn("undo_run", |run_id: &str| -> Result<Map, Box<EvalAltResult>> {
+            Ok(undo_run(&journal_dir(), run_id)?)
+        });
+        engine.register_fn("journal_runs", || -> Result<Array, Box<EvalAltResult>> {
+            Ok(list_runs(&journal_dir())?.into_iter().map(|run| Dynamic::from(run_map(&run))).collect())
+        });
+
+        println!("🟣 JournalKit: All operation journal functions registered");
+    }
+}
+
+/// Folder holding one sub-folder per journaled run
+pub fn journal_dir() -> PathBuf {
+    match dirs::document_dir() {
+        Some(docs_dir) => docs_dir.join("SnapRun").join("Journal"),
+        None => PathBuf::from("./user_data/journal"),
+    }
+}
+
+/// Start journaling on this thread; returns the new run ID. Calling it again keeps the current run.
+pub fn start(base: &Path, script: &str) -> Result<String, String> {
+    if let Some(run_id) = ACTIVE.with(|active| active.borrow().as_ref().map(|journal| run_id_of(&journal.run_dir))) {
+        return Ok(run_id);
+    }
+
+    let run_id = format!("{}-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"), &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let run_dir = base.join(&run_id);
+    fs::create_dir_all(run_dir.join("backups")).map_err(|e| format!("Failed to create journal '{}': {}", run_dir.display(), e))?;
+    let info = RunInfo {
+        run_id: run_id.clone(),
+        script: script.to_string(),
+        started: chrono::Local::now().to_rfc3339(),
+        finished: None,
+        undone: false,
+    };
+    save_info(&run_dir, &info)?;
+
+    ACTIVE.with(|active| *active.borrow_mut() = Some(ActiveJournal { run_dir, next_backup: 0 }));
+    Ok(run_id)
+}
+
+/// Stop journaling on this thread (called by the runner when a script finishes)
+pub fn finish() {
+    let Some(journal) = ACTIVE.with(|active| active.borrow_mut().take()) else { return };
+    if let Ok(mut info) = load_info(&journal.run_dir) {
+        info.finished = Some(chrono::Local::now().to_rfc3339());
+        let _ = save_info(&journal.run_dir, &info);
+    }
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.with(|active| active.borrow().is_some())
+}
+
+fn run_id_of(run_dir: &Path) -> String {
+    run_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Run `f` with the active journal, if any
+fn with_active<T>(f: impl FnOnce(&mut ActiveJournal) -> T) -> Option<T> {
+    ACTIVE.with(|active| active.borrow_mut().as_mut().map(f))
+}
+
+impl ActiveJournal {
+    fn backup_path(&mut self) -> PathBuf {
+        self.next_backup += 1;
+        self.run_dir.join("backups").join(self.next_backup.to_string())
+    }
+
+    /// Copy an existing file aside; None if there was nothing to keep
+    fn keep_copy(&mut self, path: &Path) -> Option<PathBuf> {
+        if !path.is_file() {
+            return None;
+        }
+        let backup = self.backup_path();
+        match fs::copy(path, &backup) {
+            Ok(_) => Some(backup),
+            Err(e) => {
+                println!("❌ Journal could not back up '{}': {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    fn record(&self, effect: &Effect) {
+        let line = serde_json::to_string(effect).unwrap_or_default();
+        let result = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.run_dir.join("journal.jsonl"))
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = result {
+            println!("❌ Failed to write journal entry: {}", e);
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Run `op`, a write (or copy) that replaces or creates `path`, and record it if it succeeds.
+/// Any previous content is kept first and dropped again when `op` fails.
+pub fn write<T, E>(path: &Path, op: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let effect = with_active(|journal| {
+        let path = absolute(path);
+        let backup = journal.keep_copy(&path);
+        Effect::Write { path, backup }
+    });
+    settle(effect, op())
+}
+
+/// Run `op`, which moves `from` to `to`, and record it if it succeeds, keeping any file it replaces
+pub fn move_file<T, E>(from: &Path, to: &Path, op: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+    let effect = with_active(|journal| {
+        let (from, to) = (absolute(from), absolute(to));
+        let backup = journal.keep_copy(&to);
+        Effect::Move { from, to, backup }
+    });
+    settle(effect, op())
+}
+
+/// Record a prepared effect once its operation has succeeded, or delete its backup if it failed
+fn settle<T, E>(effect: Option<Effect>, result: Result<T, E>) -> Result<T, E> {
+    match (effect, &result) {
+        (Some(effect), Ok(_)) => {
+            with_active(|journal| journal.record(&effect));
+        }
+        (Some(Effect::Write { backup: Some(backup), .. } | Effect::Move { backup: Some(backup), .. }), Err(_)) => {
+            let _ = fs::remove_file(backup);
+        }
+        _ => {}
+    }
+    result
+}
+
+/// Remove a file or directory tree by moving it into the journal.
+/// Returns None when journaling is off, so the caller deletes it as usual.
+pub fn remove(path: &Path, dir: bool) -> Option<io::Result<()>> {
+    with_active(|journal| {
+        let path = absolute(path);
+        // Fail the same way fs::remove_file / fs::remove_dir_all would
+        if fs::symlink_metadata(&path)?.is_dir() != dir {
+            let reason = if dir { "Not a directory" } else { "Is a directory" };
+            return Err(io::Error::other(reason));
+        }
+        let backup = journal.backup_path();
+        if fs::rename(&path, &backup).is_err() {
+            // Different filesystem: copy aside, then delete
+            copy_tree(&path, &backup)?;
+            if path.is_dir() { fs::remove_dir_all(&path)? } else { fs::remove_file(&path)? }
+        }
+        journal.record(&Effect::Remove { path, backup: Some(backup) });
+        Ok(())
+    })
+}
+
+/// Record an empty directory that has been removed
+pub fn removed_empty_dir(path: &Path) {
+    with_active(|journal| journal.record(&Effect::Remove { path: absolute(path), backup: None }));
+}
+
+/// Record an item that has been moved to the platform trash
+pub fn trashed(path: &Path, trashed: &Path) {
+    with_active(|journal| journal.record(&Effect::Trash { path: absolute(path), trashed: trashed.to_path_buf() }));
+}
+
+fn load_info(run_dir: &Path) -> Result<RunInfo, String> {
+    let content = fs::read_to_string(run_dir.join("run.json")).map_err(|e| format!("Failed to read journal '{}': {}", run_dir.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid journal '{}': {}", run_dir.display(), e))
+}
+
+fn save_info(run_dir: &Path, info: &RunInfo) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(info).map_err(|e| e.to_string())?;
+    fs::write(run_dir.join("run.json"), content).map_err(|e| format!("Failed to write journal '{}': {}", run_dir.display(), e))
+}
+
+fn load_effects(run_dir: &Path) -> Result<Vec<Effect>, String> {
+    let content = match fs::read_to_string(run_dir.join("journal.jsonl")) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read journal: {}", e)),
+    };
+    content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(|e| format!("Invalid journal entry: {}", e)))
+        .collect()
+}
+
+fn save_effects(run_dir: &Path, effects: &[Effect]) -> Result<(), String> {
+    let mut content = String::new();
+    for effect in effects {
+        content.push_str(&serde_json::to_string(effect).map_err(|e| e.to_string())?);
+        content.push('\n');
+    }
+    fs::write(run_dir.join("journal.jsonl"), content).map_err(|e| format!("Failed to write journal '{}': {}", run_dir.display(), e))
+}
+
+/// Journaled runs, newest first
+fn list_runs(base: &Path) -> Result<Vec<(RunInfo, usize)>, String> {
+    let entries = match fs::read_dir(base) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read journal folder '{}': {}", base.display(), e)),
+    };
+    let mut runs: Vec<(RunInfo, usize)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let info = load_info(&entry.path()).ok()?;
+            let effects = load_effects(&entry.path()).map(|e| e.len()).unwrap_or(0);
+            Some((info, effects))
+        })
+        .collect();
+    runs.sort_by(|a, b| b.0.started.cmp(&a.0.started));
+    Ok(runs)
+}
+
+fn run_map((info, effects): &(RunInfo, usize)) -> Map {
+    let mut map = Map::new();
+    map.insert("run_id".into(), Dynamic::from(info.run_id.clone()));
+    map.insert("script".into(), Dynamic::from(info.script.clone()));
+    map.insert("started".into(), Dynamic::from(info.started.clone()));
+    map.insert("finished".into(), info.finished.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map.insert("undone".into(), Dynamic::from(info.undone));
+    map.insert("effects".into(), Dynamic::from(*effects as i64));
+    map
+}
+
+/// Reverse a run's effects, newest first. Restores what it can and reports the rest; effects
+/// that could not be reversed stay in the journal, so undo can be retried once they're fixed.
+pub fn undo_run(base: &Path, run_id: &str) -> Result<Map, String> {
+    if run_id.is_empty() || run_id.contains(['/', '\\']) || run_id.contains("..") {
+        return Err(format!("Invalid run ID '{}'", run_id));
+    }
+    let run_dir = base.join(run_id);
+    let mut info = load_info(&run_dir).map_err(|_| format!("No journal found for run '{}'", run_id))?;
+    if info.undone {
+        return Err(format!("Run '{}' has already been undone", run_id));
+    }
+    // Undoing the current run ends its journal first
+    if with_active(|journal| journal.run_dir == run_dir).unwrap_or(false) {
+        finish();
+    }
+
+    let mut restored = Array::new();
+    let mut failed = Array::new();
+    let mut remaining = Vec::new();
+    for effect in load_effects(&run_dir)?.iter().rev() {
+        let (path, result) = match effect {
+            Effect::Write { path, backup } => (path, match backup {
+                Some(backup) => put_back_copy(backup, path),
+                None => fs::remove_file(path).or_else(ignore_missing).map_err(|e| e.to_string()),
+            }),
+            Effect::Move { from, to, backup } => (from, put_back(to, from).and_then(|_| match backup {
+                Some(backup) => put_back_copy(backup, to),
+                None => Ok(()),
+            })),
+            Effect::Remove { path, backup } => (path, match backup {
+                Some(_) if fs::symlink_metadata(path).is_ok() => Err("something else now exists at this path".to_string()),
+                Some(backup) => put_back(backup, path),
+                None => fs::create_dir_all(path).map_err(|e| e.to_string()),
+            }),
+            Effect::Trash { path, trashed } => (path, restore_from_trash(trashed, path)),
+        };
+        let path = Dynamic::from(path.to_string_lossy().to_string());
+        match result {
+            Ok(()) => restored.push(path),
+            Err(error) => {
+                let mut item = Map::new();
+                item.insert("path".into(), path);
+                item.insert("error".into(), Dynamic::from(error));
+                failed.push(Dynamic::from(item));
+                remaining.push(effect.clone());
+            }
+        }
+    }
+
+    remaining.reverse();
+    save_effects(&run_dir, &remaining)?;
+    info.undone = remaining.is_empty();
+    save_info(&run_dir, &info)?;
+
+    let mut report = Map::new();
+    report.insert("run_id".into(), Dynamic::from(run_id.to_string()));
+    report.insert("restored".into(), Dynamic::from(restored));
+    report.insert("failed".into(), Dynamic::from(failed));
+    report.insert("undone".into(), Dynamic::from(info.undone));
+    Ok(report)
+}
+
+fn ignore_missing(e: io::Error) -> io::Result<()> {
+    if e.kind() == io::ErrorKind::NotFound { Ok(()) } else { Err(e) }
+}
+
+fn ensure_parent(path: &Path) -> Result<(), String> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Move `from` back to `to`
+fn put_back(from: &Path, to: &Path) -> Result<(), String> {
+    ensure_parent(to)?;
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_tree(from, to).map_err(|e| e.to_string())
+}
+
+/// Copy a kept file back over `to`, leaving the backup in place
+fn put_back_copy(backup: &Path, to: &Path) -> Result<(), String> {
+    ensure_parent(to)?;
+    fs::copy(backup, to).map(|_| ()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_and_undo() {
+        let dir = std::env::temp_dir().join(format!("snaprun_journal_{}", uuid::Uuid::new_v4()));
+        let (base, work) = (dir.join("journal"), dir.join("work"));
+        fs::create_dir_all(work.join("folder")).unwrap();
+        fs::write(work.join("config.json"), "{\"v\": 1}").unwrap();
+        fs::write(work.join("a.txt"), "a").unwrap();
+        fs::write(work.join("folder").join("inner.txt"), "inner").unwrap();
+
+        // Nothing is recorded until the journal is started
+        assert!(remove(&work.join("missing"), false).is_none());
+        let run_id = start(&base, "tests").unwrap();
+        assert_eq!(start(&base, "tests").unwrap(), run_id);
+
+        write(&work.join("config.json"), || fs::write(work.join("config.json"), "{\"v\": 2}")).unwrap();
+        write(&work.join("new.txt"), || fs::write(work.join("new.txt"), "new")).unwrap();
+        move_file(&work.join("a.txt"), &work.join("b.txt"), || fs::rename(work.join("a.txt"), work.join("b.txt"))).unwrap();
+        assert!(remove(&work.join("folder"), false).unwrap().is_err());
+        remove(&work.join("folder"), true).unwrap().unwrap();
+        assert!(!work.join("folder").exists());
+        let runs = list_runs(&base).unwrap();
+        assert_eq!((runs[0].0.script.as_str(), runs[0].1), ("tests", 4));
+
+        // Undoing the run in progress closes its journal
+        let report = undo_run(&base, &run_id).unwrap();
+        assert!(!is_active());
+        assert_eq!(report["restored"].clone().cast::<Array>().len(), 4);
+        assert_eq!(fs::read_to_string(work.join("config.json")).unwrap(), "{\"v\": 1}");
+        assert_eq!(fs::read_to_string(work.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(work.join("folder").join("inner.txt")).unwrap(), "inner");
+        assert!(!work.join("new.txt").exists() && !work.join("b.txt").exists());
+        assert!(undo_run(&base, &run_id).unwrap_err().contains("already been undone"));
+        assert!(undo_run(&base, "../etc").is_err());
+
+        // A failed restore keeps the run open so undo can be retried
+        let run_id = start(&base, "tests").unwrap();
+        remove(&work.join("a.txt"), false).unwrap().unwrap();
+        finish();
+        fs::write(work.join("a.txt"), "in the way").unwrap();
+        let report = undo_run(&base, &run_id).unwrap();
+        assert_eq!((report["failed"].clone().cast::<Array>().len(), report["undone"].as_bool().unwrap()), (1, false));
+        fs::remove_file(work.join("a.txt")).unwrap();
+        let report = undo_run(&base, &run_id).unwrap();
+        assert_eq!((report["restored"].clone().cast::<Array>().len(), report["undone"].as_bool().unwrap()), (1, true));
+        assert_eq!(fs::read_to_string(work.join("a.txt")).unwrap(), "a");
+
+        // A move that fails (e.g. across devices) is not recorded, so undo leaves both files alone
+        fs::write(work.join("b.txt"), "b").unwrap();
+        let run_id = start(&base, "tests").unwrap();
+        let failed = move_file(&work.join("a.txt"), &work.join("b.txt"), || Err::<(), _>(io::Error::other("cross-device link")));
+        assert!(failed.is_err());
+        finish();
+        let report = undo_run(&base, &run_id).unwrap();
+        assert_eq!((report["restored"].clone().cast::<Array>().len(), report["undone"].as_bool().unwrap()), (0, true));
+        assert_eq!(fs::read_to_string(work.join("a.txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(work.join("b.txt")).unwrap(), "b");
+        assert_eq!(fs::read_dir(base.join(&run_id).join("backups")).unwrap().count(), 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
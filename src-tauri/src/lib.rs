@@ -5,6 +5,27 @@ mod rhai_engine;
 mod script_manager;
 mod fs_kit;
 mod process_kit;
+mod http_kit;
+mod datetime_kit;
+mod string_kit;
+mod encoding_kit;
+mod env_kit;
+mod template_kit;
+mod data_format;
+mod markup_kit;
+mod archive_kit;
+mod package_kit;
+mod hash_kit;
+mod sync_kit;
+mod reader_kit;
+mod trash;
+mod journal;
+mod dry_run;
+mod store_kit;
+mod db_kit;
+mod secrets_kit;
+mod scaffold_kit;
+mod settings;
 mod logging;
 
 use ui_controller::{ui_response, demo_ui_controller};
@@ -12,6 +33,7 @@ use kits::{demo_kit_usage, ui_kit::Kit};
 use scripts::{greeting_script, html_demo_script};
 use rhai_engine::RhaiScriptRunner;
 use script_manager::{ScriptManager, ScriptInfo};
+use settings::Settings;
 use logging::{LogLevel, LogSource, get_logger};
 use tauri::Manager;
 use tauri::{
@@ -154,30 +176,10 @@ fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<TrayIcon<
         .build(app)
 }
 
-// List all available Rhai scripts
-#[tauri::command]
-async fn list_rhai_scripts() -> Result<Vec<ScriptInfo>, String> {
-    // Try to get WIN_SCRIPT2_PATH environment variable first
-    let user_scripts_path = match std::env::var("WIN_SCRIPT2_PATH") {
-        Ok(path) => {
-            println!("🟣 Using WIN_SCRIPT2_PATH: {}", path);
-            std::path::PathBuf::from(path)
-        },
-        Err(_) => {
-            // Fallback to current directory logic for development
-            let current_dir = std::env::current_dir()
-                .map_err(|e| format!("Failed to get current directory: {}", e))?;
-            
-            let fallback_path = if current_dir.file_name().and_then(|n| n.to_str()) == Some("src-tauri") {
-                current_dir.parent().unwrap().join("user_scripts")
-            } else {
-                current_dir.join("user_scripts")
-            };
-            
-            println!("🟣 WIN_SCRIPT2_PATH not found, using fallback: {:?}", fallback_path);
-            fallback_path
-        }
-    };
+/// Discover scripts from the folders configured in settings
+fn load_script_manager() -> Result<ScriptManager, String> {
+    let user_scripts_path = settings::current().scripts.bundled_scripts_dir();
+    println!("🟣 Using bundled scripts folder: {:?}", user_scripts_path);
     
     // ScriptManager expects project root, so get parent of user_scripts
     let project_root = user_scripts_path.parent()
@@ -186,58 +188,90 @@ async fn list_rhai_scripts() -> Result<Vec<ScriptInfo>, String> {
     
     let mut script_manager = ScriptManager::new(project_root);
     script_manager.load_scripts().map_err(|e| format!("Failed to load scripts: {}", e))?;
-    
-    Ok(script_manager.scripts)
+    Ok(script_manager)
+}
+
+// List all available Rhai scripts
+#[tauri::command]
+async fn list_rhai_scripts() -> Result<Vec<ScriptInfo>, String> {
+    Ok(load_script_manager()?.scripts)
+}
+
+// Script roots in priority order with their script counts, plus scripts hidden by same-named ones
+#[tauri::command]
+async fn list_script_roots() -> Result<serde_json::Value, String> {
+    let script_manager = load_script_manager()?;
+    let roots: Vec<serde_json::Value> = script_manager.roots.iter()
+        .map(|root| {
+            let count = script_manager.scripts.iter().filter(|script| script.category == root.label).count();
+            serde_json::json!({
+                "label": root.label,
+                "path": root.path,
+                "priority": root.priority,
+                "read_only": root.read_only,
+                "enabled": root.enabled,
+                "exists": root.path.is_dir(),
+                "scripts": count,
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({ "roots": roots, "shadowed": script_manager.shadowed }))
+}
+
+// Installed script packages
+#[tauri::command]
+async fn list_packages() -> Result<serde_json::Value, String> {
+    let packages: Vec<serde_json::Value> = package_kit::installed(&package_kit::packages_dir())?
+        .into_iter()
+        .map(|package| serde_json::json!({ "manifest": package.manifest, "path": package.path }))
+        .collect();
+    Ok(serde_json::Value::Array(packages))
+}
+
+// Install or upgrade a package from a folder or zip, with its dependencies
+#[tauri::command]
+async fn install_package(source: String, upgrade: bool, force: Option<bool>) -> Result<serde_json::Value, String> {
+    let mode = if upgrade { package_kit::InstallMode::Upgrade } else { package_kit::InstallMode::Install };
+    let changes = package_kit::install(&package_kit::packages_dir(), std::path::Path::new(&source), mode, force.unwrap_or(false))?;
+    Ok(serde_json::Value::Array(changes.into_iter()
+        .map(|change| serde_json::json!({ "name": change.name, "version": change.version, "previous": change.previous }))
+        .collect()))
+}
+
+#[tauri::command]
+async fn uninstall_package(name: String) -> Result<String, String> {
+    package_kit::uninstall(&package_kit::packages_dir(), &name).map(|version| version.to_string())
 }
 
 // Execute a Rhai script by its ID
 #[tauri::command]
-async fn run_rhai_script(script_id: String, app_handle: tauri::AppHandle) -> Result<String, String> {
+async fn run_rhai_script(script_id: String, dry_run: Option<bool>, app_handle: tauri::AppHandle) -> Result<String, String> {
     // Create Kit instance using the app handle
     let kit = Kit::new(app_handle);
     
-    // Try to get WIN_SCRIPT2_PATH environment variable first
-    let user_scripts_path = match std::env::var("WIN_SCRIPT2_PATH") {
-        Ok(path) => {
-            println!("🟣 Using WIN_SCRIPT2_PATH: {}", path);
-            std::path::PathBuf::from(path)
-        },
-        Err(_) => {
-            // Fallback to current directory logic for development
-            let current_dir = std::env::current_dir()
-                .map_err(|e| format!("Failed to get current directory: {}", e))?;
-            
-            let fallback_path = if current_dir.file_name().and_then(|n| n.to_str()) == Some("src-tauri") {
-                current_dir.parent().unwrap().join("user_scripts")
-            } else {
-                current_dir.join("user_scripts")
-            };
-            
-            println!("🟣 WIN_SCRIPT2_PATH not found, using fallback: {:?}", fallback_path);
-            fallback_path
-        }
-    };
-    
-    // ScriptManager expects project root, so get parent of user_scripts
-    let project_root = user_scripts_path.parent()
-        .ok_or_else(|| "Failed to get parent directory of user_scripts".to_string())?
-        .to_path_buf();
-    
     // Load scripts to find the requested one
-    let mut script_manager = ScriptManager::new(project_root);
-    script_manager.load_scripts().map_err(|e| format!("Failed to load scripts: {}", e))?;
+    let script_manager = load_script_manager()?;
     
-    let script_info = script_manager.scripts.iter()
-        .find(|s| s.id == script_id)
+    let script_info = script_manager.get_script_by_id(&script_id)
+        .cloned()
         .ok_or_else(|| format!("Script '{}' not found", script_id))?;
     
     // Read script content
     let script_content = std::fs::read_to_string(&script_info.file_path)
         .map_err(|e| format!("Failed to read script '{}': {}", script_id, e))?;
     
-    // Create Rhai runner and execute
+    // Create Rhai runner and execute, making other scripts callable via run_script
     let runner = RhaiScriptRunner::new(kit);
+    runner.set_script_id(&script_info.id);
+    runner.set_script_path(&script_info.file_path);
+    runner.set_script_manager(script_manager);
+    let sandbox = settings::current().sandbox;
+    runner.set_max_script_depth(sandbox.max_script_depth);
+    runner.set_journal(sandbox.journal_by_default);
+    let dry_run = dry_run.unwrap_or(sandbox.dry_run_by_default);
+    runner.set_dry_run(dry_run);
     match runner.run_script_with_name(&script_content, &script_info.name) {
+        Ok(_) if dry_run => Ok(format!("Dry run of '{}' finished; nothing was changed", script_info.name)),
         Ok(_) => Ok(format!("Script '{}' executed successfully", script_info.name)),
         Err(e) => Err(format!("Script execution failed: {}", e))
     }
@@ -274,6 +308,77 @@ async fn get_logs_directory() -> Result<String, String> {
     }
 }
 
+// Undo the file system effects recorded by a journaled script run
+#[tauri::command]
+async fn undo_run(run_id: String) -> Result<serde_json::Value, String> {
+    let report = journal::undo_run(&journal::journal_dir(), &run_id)?;
+    data_format::rhai_to_json_value(rhai::Dynamic::from(report))
+}
+
+// Settings as saved, the effective values after environment overrides, and which variables applied
+#[tauri::command]
+async fn get_settings() -> Result<serde_json::Value, String> {
+    let stored = settings::stored();
+    let mut effective = stored.clone();
+    let overrides: Vec<serde_json::Value> = effective.apply_env(|name| std::env::var(name).ok())
+        .into_iter()
+        .map(|(variable, setting)| serde_json::json!({ "variable": variable, "setting": setting }))
+        .collect();
+    let supported: Vec<&str> = settings::ENV_OVERRIDES.iter().map(|(variable, _)| *variable).collect();
+    Ok(serde_json::json!({
+        "settings": stored,
+        "effective": effective,
+        "overrides": overrides,
+        "supported_overrides": supported,
+        "path": settings::settings_path(),
+    }))
+}
+
+// Validate and save settings, applying the hotkey and log level right away
+#[tauri::command]
+async fn update_settings(settings: Settings, app_handle: tauri::AppHandle) -> Result<Settings, String> {
+    settings.validate()?;
+    let previous = settings::current().hotkeys.show_window;
+    let next = settings::effective(settings.clone()).hotkeys.show_window;
+    let hotkey_changed = next != previous;
+
+    // Take the new shortcut before saving, so a shortcut that can't be registered is never
+    // saved and the old one keeps working
+    if hotkey_changed {
+        register_show_hotkey(&app_handle, &next)?;
+    }
+    let effective = match settings::update(settings) {
+        Ok(effective) => effective,
+        Err(e) => {
+            if hotkey_changed {
+                let _ = app_handle.global_shortcut().unregister(next.as_str());
+            }
+            return Err(e);
+        }
+    };
+    if hotkey_changed {
+        let _ = app_handle.global_shortcut().unregister(previous.as_str());
+    }
+    logging::set_max_level(effective.logging.level.to_log_level());
+    Ok(effective)
+}
+
+/// Register the global shortcut that shows the window and returns to script search
+fn register_show_hotkey(app_handle: &tauri::AppHandle, shortcut: &str) -> Result<(), String> {
+    let shortcut_handle = app_handle.clone();
+    app_handle.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, _event| {
+        if let Some(window) = shortcut_handle.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+            
+            // Reset UI to script search page
+            if let Err(e) = window.emit("reset-to-script-search", ()) {
+                eprintln!("Failed to emit reset-to-script-search event: {}", e);
+            }
+        }
+    }).map_err(|e| format!("Failed to register global shortcut {}: {}", shortcut, e))
+}
+
 #[tauri::command]
 async fn test_all_scripts(app_handle: tauri::AppHandle) -> Result<String, String> {
     println!("🧪 Running script tests...");
@@ -329,6 +434,14 @@ pub fn run() {
             if let Err(e) = logging::init_logger() {
                 eprintln!("❌ Failed to initialize logging system: {}", e);
             }
+            let log_settings = settings::current().logging;
+            logging::set_max_level(log_settings.level.to_log_level());
+            if let Some(logger) = get_logger() {
+                let pruned = logger.prune_old_logs(log_settings.retention_days);
+                if pruned > 0 {
+                    println!("📋 Removed {} log file(s) older than {} days", pruned, log_settings.retention_days);
+                }
+            }
             
             let window = app.get_webview_window("main").unwrap();
             
@@ -341,36 +454,20 @@ pub fn run() {
             let _tray = create_tray(&app.handle())
                 .expect("Failed to create system tray");
 
-            // Register global shortcuts with error handling
-            let app_handle = app.handle().clone();
+            // Register the global shortcut from settings
+            let hotkey = settings::current().hotkeys.show_window;
             
             // Try to unregister the shortcut first in case it's already registered
-            let _ = app.global_shortcut().unregister("CmdOrCtrl+Shift+J");
-            
-            // Ctrl+Shift+J to show the window from tray
-            let shortcut_handle = app_handle.clone();
-            let _ = app.global_shortcut().on_shortcut("CmdOrCtrl+Shift+J", move |_app, _shortcut, _event| {
-                if let Some(window) = shortcut_handle.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                    
-                    // Reset UI to script search page
-                    if let Err(e) = window.emit("reset-to-script-search", ()) {
-                        eprintln!("Failed to emit reset-to-script-search event: {}", e);
-                    }
-                }
-            });
-            
-            // Register the shortcut with error handling
-            match app.global_shortcut().register("CmdOrCtrl+Shift+J") {
-                Ok(_) => println!("✅ Global shortcut Ctrl+Shift+J registered successfully"),
-                Err(e) => eprintln!("⚠️  Failed to register global shortcut Ctrl+Shift+J: {}", e),
+            let _ = app.global_shortcut().unregister(hotkey.as_str());
+            match register_show_hotkey(app.handle(), &hotkey) {
+                Ok(_) => println!("✅ Global shortcut {} registered successfully", hotkey),
+                Err(e) => eprintln!("⚠️  {}", e),
             }
 
             // Show window on first launch
             window.show().unwrap();
             
-            println!("🚀 SnapRun initialized with system tray and global shortcut (Ctrl+Shift+J)");
+            println!("🚀 SnapRun initialized with system tray and global shortcut ({})", hotkey);
 
             Ok(())
         })
@@ -384,7 +481,7 @@ pub fn run() {
                 _ => {}
             }
         })
-        .invoke_handler(tauri::generate_handler![greet, ui_response, demo_ui_controller, demo_kit_usage, greeting_script, html_demo_script, list_rhai_scripts, run_rhai_script, get_platform, reset_ui_state, log_frontend_message, get_logs_directory, test_all_scripts])
+        .invoke_handler(tauri::generate_handler![greet, ui_response, demo_ui_controller, demo_kit_usage, greeting_script, html_demo_script, list_rhai_scripts, list_script_roots, list_packages, install_package, uninstall_package, run_rhai_script, get_platform, reset_ui_state, log_frontend_message, get_logs_directory, test_all_scripts, undo_run, get_settings, update_settings])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
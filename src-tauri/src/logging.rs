@@ -1,9 +1,10 @@
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use chrono::{DateTime, Local};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 #[derive(Debug, Clone)]
 pub enum LogLevel {
@@ -25,6 +26,17 @@ impl LogLevel {
         }
     }
 
+    /// Lower is more severe
+    fn severity(&self) -> u8 {
+        match self {
+            LogLevel::Error => 0,
+            LogLevel::Warn => 1,
+            LogLevel::Info => 2,
+            LogLevel::Debug => 3,
+            LogLevel::Trace => 4,
+        }
+    }
+
     pub fn emoji(&self) -> &'static str {
         match self {
             LogLevel::Info => "ℹ️",
@@ -79,6 +91,40 @@ impl LogEntry {
     }
 }
 
+// Most verbose level that is still logged; everything until settings are applied
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(4);
+
+/// Drop entries less severe than `level`
+pub fn set_max_level(level: LogLevel) {
+    MAX_LEVEL.store(level.severity(), Ordering::Relaxed);
+}
+
+// Secret values handed to scripts; they are masked in every log line
+static REDACTED_VALUES: OnceLock<Mutex<BTreeSet<String>>> = OnceLock::new();
+
+/// Values shorter than this are not redacted, since masking them would mangle ordinary text
+const MIN_REDACTED_LEN: usize = 4;
+
+/// Mask `value` in all future log output
+pub fn register_secret(value: &str) {
+    if value.chars().count() >= MIN_REDACTED_LEN {
+        if let Ok(mut values) = REDACTED_VALUES.get_or_init(|| Mutex::new(BTreeSet::new())).lock() {
+            values.insert(value.to_string());
+        }
+    }
+}
+
+/// Replace every registered secret value in `message` with `***`
+pub fn redact(message: &str) -> String {
+    let Some(Ok(values)) = REDACTED_VALUES.get().map(|values| values.lock()) else {
+        return message.to_string();
+    };
+    // Longest first, so a secret containing another one is masked whole
+    let mut sorted: Vec<&String> = values.iter().collect();
+    sorted.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    sorted.iter().fold(message.to_string(), |text, value| text.replace(value.as_str(), "***"))
+}
+
 pub struct SnapRunLogger {
     logs_dir: PathBuf,
     file_handles: Arc<Mutex<HashMap<String, std::fs::File>>>,
@@ -111,11 +157,14 @@ impl SnapRunLogger {
     }
 
     pub fn log(&self, level: LogLevel, source: LogSource, message: &str, script_context: Option<String>) {
+        if level.severity() > MAX_LEVEL.load(Ordering::Relaxed) {
+            return;
+        }
         let entry = LogEntry {
             timestamp: Local::now(),
             level: level.clone(),
             source: source.clone(),
-            message: message.to_string(),
+            message: redact(message),
             script_context: script_context.clone(),
         };
 
@@ -224,6 +273,21 @@ impl SnapRunLogger {
         self.console_enabled = enabled;
     }
 
+    /// Delete log files not written to for `retention_days`; returns how many were removed
+    pub fn prune_old_logs(&self, retention_days: u32) -> usize {
+        let max_age = std::time::Duration::from_secs(u64::from(retention_days) * 24 * 60 * 60);
+        let Ok(entries) = fs::read_dir(&self.logs_dir) else { return 0 };
+        let open = self.file_handles.lock().map(|handles| handles.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        entries.flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
+            .filter(|entry| !open.contains(&entry.file_name().to_string_lossy().to_string()))
+            .filter(|entry| entry.metadata().and_then(|m| m.modified()).ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > max_age))
+            .filter(|entry| fs::remove_file(entry.path()).is_ok())
+            .count()
+    }
+
     pub fn get_logs_directory_path(&self) -> &Path {
         &self.logs_dir
     }
@@ -0,0 +1,648 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use ego_tree::{NodeId, NodeRef};
+use html5ever::{Attribute, LocalName, Namespace, QualName};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map};
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// HTML and XML parsing with CSS selectors and XPath-style queries
+pub struct MarkupKit;
+
+/// Parsed document; XML is loaded into the same tree type so CSS selectors work on both
+struct Document {
+    tree: Html,
+    /// HTML tag and attribute names are matched case-insensitively
+    is_html: bool,
+}
+
+impl Document {
+    fn root(&self) -> NodeId {
+        self.tree.tree.root().id()
+    }
+
+    fn node(&self, id: NodeId) -> NodeRef<'_, Node> {
+        self.tree.tree.get(id).expect("node id belongs to this document")
+    }
+
+    fn name(&self, id: NodeId) -> Option<&str> {
+        self.node(id).value().as_element().map(|element| element.name())
+    }
+
+    fn attr(&self, id: NodeId, attr: &str) -> Option<&str> {
+        self.node(id).value().as_element()?
+            .attrs()
+            .find(|(k, _)| self.names_equal(k, attr))
+            .map(|(_, v)| v)
+    }
+
+    fn element_children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.node(id).children().filter(|c| c.value().is_element()).map(|c| c.id())
+    }
+
+    fn text(&self, id: NodeId) -> String {
+        self.node(id).descendants()
+            .filter_map(|d| d.value().as_text().map(|text| &**text))
+            .collect()
+    }
+
+    fn names_equal(&self, a: &str, b: &str) -> bool {
+        if self.is_html { a.eq_ignore_ascii_case(b) } else { a == b }
+    }
+
+    /// Elements below `id` that match `selector`, in document order
+    fn select(&self, id: NodeId, selector: &Selector) -> Vec<NodeId> {
+        let scope = ElementRef::wrap(self.node(id));
+        self.node(id).descendants()
+            .skip(1)
+            .filter_map(ElementRef::wrap)
+            .filter(|element| selector.matches_with_scope(element, scope))
+            .map(|element| element.id())
+            .collect()
+    }
+
+    /// Position of every node in document order; tree ids follow insertion order,
+    /// which the HTML parser does not keep when it moves misplaced content
+    fn document_order(&self) -> HashMap<NodeId, usize> {
+        self.tree.tree.root().descendants().enumerate().map(|(i, n)| (n.id(), i)).collect()
+    }
+
+    fn write_markup(&self, id: NodeId, out: &mut String) {
+        let node = self.node(id);
+        match node.value() {
+            Node::Document | Node::Fragment => node.children().for_each(|c| self.write_markup(c.id(), out)),
+            Node::Text(text) => out.push_str(&escape_markup(text, false)),
+            Node::Element(element) => {
+                let name = element.name();
+                out.push('<');
+                out.push_str(name);
+                for (key, value) in element.attrs() {
+                    out.push_str(&format!(" {}=\"{}\"", key, escape_markup(value, true)));
+                }
+                if !node.has_children() && (!self.is_html || is_void_element(name)) {
+                    out.push_str(if self.is_html { ">" } else { "/>" });
+                    return;
+                }
+                out.push('>');
+                node.children().for_each(|c| self.write_markup(c.id(), out));
+                out.push_str(&format!("</{}>", name));
+            }
+            // Doctype, comments and processing instructions are not part of the queryable markup
+            _ => {}
+        }
+    }
+}
+
+fn escape_markup(text: &str, attribute: bool) -> String {
+    let escaped = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    if attribute { escaped.replace('"', "&quot;") } else { escaped }
+}
+
+fn is_void_element(name: &str) -> bool {
+    matches!(name, "area" | "base" | "br" | "col" | "embed" | "hr" | "img" | "input" | "link" | "meta" | "source" | "track" | "wbr")
+}
+
+/// Handle to a node of a parsed document; the document itself is the root node
+#[derive(Clone)]
+pub struct MarkupNode {
+    doc: Rc<Document>,
+    id: NodeId,
+}
+
+impl MarkupNode {
+    fn at(&self, id: NodeId) -> Self {
+        Self { doc: self.doc.clone(), id }
+    }
+
+    fn nodes(&self, ids: Vec<NodeId>) -> Array {
+        ids.into_iter().map(|id| Dynamic::from(self.at(id))).collect()
+    }
+
+    fn tag(&self) -> String {
+        self.doc.name(self.id).unwrap_or_default().to_string()
+    }
+
+    fn attrs(&self) -> Map {
+        match self.doc.node(self.id).value().as_element() {
+            Some(element) => element.attrs()
+                .map(|(k, v)| (k.into(), Dynamic::from(v.to_string())))
+                .collect(),
+            None => Map::new(),
+        }
+    }
+
+    fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        map.insert("tag".into(), Dynamic::from(self.tag()));
+        map.insert("attrs".into(), Dynamic::from(self.attrs()));
+        map.insert("text".into(), Dynamic::from(self.doc.text(self.id)));
+        let children: Array = self.doc.element_children(self.id)
+            .map(|c| Dynamic::from(self.at(c).to_map()))
+            .collect();
+        map.insert("children".into(), Dynamic::from(children));
+        map
+    }
+
+    fn outer_html(&self) -> String {
+        let mut out = String::new();
+        self.doc.write_markup(self.id, &mut out);
+        out
+    }
+
+    fn inner_html(&self) -> String {
+        let mut out = String::new();
+        for child in self.doc.node(self.id).children() {
+            self.doc.write_markup(child.id(), &mut out);
+        }
+        out
+    }
+
+    fn select(&self, css: &str) -> Result<Vec<NodeId>, String> {
+        let selector = Selector::parse(css).map_err(|e| format!("Invalid CSS selector '{}': {}", css, e))?;
+        Ok(self.doc.select(self.id, &selector))
+    }
+
+    fn xpath(&self, expr: &str) -> Result<Array, String> {
+        let path = XPath::parse(expr)?;
+        Ok(path.evaluate(self))
+    }
+}
+
+impl std::fmt::Display for MarkupNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.doc.node(self.id).value() {
+            Node::Element(element) => write!(f, "<{}>", element.name()),
+            Node::Text(text) => write!(f, "{:?}", &**text),
+            _ => write!(f, "<#document>"),
+        }
+    }
+}
+
+impl MarkupKit {
+    /// Register all markup parsing functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 MarkupKit: Registering HTML/XML functions");
+
+        engine.register_type_with_name::<MarkupNode>("Node");
+        engine.register_fn("parse_html", |html: &str| -> MarkupNode {
+            Self::parse_html(html)
+        });
+        engine.register_fn("parse_xml", |xml: &str| -> Result<MarkupNode, Box<EvalAltResult>> {
+            Ok(Self::parse_xml(xml)?)
+        });
+
+        engine.register_fn("select", |node: &mut MarkupNode, css: &str| -> Result<Array, Box<EvalAltResult>> {
+            let ids = node.select(css)?;
+            Ok(node.nodes(ids))
+        });
+        engine.register_fn("select_first", |node: &mut MarkupNode, css: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            let ids = node.select(css)?;
+            Ok(ids.first().map(|&id| Dynamic::from(node.at(id))).unwrap_or(Dynamic::UNIT))
+        });
+        engine.register_fn("xpath", |node: &mut MarkupNode, expr: &str| -> Result<Array, Box<EvalAltResult>> {
+            Ok(node.xpath(expr)?)
+        });
+
+        engine.register_fn("text", |node: &mut MarkupNode| node.doc.text(node.id));
+        engine.register_fn("attr", |node: &mut MarkupNode, name: &str| -> Dynamic {
+            node.doc.attr(node.id, name).map(|v| Dynamic::from(v.to_string())).unwrap_or(Dynamic::UNIT)
+        });
+        engine.register_fn("attrs", |node: &mut MarkupNode| node.attrs());
+        engine.register_fn("children", |node: &mut MarkupNode| -> Array {
+            let ids = node.doc.element_children(node.id).collect();
+            node.nodes(ids)
+        });
+        engine.register_fn("parent", |node: &mut MarkupNode| -> Dynamic {
+            node.doc.node(node.id).parent().map(|p| Dynamic::from(node.at(p.id()))).unwrap_or(Dynamic::UNIT)
+        });
+        engine.register_fn("html", |node: &mut MarkupNode| node.outer_html());
+        engine.register_fn("inner_html", |node: &mut MarkupNode| node.inner_html());
+        engine.register_fn("to_map", |node: &mut MarkupNode| node.to_map());
+        engine.register_get("tag", |node: &mut MarkupNode| node.tag());
+
+        engine.register_fn("to_string", |node: &mut MarkupNode| node.to_string());
+        engine.register_fn("to_debug", |node: &mut MarkupNode| node.to_string());
+
+        println!("🟣 MarkupKit: All HTML/XML functions registered");
+    }
+
+    /// Parse an HTML document (lenient, like a browser)
+    pub fn parse_html(html: &str) -> MarkupNode {
+        let doc = Document { tree: Html::parse_document(html), is_html: true };
+        let id = doc.root();
+        MarkupNode { doc: Rc::new(doc), id }
+    }
+
+    /// Parse a well-formed XML document; element names are matched by local name
+    pub fn parse_xml(xml: &str) -> Result<MarkupNode, String> {
+        let parsed = roxmltree::Document::parse(xml).map_err(|e| format!("XML parse error: {}", e))?;
+        let mut doc = Document { tree: Html::new_document(), is_html: false };
+        let root = doc.root();
+        for child in parsed.root().children() {
+            Self::copy_xml_node(&mut doc.tree, root, child);
+        }
+        Ok(MarkupNode { doc: Rc::new(doc), id: root })
+    }
+
+    fn copy_xml_node(tree: &mut Html, parent: NodeId, node: roxmltree::Node) {
+        let qual_name = |namespace: Option<&str>, local: &str| {
+            QualName::new(None, Namespace::from(namespace.unwrap_or_default()), LocalName::from(local))
+        };
+        let value = if node.is_text() {
+            Node::Text(scraper::node::Text { text: node.text().unwrap_or_default().into() })
+        } else if node.is_element() {
+            let attrs = node.attributes()
+                .map(|a| Attribute { name: qual_name(a.namespace(), a.name()), value: a.value().into() })
+                .collect();
+            Node::Element(scraper::node::Element::new(qual_name(node.tag_name().namespace(), node.tag_name().name()), attrs))
+        } else {
+            return;
+        };
+        let id = tree.tree.get_mut(parent).expect("parent was just inserted").append(value).id();
+        for child in node.children() {
+            Self::copy_xml_node(tree, id, child);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// XPath subset: `/`, `//`, `.`, `..`, `*`, local names, `@attr`, `@*`, `text()`,
+// predicates `[n]`, `[last()]`, `[@a]`, `[@a='v']`, `[child='v']`, `[text()='v']`,
+// `[contains(x, 'v')]`, `[starts-with(x, 'v')]`.
+// Anything else (axes, namespace prefixes, unions, other functions or operators)
+// is rejected with an error rather than guessed at.
+// ---------------------------------------------------------------------------
+
+enum StepTest {
+    SelfNode,
+    Parent,
+    Name(String),
+    Attribute(String),
+    Text,
+}
+
+struct Step {
+    descendant: bool,
+    test: StepTest,
+    predicates: Vec<Predicate>,
+}
+
+enum Operand {
+    Attribute(String),
+    Text,
+    Child(String),
+}
+
+enum Predicate {
+    Position(usize),
+    Last,
+    Exists(Operand),
+    Equals(Operand, String, bool),
+    Contains(Operand, String),
+    StartsWith(Operand, String),
+}
+
+struct XPath {
+    absolute: bool,
+    steps: Vec<Step>,
+}
+
+/// A plain XML local name; prefixed names would silently never match the local names we store
+fn is_local_name(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c))
+}
+
+impl XPath {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let invalid = || format!("Unsupported XPath expression '{}'", expr);
+        let expr = expr.trim();
+        let absolute = expr.starts_with('/');
+        let mut steps = Vec::new();
+        let mut rest = expr;
+
+        while !rest.is_empty() {
+            let descendant = if let Some(after) = rest.strip_prefix("//") {
+                rest = after;
+                true
+            } else {
+                rest = rest.strip_prefix('/').unwrap_or(rest);
+                false
+            };
+
+            let end = Self::step_end(rest);
+            let step_text = &rest[..end];
+            rest = &rest[end..];
+            if step_text.is_empty() {
+                return Err(invalid());
+            }
+
+            let (test_text, mut predicate_text) = match step_text.find('[') {
+                Some(index) => step_text.split_at(index),
+                None => (step_text, ""),
+            };
+            if test_text.contains(':') {
+                return Err(format!("XPath '{}': axes and namespace prefixes are not supported ('{}'); match on the local name instead", expr, test_text));
+            }
+            let test = match test_text {
+                "." => StepTest::SelfNode,
+                ".." => StepTest::Parent,
+                "text()" => StepTest::Text,
+                "@*" => StepTest::Attribute("*".to_string()),
+                "*" => StepTest::Name("*".to_string()),
+                name if name.strip_prefix('@').is_some_and(is_local_name) => StepTest::Attribute(name[1..].to_string()),
+                name if is_local_name(name) => StepTest::Name(name.to_string()),
+                _ => return Err(invalid()),
+            };
+
+            let mut predicates = Vec::new();
+            while let Some(after) = predicate_text.strip_prefix('[') {
+                let close = Self::matching_bracket(after).ok_or_else(invalid)?;
+                predicates.push(Self::parse_predicate(after[..close].trim()).ok_or_else(invalid)?);
+                predicate_text = &after[close + 1..];
+            }
+            if !predicate_text.is_empty() {
+                return Err(invalid());
+            }
+            if !predicates.is_empty() && matches!(test, StepTest::Attribute(_) | StepTest::Text) {
+                return Err(format!("XPath '{}': predicates on @attribute and text() steps are not supported", expr));
+            }
+            steps.push(Step { descendant, test, predicates });
+        }
+
+        if steps.is_empty() && !absolute {
+            return Err(invalid());
+        }
+        let last = steps.len().saturating_sub(1);
+        if steps.iter().enumerate().any(|(i, s)| i != last && matches!(s.test, StepTest::Attribute(_) | StepTest::Text)) {
+            return Err(format!("XPath '{}': @attribute and text() are only allowed as the last step", expr));
+        }
+        Ok(Self { absolute, steps })
+    }
+
+    /// End of the current step: the next `/` outside of predicates
+    fn step_end(text: &str) -> usize {
+        let mut depth = 0;
+        let mut quote: Option<char> = None;
+        for (i, c) in text.char_indices() {
+            match (quote, c) {
+                (Some(q), _) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '[') => depth += 1,
+                (None, ']') => depth -= 1,
+                (None, '/') if depth == 0 => return i,
+                _ => {}
+            }
+        }
+        text.len()
+    }
+
+    fn matching_bracket(text: &str) -> Option<usize> {
+        let mut depth = 0;
+        let mut quote: Option<char> = None;
+        for (i, c) in text.char_indices() {
+            match (quote, c) {
+                (Some(q), _) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '[') => depth += 1,
+                (None, ']') if depth == 0 => return Some(i),
+                (None, ']') => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Byte offset of `pattern` outside of quoted literals
+    fn find_unquoted(text: &str, pattern: &str) -> Option<usize> {
+        let mut quote: Option<char> = None;
+        for (i, c) in text.char_indices() {
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if text[i..].starts_with(pattern) => return Some(i),
+                None => {}
+            }
+        }
+        None
+    }
+
+    /// Split on `separator` outside of quoted literals
+    fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+        let mut parts = Vec::new();
+        let mut rest = text;
+        while let Some(index) = Self::find_unquoted(rest, separator.encode_utf8(&mut [0; 4])) {
+            parts.push(&rest[..index]);
+            rest = &rest[index + separator.len_utf8()..];
+        }
+        parts.push(rest);
+        parts
+    }
+
+    fn parse_operand(text: &str) -> Option<Operand> {
+        let text = text.trim();
+        if text == "text()" || text == "." {
+            Some(Operand::Text)
+        } else if let Some(name) = text.strip_prefix('@').filter(|name| is_local_name(name)) {
+            Some(Operand::Attribute(name.to_string()))
+        } else if is_local_name(text) {
+            Some(Operand::Child(text.to_string()))
+        } else {
+            None
+        }
+    }
+
+    /// A quoted string literal; XPath 1.0 has no escapes, so the literal cannot contain its own quote
+    fn parse_literal(text: &str) -> Option<String> {
+        let text = text.trim();
+        let quote = text.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let inner = text.strip_prefix(quote)?.strip_suffix(quote)?;
+        if inner.contains(quote) { None } else { Some(inner.to_string()) }
+    }
+
+    fn parse_predicate(text: &str) -> Option<Predicate> {
+        if let Ok(position) = text.parse::<usize>() {
+            return Some(Predicate::Position(position));
+        }
+        if text == "last()" {
+            return Some(Predicate::Last);
+        }
+        for (function, is_contains) in [("contains(", true), ("starts-with(", false)] {
+            if let Some(args) = text.strip_prefix(function).and_then(|t| t.strip_suffix(')')) {
+                let parts = Self::split_unquoted(args, ',');
+                let [operand, literal] = parts.as_slice() else { return None };
+                let operand = Self::parse_operand(operand)?;
+                let literal = Self::parse_literal(literal)?;
+                return Some(if is_contains { Predicate::Contains(operand, literal) } else { Predicate::StartsWith(operand, literal) });
+            }
+        }
+        if let Some(index) = Self::find_unquoted(text, "!=") {
+            return Some(Predicate::Equals(Self::parse_operand(&text[..index])?, Self::parse_literal(&text[index + 2..])?, false));
+        }
+        if let Some(index) = Self::find_unquoted(text, "=") {
+            return Some(Predicate::Equals(Self::parse_operand(&text[..index])?, Self::parse_literal(&text[index + 1..])?, true));
+        }
+        Self::parse_operand(text).map(Predicate::Exists)
+    }
+
+    fn evaluate(&self, start: &MarkupNode) -> Array {
+        let doc = &start.doc;
+        let order = doc.document_order();
+        let mut context = vec![if self.absolute { doc.root() } else { start.id }];
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let is_last = index + 1 == self.steps.len();
+            match &step.test {
+                StepTest::Attribute(name) if is_last => {
+                    let mut values = Array::new();
+                    for id in Self::expand(doc, &order, &context, step.descendant) {
+                        if let Some(element) = doc.node(id).value().as_element() {
+                            values.extend(element.attrs()
+                                .filter(|(k, _)| name == "*" || doc.names_equal(k, name))
+                                .map(|(_, v)| Dynamic::from(v.to_string())));
+                        }
+                    }
+                    return values;
+                }
+                StepTest::Text if is_last => {
+                    let mut values = Array::new();
+                    for id in Self::expand(doc, &order, &context, step.descendant) {
+                        values.extend(doc.node(id).children()
+                            .filter_map(|c| c.value().as_text().map(|t| Dynamic::from(t.to_string()))));
+                    }
+                    return values;
+                }
+                _ => {}
+            }
+
+            let mut next = Vec::new();
+            for id in Self::expand(doc, &order, &context, step.descendant) {
+                let candidates: Vec<NodeId> = match &step.test {
+                    StepTest::SelfNode => vec![id],
+                    StepTest::Parent => doc.node(id).parent().map(|p| p.id()).into_iter().collect(),
+                    StepTest::Name(name) => doc.element_children(id)
+                        .filter(|&c| name == "*" || doc.name(c).is_some_and(|n| doc.names_equal(n, name)))
+                        .collect(),
+                    StepTest::Attribute(_) | StepTest::Text => Vec::new(),
+                };
+                next.extend(Self::apply_predicates(doc, candidates, &step.predicates));
+            }
+            context = Self::in_document_order(&order, next);
+        }
+
+        start.nodes(context)
+    }
+
+    fn in_document_order(order: &HashMap<NodeId, usize>, nodes: Vec<NodeId>) -> Vec<NodeId> {
+        let mut unique: Vec<NodeId> = nodes.into_iter().collect::<HashSet<_>>().into_iter().collect();
+        unique.sort_unstable_by_key(|id| order[id]);
+        unique
+    }
+
+    /// `//` means the step applies to every descendant-or-self of the context nodes
+    fn expand(doc: &Document, order: &HashMap<NodeId, usize>, context: &[NodeId], descendant: bool) -> Vec<NodeId> {
+        if !descendant {
+            return context.to_vec();
+        }
+        let expanded = context.iter()
+            .flat_map(|&id| doc.node(id).descendants())
+            .filter(|n| !n.value().is_text())
+            .map(|n| n.id())
+            .collect();
+        Self::in_document_order(order, expanded)
+    }
+
+    fn apply_predicates(doc: &Document, mut nodes: Vec<NodeId>, predicates: &[Predicate]) -> Vec<NodeId> {
+        for predicate in predicates {
+            let count = nodes.len();
+            nodes = nodes.into_iter().enumerate().filter(|(position, id)| match predicate {
+                Predicate::Position(n) => position + 1 == *n,
+                Predicate::Last => position + 1 == count,
+                Predicate::Exists(operand) => !Self::operand_values(doc, *id, operand).is_empty(),
+                Predicate::Equals(operand, value, equal) => {
+                    Self::operand_values(doc, *id, operand).iter().any(|v| v == value) == *equal
+                }
+                Predicate::Contains(operand, value) => Self::operand_values(doc, *id, operand).iter().any(|v| v.contains(value.as_str())),
+                Predicate::StartsWith(operand, value) => Self::operand_values(doc, *id, operand).iter().any(|v| v.starts_with(value.as_str())),
+            }).map(|(_, id)| id).collect();
+        }
+        nodes
+    }
+
+    fn operand_values(doc: &Document, id: NodeId, operand: &Operand) -> Vec<String> {
+        match operand {
+            Operand::Attribute(name) => doc.attr(id, name).map(str::to_string).into_iter().collect(),
+            Operand::Text => vec![doc.text(id)],
+            Operand::Child(name) => doc.element_children(id)
+                .filter(|&c| doc.name(c).is_some_and(|n| doc.names_equal(n, name)))
+                .map(|c| doc.text(c))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        MarkupKit::register_functions(&mut engine);
+        engine
+    }
+
+    #[test]
+    fn test_html_css_selectors() {
+        let engine = engine();
+        let result = engine.eval::<String>(r#"
+            let doc = parse_html("<ul id='todo'><li class='done big'>Ship</li><li><a href='/docs'>Docs</a></li></ul><p>Other</p>");
+            let items = doc.select("ul#todo > li");
+            let done = doc.select(".done").map(|n| n.text());
+            let link = doc.select_first("li a[href^='/']");
+            items.len() + "|" + done[0] + "|" + link.attr("href") + "|" + link.parent().tag + "|" +
+                doc.select("li:last-child, p").len() + "|" + doc.select("ul p").len()
+        "#).unwrap();
+        assert_eq!(result, "2|Ship|/docs|li|2|0");
+
+        let result = engine.eval::<String>(r#"
+            let doc = parse_html("<ol><li>1</li><li title='a]b'>2</li><li>3</li><li>4</li></ol><p>after</p>");
+            let odd = doc.select("li:nth-child(2n+1)").map(|n| n.text());
+            let next = doc.select("li + li:not(:last-child)").map(|n| n.text());
+            odd + "|" + next + "|" + doc.select_first("[title=\"a]b\"]").text() + "|" + doc.select("ol ~ p").len()
+        "#).unwrap();
+        assert_eq!(result, "[\"1\", \"3\"]|[\"2\", \"3\"]|2|1");
+    }
+
+    #[test]
+    fn test_xml_xpath_and_children() {
+        let engine = engine();
+        let result = engine.eval::<String>(r#"
+            let doc = parse_xml("<config><server name='a' port='80'><host>one</host></server><server name='b' port='8080'><host>two</host></server></config>");
+            let ports = doc.xpath("//server/@port");
+            let second = doc.xpath("/config/server[@name='b']/host/text()");
+            let last = doc.xpath("//server[last()]")[0];
+            let with_host = doc.xpath("//server[host='one']");
+            ports + "|" + second[0] + "|" + last.attr("name") + "|" + with_host.len() + "|" +
+                doc.children()[0].children().len() + "|" + last.to_map().children[0].text + "|" +
+                doc.select("server[name=b] > host")[0].text()
+        "#).unwrap();
+        assert_eq!(result, "[\"80\", \"8080\"]|two|b|1|2|two|two");
+    }
+
+    #[test]
+    fn test_invalid_input_errors() {
+        let engine = engine();
+        assert!(engine.eval::<Array>(r#"parse_xml("<a><b></a>").children()"#).is_err());
+        assert!(engine.eval::<Array>(r#"parse_html("<p>x</p>").select("p >")"#).is_err());
+        for expr in ["//a/@x/b", "child::a", "//ns:a", "//a | //b", "count(//a)", "//a[@x > 1]", "//a/@x[1]"] {
+            let script = format!(r#"parse_xml("<a x='1'/>").xpath("{}")"#, expr);
+            assert!(engine.eval::<Array>(&script).is_err(), "{} should be rejected", expr);
+        }
+        let found = engine.eval::<i64>(r#"parse_xml("<a x='1=2'/>").xpath("//a[@x='1=2']").len()"#).unwrap();
+        assert_eq!(found, 1);
+        let html = engine.eval::<String>(r#"parse_html("<p title='a&quot;b'>1 &lt; 2<br></p>").select_first("p").html()"#).unwrap();
+        assert_eq!(html, "<p title=\"a&quot;b\">1 &lt; 2<br></p>");
+    }
+}
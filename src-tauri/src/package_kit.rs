@@ -0,0 +1,591 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use crate::archive_kit::ArchiveKit;
+use crate::dry_run;
+use crate::fs_kit::copy_tree;
+use crate::process_kit::ProcessKit;
+use crate::settings::{ScriptRoot, PACKAGE_LABEL_PREFIX, PACKAGE_ROOT_PRIORITY};
+use crate::trash;
+
+/// File at the top of every package
+pub const MANIFEST_FILE: &str = "snaprun.toml";
+
+/// `snaprun.toml`: what a package is, what it ships and what it needs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub name: String,
+    pub version: Version,
+    #[serde(default)]
+    pub description: String,
+    /// Scripts to list, relative to the package folder
+    pub scripts: Vec<PathBuf>,
+    /// SnapRun versions the package works with, e.g. ">=1.0"
+    #[serde(default)]
+    pub snaprun: Option<VersionReq>,
+    /// Other packages by name, with a version requirement
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, VersionReq>,
+    /// Commands that must be on PATH, checked like `command_exists`
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+/// A package in the packages folder
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    pub manifest: Manifest,
+    pub path: PathBuf,
+}
+
+/// A package that could be installed: its manifest and the folder or zip it comes from
+#[derive(Debug, Clone)]
+struct Candidate {
+    manifest: Manifest,
+    source: PathBuf,
+}
+
+/// One package written by an install or upgrade
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstalledChange {
+    pub name: String,
+    pub version: Version,
+    /// Version that was replaced, if any
+    pub previous: Option<Version>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstallMode {
+    /// The package must not be installed yet
+    Install,
+    /// The package must be installed with an older version
+    Upgrade,
+}
+
+/// Script packages for Rhai scripts: install, upgrade, uninstall and list
+pub struct PackageKit;
+
+impl PackageKit {
+    /// Register the package functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 PackageKit: Registering package functions");
+
+        engine.register_fn("package_install", |source: &str| -> Result<Array, Box<EvalAltResult>> {
+            Self::install_from_script(source, InstallMode::Install, Map::new())
+        });
+        engine.register_fn("package_install", |source: &str, options: Map| -> Result<Array, Box<EvalAltResult>> {
+            Self::install_from_script(source, InstallMode::Install, options)
+        });
+        engine.register_fn("package_upgrade", |source: &str| -> Result<Array, Box<EvalAltResult>> {
+            Self::install_from_script(source, InstallMode::Upgrade, Map::new())
+        });
+        engine.register_fn("package_upgrade", |source: &str, options: Map| -> Result<Array, Box<EvalAltResult>> {
+            Self::install_from_script(source, InstallMode::Upgrade, options)
+        });
+        engine.register_fn("package_uninstall", |name: &str| -> Result<String, Box<EvalAltResult>> {
+            if dry_run::intercept("package_uninstall", || name.to_string()) {
+                return Ok(String::new());
+            }
+            Ok(uninstall(&packages_dir(), name)?.to_string())
+        });
+        engine.register_fn("package_list", || -> Result<Array, Box<EvalAltResult>> {
+            Ok(installed(&packages_dir())?.iter().map(|package| Dynamic::from(package_map(package))).collect())
+        });
+
+        println!("🟣 PackageKit: All package functions registered");
+    }
+
+    fn install_from_script(source: &str, mode: InstallMode, options: Map) -> Result<Array, Box<EvalAltResult>> {
+        let mut force = false;
+        for (key, value) in options {
+            match key.as_str() {
+                "force" => force = value.as_bool().map_err(|_| "Package option 'force' must be true or false".to_string())?,
+                other => return Err(format!("Unknown package option '{}'", other).into()),
+            }
+        }
+        let function = if mode == InstallMode::Install { "package_install" } else { "package_upgrade" };
+        if dry_run::intercept(function, || source.to_string()) {
+            return Ok(Array::new());
+        }
+        let changes = install(&packages_dir(), Path::new(source), mode, force)?;
+        Ok(changes.into_iter().map(|change| {
+            let mut item_map = Map::new();
+            item_map.insert("name".into(), Dynamic::from(change.name));
+            item_map.insert("version".into(), Dynamic::from(change.version.to_string()));
+            item_map.insert("previous".into(), change.previous.map(|v| Dynamic::from(v.to_string())).unwrap_or(Dynamic::UNIT));
+            Dynamic::from(item_map)
+        }).collect())
+    }
+}
+
+/// Rhai map describing an installed package
+pub fn package_map(package: &InstalledPackage) -> Map {
+    let manifest = &package.manifest;
+    let mut item_map = Map::new();
+    item_map.insert("name".into(), Dynamic::from(manifest.name.clone()));
+    item_map.insert("version".into(), Dynamic::from(manifest.version.to_string()));
+    item_map.insert("description".into(), Dynamic::from(manifest.description.clone()));
+    item_map.insert("path".into(), Dynamic::from(package.path.to_string_lossy().to_string()));
+    let scripts: Array = manifest.scripts.iter().map(|script| Dynamic::from(script.to_string_lossy().to_string())).collect();
+    item_map.insert("scripts".into(), Dynamic::from(scripts));
+    let dependencies: Map = manifest.dependencies.iter().map(|(name, req)| (name.into(), Dynamic::from(req.to_string()))).collect();
+    item_map.insert("dependencies".into(), Dynamic::from(dependencies));
+    let commands: Array = manifest.commands.iter().cloned().map(Dynamic::from).collect();
+    item_map.insert("commands".into(), Dynamic::from(commands));
+    item_map
+}
+
+/// Installed packages live in Documents/SnapRun/Packages, one folder per package
+pub fn packages_dir() -> PathBuf {
+    match dirs::document_dir() {
+        Some(docs_dir) => docs_dir.join("SnapRun").join("Packages"),
+        None => PathBuf::from("./user_data/Packages"),
+    }
+}
+
+/// The version of SnapRun that `snaprun` requirements are checked against
+fn snaprun_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).unwrap_or_else(|_| Version::new(0, 0, 0))
+}
+
+impl Manifest {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let manifest: Manifest = toml::from_str(text).map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !is_valid_name(&self.name) {
+            return Err(format!("Invalid package name '{}': use letters, digits, '-' and '_'", self.name));
+        }
+        if self.scripts.is_empty() {
+            return Err(format!("Package '{}' lists no scripts", self.name));
+        }
+        for script in &self.scripts {
+            let inside = script.components().all(|component| matches!(component, Component::Normal(_)));
+            if !inside || script.extension().is_none_or(|ext| ext != "rhai") {
+                return Err(format!("Package '{}': script '{}' must be a .rhai file inside the package", self.name, script.display()));
+            }
+        }
+        if let Some(dependency) = self.dependencies.keys().find(|name| !is_valid_name(name) || **name == self.name) {
+            return Err(format!("Package '{}': invalid dependency '{}'", self.name, dependency));
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Read the manifest of a package folder or zip. In a zip it may sit at the top or in one top-level folder.
+pub fn read_manifest(source: &Path) -> Result<Manifest, String> {
+    let text = if is_zip(source) {
+        let file = File::open(source).map_err(|e| format!("Failed to open '{}': {}", source.display(), e))?;
+        let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Failed to read archive '{}': {}", source.display(), e))?;
+        let name = zip_manifest_name(zip.file_names()).ok_or_else(|| format!("'{}' has no {}", source.display(), MANIFEST_FILE))?;
+        let mut text = String::new();
+        zip.by_name(&name)
+            .map_err(|e| format!("Failed to read archive '{}': {}", source.display(), e))?
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Failed to read {} in '{}': {}", MANIFEST_FILE, source.display(), e))?;
+        text
+    } else {
+        fs::read_to_string(source.join(MANIFEST_FILE)).map_err(|e| format!("'{}' has no readable {}: {}", source.display(), MANIFEST_FILE, e))?
+    };
+    Manifest::parse(&text).map_err(|e| format!("{}: {}", source.display(), e))
+}
+
+fn zip_manifest_name<'a>(names: impl Iterator<Item = &'a str>) -> Option<String> {
+    names
+        .filter(|name| {
+            let parts: Vec<&str> = name.split('/').collect();
+            parts.len() <= 2 && parts.last() == Some(&MANIFEST_FILE)
+        })
+        .min_by_key(|name| name.len())
+        .map(str::to_string)
+}
+
+/// Every valid package in `dir`, by name. Folders with a broken manifest are skipped with a warning.
+pub fn installed(dir: &Path) -> Result<Vec<InstalledPackage>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read '{}': {}", dir.display(), e)),
+    };
+    let mut packages = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden || !path.is_dir() {
+            continue;
+        }
+        match read_manifest(&path) {
+            Ok(manifest) => packages.push(InstalledPackage { manifest, path }),
+            Err(e) => println!("⚠️ PackageKit: Skipping {}: {}", path.display(), e),
+        }
+    }
+    packages.sort_by(|a, b| a.manifest.name.cmp(&b.manifest.name));
+    Ok(packages)
+}
+
+/// A read-only script root for every installed package, listing only the scripts its manifest names.
+/// Roots are labelled `pkg:<name>` so their script IDs never collide with other roots.
+pub fn package_roots(dir: &Path) -> Vec<ScriptRoot> {
+    installed(dir).unwrap_or_else(|e| {
+        println!("⚠️ PackageKit: {}", e);
+        Vec::new()
+    })
+    .into_iter()
+    .map(|package| ScriptRoot {
+        label: format!("{}{}", PACKAGE_LABEL_PREFIX, package.manifest.name),
+        path: package.path,
+        priority: PACKAGE_ROOT_PRIORITY,
+        read_only: true,
+        enabled: true,
+        scripts: Some(package.manifest.scripts),
+    })
+    .collect()
+}
+
+/// Packages next to `source` (folders and zips in the same folder) that can satisfy dependencies
+fn candidates_near(source: &Path) -> Vec<Candidate> {
+    let Some(parent) = source.parent().filter(|parent| parent.is_dir()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() || is_zip(path))
+        .filter_map(|path| Some(Candidate { manifest: read_manifest(&path).ok()?, source: path }))
+        .collect()
+}
+
+/// Install (or upgrade) the package at `source` into `dir`, along with any dependencies it needs
+/// from packages next to it. Nothing is written when a version conflict is found.
+pub fn install(dir: &Path, source: &Path, mode: InstallMode, force: bool) -> Result<Vec<InstalledChange>, String> {
+    let manifest = read_manifest(source)?;
+    let installed: BTreeMap<String, InstalledPackage> = installed(dir)?
+        .into_iter()
+        .map(|package| (package.manifest.name.clone(), package))
+        .collect();
+
+    match (mode, installed.get(&manifest.name)) {
+        (InstallMode::Install, Some(current)) if !force => {
+            return Err(format!("{} {} is already installed; use package_upgrade", manifest.name, current.manifest.version));
+        }
+        (InstallMode::Upgrade, None) => {
+            return Err(format!("{} is not installed", manifest.name));
+        }
+        (InstallMode::Upgrade, Some(current)) if !force && manifest.version <= current.manifest.version => {
+            return Err(format!("{} {} is not newer than the installed {}", manifest.name, manifest.version, current.manifest.version));
+        }
+        _ => {}
+    }
+
+    let available = candidates_near(source);
+    let mut plan = Vec::new();
+    add_to_plan(Candidate { manifest, source: source.to_path_buf() }, &installed, &available, &mut plan)?;
+    check_dependants(&plan, &installed)?;
+    for candidate in &plan {
+        check_requirements(&candidate.manifest)?;
+    }
+
+    // Stage everything first so a bad package leaves the installed ones untouched
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    let mut staged = Vec::new();
+    for candidate in &plan {
+        match stage(dir, candidate) {
+            Ok(path) => staged.push(path),
+            Err(e) => {
+                clear_staging(dir, &plan);
+                return Err(e);
+            }
+        }
+    }
+
+    // Dependencies go in before the packages that need them. Replaced versions are kept
+    // aside until every package is in place, so a failure can put them all back.
+    let mut swapped: Vec<&str> = Vec::new();
+    for (candidate, package_root) in plan.iter().zip(&staged).rev() {
+        if let Err(e) = swap_in(dir, &candidate.manifest.name, package_root) {
+            for name in swapped.iter().rev() {
+                roll_back(dir, name);
+            }
+            clear_staging(dir, &plan);
+            return Err(e);
+        }
+        swapped.push(&candidate.manifest.name);
+    }
+    clear_staging(dir, &plan);
+
+    let mut changes = Vec::new();
+    for candidate in plan.iter().rev() {
+        let name = &candidate.manifest.name;
+        let backup = backup_dir(dir, name);
+        if backup.exists() {
+            if let Err(e) = trash::move_to_trash(&backup) {
+                println!("⚠️ PackageKit: The previous {} is left in {}: {}", name, backup.display(), e);
+            }
+        }
+        println!("🟣 PackageKit: Installed {} {}", name, candidate.manifest.version);
+        let previous = installed.get(name).map(|package| package.manifest.version.clone());
+        changes.push(InstalledChange { name: name.clone(), version: candidate.manifest.version.clone(), previous });
+    }
+    Ok(changes)
+}
+
+/// Move the installed `name` aside to its backup folder and the staged package into its place
+fn swap_in(dir: &Path, name: &str, package_root: &Path) -> Result<(), String> {
+    let target = dir.join(name);
+    let backup = backup_dir(dir, name);
+    // Left over from an install that was interrupted after the swap
+    if backup.exists() {
+        trash::move_to_trash(&backup)?;
+    }
+    if target.exists() {
+        fs::rename(&target, &backup).map_err(|e| format!("Failed to move the installed {} aside: {}", name, e))?;
+    }
+    if let Err(e) = fs::rename(package_root, &target) {
+        if backup.exists() {
+            let _ = fs::rename(&backup, &target);
+        }
+        return Err(format!("Failed to install {}: {}", name, e));
+    }
+    Ok(())
+}
+
+/// Undo `swap_in`: drop the new package and put the previous one back
+fn roll_back(dir: &Path, name: &str) {
+    let target = dir.join(name);
+    let backup = backup_dir(dir, name);
+    let _ = fs::remove_dir_all(&target);
+    if backup.exists() {
+        if let Err(e) = fs::rename(&backup, &target) {
+            println!("❌ PackageKit: Could not restore {} from {}: {}", name, backup.display(), e);
+        }
+    }
+}
+
+fn clear_staging(dir: &Path, plan: &[Candidate]) {
+    for candidate in plan {
+        let _ = fs::remove_dir_all(staging_dir(dir, &candidate.manifest.name));
+    }
+}
+
+/// Add `candidate` and, recursively, every dependency that is not installed in a matching version
+fn add_to_plan(candidate: Candidate, installed: &BTreeMap<String, InstalledPackage>, available: &[Candidate], plan: &mut Vec<Candidate>) -> Result<(), String> {
+    let name = candidate.manifest.name.clone();
+    let dependencies = candidate.manifest.dependencies.clone();
+    plan.push(candidate);
+
+    for (dependency, req) in dependencies {
+        if let Some(planned) = plan.iter().find(|c| c.manifest.name == dependency) {
+            if !req.matches(&planned.manifest.version) {
+                return Err(format!("Version conflict: {} requires {} {}, but {} {} is being installed", name, dependency, req, dependency, planned.manifest.version));
+            }
+            continue;
+        }
+        let current = installed.get(&dependency).map(|package| &package.manifest.version);
+        if current.is_some_and(|version| req.matches(version)) {
+            continue;
+        }
+        let best = available.iter()
+            .filter(|c| c.manifest.name == dependency && req.matches(&c.manifest.version))
+            .max_by(|a, b| a.manifest.version.cmp(&b.manifest.version));
+        match (best, current) {
+            (Some(best), _) => add_to_plan(best.clone(), installed, available, plan)?,
+            (None, Some(version)) => {
+                return Err(format!("Version conflict: {} requires {} {}, but {} is installed and no matching version was found", name, dependency, req, version));
+            }
+            (None, None) => {
+                return Err(format!("{} requires {} {}, which is not installed and was not found next to the package", name, dependency, req));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Installed packages that stay must still accept the versions being installed
+fn check_dependants(plan: &[Candidate], installed: &BTreeMap<String, InstalledPackage>) -> Result<(), String> {
+    let replaced = |name: &str| plan.iter().any(|c| c.manifest.name == name);
+    for package in installed.values().filter(|package| !replaced(&package.manifest.name)) {
+        for (dependency, req) in &package.manifest.dependencies {
+            if let Some(planned) = plan.iter().find(|c| &c.manifest.name == dependency) {
+                if !req.matches(&planned.manifest.version) {
+                    return Err(format!(
+                        "Version conflict: installed {} {} requires {} {}, which {} {} does not satisfy",
+                        package.manifest.name, package.manifest.version, dependency, req, dependency, planned.manifest.version
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_requirements(manifest: &Manifest) -> Result<(), String> {
+    if let Some(req) = &manifest.snaprun {
+        let version = snaprun_version();
+        if !req.matches(&version) {
+            return Err(format!("{} {} needs SnapRun {}, this is {}", manifest.name, manifest.version, req, version));
+        }
+    }
+    let missing: Vec<&str> = manifest.commands.iter()
+        .map(String::as_str)
+        .filter(|command| !ProcessKit::command_exists_sync(command))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("{} {} needs commands that are not on PATH: {}", manifest.name, manifest.version, missing.join(", ")));
+    }
+    Ok(())
+}
+
+fn staging_dir(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!(".staging-{}", name))
+}
+
+fn backup_dir(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!(".previous-{}", name))
+}
+
+/// Copy or extract a package into its staging folder; returns the folder holding the manifest
+fn stage(dir: &Path, candidate: &Candidate) -> Result<PathBuf, String> {
+    let staging = staging_dir(dir, &candidate.manifest.name);
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| format!("Failed to clear '{}': {}", staging.display(), e))?;
+    }
+    let package_root = if is_zip(&candidate.source) {
+        ArchiveKit::extract_zip(&candidate.source, &staging)?;
+        if staging.join(MANIFEST_FILE).is_file() {
+            staging.clone()
+        } else {
+            // Archives made from a folder keep that folder as their only top-level entry
+            fs::read_dir(&staging).map_err(|e| e.to_string())?
+                .flatten()
+                .map(|entry| entry.path())
+                .find(|path| path.join(MANIFEST_FILE).is_file())
+                .ok_or_else(|| format!("'{}' has no {}", candidate.source.display(), MANIFEST_FILE))?
+        }
+    } else {
+        copy_tree(&candidate.source, &staging).map_err(|e| format!("Failed to copy '{}': {}", candidate.source.display(), e))?;
+        staging.clone()
+    };
+    if let Some(script) = candidate.manifest.scripts.iter().find(|script| !package_root.join(script).is_file()) {
+        return Err(format!("Package '{}' lists '{}', which it does not contain", candidate.manifest.name, script.display()));
+    }
+    Ok(package_root)
+}
+
+/// Remove an installed package (to the trash); refused while other packages depend on it
+pub fn uninstall(dir: &Path, name: &str) -> Result<Version, String> {
+    let packages = installed(dir)?;
+    let package = packages.iter()
+        .find(|package| package.manifest.name == name)
+        .ok_or_else(|| format!("{} is not installed", name))?;
+    let dependants: Vec<&str> = packages.iter()
+        .filter(|other| other.manifest.dependencies.contains_key(name))
+        .map(|other| other.manifest.name.as_str())
+        .collect();
+    if !dependants.is_empty() {
+        return Err(format!("Cannot uninstall {}: required by {}", name, dependants.join(", ")));
+    }
+    trash::move_to_trash(&package.path)?;
+    println!("🟣 PackageKit: Uninstalled {} {}", name, package.manifest.version);
+    Ok(package.manifest.version.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_package(dir: &Path, folder: &str, manifest: &str, scripts: &[&str]) -> PathBuf {
+        let path = dir.join(folder);
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join(MANIFEST_FILE), manifest).unwrap();
+        for script in scripts {
+            fs::write(path.join(script), "print(\"hi\");").unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_manifest_validation() {
+        let manifest = Manifest::parse("name = \"git-tools\"\nversion = \"1.2.0\"\nsnaprun = \">=1.0\"\nscripts = [\"status.rhai\"]\ncommands = [\"git\"]\n[dependencies]\nshared = \"^0.3\"\n").unwrap();
+        assert_eq!(manifest.version, Version::new(1, 2, 0));
+        assert!(manifest.dependencies["shared"].matches(&Version::new(0, 3, 4)));
+
+        assert!(Manifest::parse("name = \"../x\"\nversion = \"1.0.0\"\nscripts = [\"a.rhai\"]\n").unwrap_err().contains("Invalid package name"));
+        assert!(Manifest::parse("name = \"x\"\nversion = \"1.0.0\"\nscripts = [\"../a.rhai\"]\n").unwrap_err().contains("inside the package"));
+        assert!(Manifest::parse("name = \"x\"\nversion = \"one\"\nscripts = [\"a.rhai\"]\n").is_err());
+        assert!(Manifest::parse("name = \"x\"\nversion = \"1.0.0\"\nscripts = [\"a.rhai\"]\nauthor = \"me\"\n").is_err());
+    }
+
+    #[test]
+    fn test_install_with_dependencies_and_conflicts() {
+        let base = std::env::temp_dir().join(format!("snaprun_packages_{}", uuid::Uuid::new_v4()));
+        let repo = base.join("repo");
+        let dir = base.join("installed");
+        write_package(&repo, "shared-0.3", "name = \"shared\"\nversion = \"0.3.1\"\nscripts = [\"shared.rhai\"]\n", &["shared.rhai"]);
+        write_package(&repo, "shared-0.4", "name = \"shared\"\nversion = \"0.4.0\"\nscripts = [\"shared.rhai\"]\n", &["shared.rhai"]);
+        let tools = write_package(&repo, "tools", "name = \"tools\"\nversion = \"1.0.0\"\nscripts = [\"deploy.rhai\"]\n[dependencies]\nshared = \"^0.3\"\n", &["deploy.rhai"]);
+
+        // The dependency is picked from the folder next to the package, highest matching version
+        let changes = install(&dir, &tools, InstallMode::Install, false).unwrap();
+        let names: Vec<String> = changes.iter().map(|c| format!("{} {}", c.name, c.version)).collect();
+        assert_eq!(names, vec!["shared 0.3.1", "tools 1.0.0"]);
+        assert!(install(&dir, &tools, InstallMode::Install, false).unwrap_err().contains("already installed"));
+
+        // Upgrading shared to 0.4 would break tools
+        let shared_04 = repo.join("shared-0.4");
+        assert!(install(&dir, &shared_04, InstallMode::Upgrade, false).unwrap_err().contains("Version conflict: installed tools"));
+        assert!(uninstall(&dir, "shared").unwrap_err().contains("required by tools"));
+
+        // A package needing something nobody has
+        let lonely = write_package(&base.join("elsewhere"), "lonely", "name = \"lonely\"\nversion = \"0.1.0\"\nscripts = [\"a.rhai\"]\n[dependencies]\nghost = \"1\"\n", &["a.rhai"]);
+        assert!(install(&dir, &lonely, InstallMode::Install, false).unwrap_err().contains("not found"));
+
+        let roots = package_roots(&dir);
+        let labels: Vec<&str> = roots.iter().map(|root| root.label.as_str()).collect();
+        assert_eq!(labels, vec!["pkg:shared", "pkg:tools"]);
+        assert!(roots.iter().all(|root| root.read_only && root.priority == PACKAGE_ROOT_PRIORITY));
+
+        // Zips made from a package folder keep the folder as their top-level entry
+        let archive = base.join("tools.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("tools/snaprun.toml", zip::write::SimpleFileOptions::default()).unwrap();
+        std::io::Write::write_all(&mut zip, b"name = \"tools\"\nversion = \"1.1.0\"\nscripts = [\"deploy.rhai\"]\n").unwrap();
+        zip.finish().unwrap();
+        assert_eq!(read_manifest(&archive).unwrap().version, Version::new(1, 1, 0));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn test_swap_rolls_back_to_the_installed_version() {
+        let base = std::env::temp_dir().join(format!("snaprun_packages_{}", uuid::Uuid::new_v4()));
+        let dir = base.join("installed");
+        let tools = write_package(&base.join("v1"), "tools", "name = \"tools\"\nversion = \"1.0.0\"\nscripts = [\"deploy.rhai\"]\n", &["deploy.rhai"]);
+        install(&dir, &tools, InstallMode::Install, false).unwrap();
+
+        // A swap that is rolled back leaves the installed version in place
+        let staged = write_package(&base, "staged", "name = \"tools\"\nversion = \"2.0.0\"\nscripts = [\"deploy.rhai\"]\n", &["deploy.rhai"]);
+        swap_in(&dir, "tools", &staged).unwrap();
+        assert_eq!(read_manifest(&dir.join("tools")).unwrap().version, Version::new(2, 0, 0));
+        roll_back(&dir, "tools");
+        assert_eq!(read_manifest(&dir.join("tools")).unwrap().version, Version::new(1, 0, 0));
+        assert!(!backup_dir(&dir, "tools").exists() && !staged.exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
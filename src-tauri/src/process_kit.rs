@@ -3,6 +3,7 @@ use std::io::{BufRead, BufReader};
 use std::thread;
 use std::sync::mpsc;
 use rhai::{Engine, Dynamic, Map};
+use crate::{dry_run, env_kit};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -59,6 +60,10 @@ impl ProcessKit {
     
     /// Run a simple command and return the output as a string
     pub fn run_command_sync(command: &str) -> String {
+        if dry_run::intercept("run_command", || command.to_string()) {
+            return String::new();
+        }
+        
         match Self::execute_command_internal(command, &[]) {
             Ok(result) => {
                 if result.success {
@@ -74,6 +79,10 @@ impl ProcessKit {
     
     /// Run a command with arguments and return the output
     pub fn run_command_with_args_sync(command: &str, args: rhai::Array) -> String {
+        if dry_run::intercept("run_command_with_args", || format!("{} {:?}", command, args)) {
+            return String::new();
+        }
+        
         let string_args: Vec<String> = args.into_iter()
             .map(|arg| arg.to_string())
             .collect();
@@ -94,7 +103,12 @@ impl ProcessKit {
     
     /// Execute a command and return detailed result information
     pub fn exec_command_sync(command: &str) -> Map {
-        let result = Self::execute_command_internal(command, &[])
+        let result = if dry_run::intercept("exec_command", || command.to_string()) {
+            Ok(ProcessResult { stdout: String::new(), stderr: String::new(), exit_code: 0, success: true })
+        } else {
+            Self::execute_command_internal(command, &[])
+        };
+        let result = result
             .unwrap_or_else(|e| ProcessResult {
                 stdout: String::new(),
                 stderr: e.to_string(),
@@ -112,6 +126,10 @@ impl ProcessKit {
     
     /// Run a shell command (cross-platform)
     pub fn shell_command_sync(command: &str) -> String {
+        if dry_run::intercept("shell_command", || command.to_string()) {
+            return String::new();
+        }
+        
         let (shell, flag) = if cfg!(target_os = "windows") {
             ("cmd", "/C")
         } else {
@@ -133,6 +151,10 @@ impl ProcessKit {
     
     /// Spawn a process without waiting for it to complete
     pub fn spawn_process_sync(command: &str) -> String {
+        if dry_run::intercept("spawn_process", || command.to_string()) {
+            return "Process spawned with PID: 0".to_string();
+        }
+        
         let parts: Vec<&str> = command.split_whitespace().collect();
         if parts.is_empty() {
             return "Error: Empty command".to_string();
@@ -149,6 +171,7 @@ impl ProcessKit {
             
         // Configure for silent execution on Windows
         Self::configure_silent_command(&mut command_builder);
+        env_kit::apply(&mut command_builder);
         
         match command_builder.spawn() {
             Ok(child) => format!("Process spawned with PID: {}", child.id()),
@@ -176,6 +199,7 @@ impl ProcessKit {
         
         // Configure for silent execution on Windows
         Self::configure_silent_command(&mut cmd);
+        env_kit::apply(&mut cmd);
         
         let mut child = cmd.spawn()?;
         
@@ -227,7 +251,8 @@ impl ProcessKit {
     
     /// Internal function to find command path
     fn which_command_internal(command: &str) -> Option<String> {
-        if let Ok(path) = std::env::var("PATH") {
+        // Honour a PATH changed with env_set
+        if let Some(path) = env_kit::get("PATH") {
             let separator = if cfg!(target_os = "windows") { ";" } else { ":" };
             let extensions = if cfg!(target_os = "windows") {
                 vec!["", ".exe", ".cmd", ".bat"]
@@ -0,0 +1,333 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map, FnPtr, NativeCallContext};
+
+/// Streaming line reading, `tail` and `tail -f` style following for large files
+pub struct ReaderKit;
+
+/// How long `follow` runs when the script doesn't set `timeout_ms`. Runs can't be cancelled
+/// from the UI, so an unbounded follow would block the runner for good.
+const DEFAULT_FOLLOW_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Open file handle returned by `open_reader`. Copies share the same position.
+#[derive(Clone)]
+pub struct FileReader {
+    path: PathBuf,
+    reader: Arc<Mutex<Option<BufReader<File>>>>,
+}
+
+struct FollowOptions {
+    /// Stop after this long in total
+    timeout: Duration,
+    /// Stop after delivering this many lines
+    max_lines: Option<usize>,
+    /// Start with the existing content instead of only new lines
+    from_start: bool,
+    poll_interval: Duration,
+}
+
+impl FollowOptions {
+    fn from_map(options: Map) -> Result<Self, String> {
+        let mut parsed = Self { timeout: DEFAULT_FOLLOW_TIMEOUT, max_lines: None, from_start: false, poll_interval: Duration::from_millis(250) };
+        for (key, value) in options {
+            let number = |value: &Dynamic| value.as_int().ok().filter(|n| *n >= 0).ok_or_else(|| format!("Follow option '{}' must be a non-negative integer", key));
+            match key.as_str() {
+                "timeout_ms" => parsed.timeout = Duration::from_millis(number(&value)? as u64),
+                "max_lines" => parsed.max_lines = Some(number(&value)? as usize),
+                "poll_ms" => parsed.poll_interval = Duration::from_millis(number(&value)?.max(10) as u64),
+                "from_start" => parsed.from_start = value.as_bool().map_err(|_| "Follow option 'from_start' must be true or false".to_string())?,
+                other => return Err(format!("Unknown follow option '{}'", other)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl ReaderKit {
+    /// Register the streaming reader functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 ReaderKit: Registering streaming file reader functions");
+
+        engine.register_type_with_name::<FileReader>("FileReader");
+        engine.register_iterator::<FileReader>();
+        engine.register_fn("open_reader", |path: &str| -> Result<FileReader, Box<EvalAltResult>> { Ok(FileReader::open(Path::new(path))?) });
+        engine.register_fn("read_line", |reader: &mut FileReader| -> Result<Dynamic, Box<EvalAltResult>> {
+            Ok(reader.read_line()?.map(Dynamic::from).unwrap_or(Dynamic::UNIT))
+        });
+        engine.register_fn("read_lines", |reader: &mut FileReader, count: i64| -> Result<Array, Box<EvalAltResult>> {
+            let mut lines = Array::new();
+            while lines.len() < count.max(0) as usize {
+                match reader.read_line()? {
+                    Some(line) => lines.push(Dynamic::from(line)),
+                    None => break,
+                }
+            }
+            Ok(lines)
+        });
+        // `for line in reader.lines()` streams the remaining lines
+        engine.register_fn("lines", |reader: &mut FileReader| reader.clone());
+        engine.register_fn("seek", |reader: &mut FileReader, offset: i64| -> Result<i64, Box<EvalAltResult>> { Ok(reader.seek(offset)?) });
+        engine.register_get("position", |reader: &mut FileReader| -> Result<i64, Box<EvalAltResult>> { Ok(reader.position()?) });
+        engine.register_get("size", |reader: &mut FileReader| fs::metadata(&reader.path).map(|m| m.len() as i64).unwrap_or(-1));
+        engine.register_get("path", |reader: &mut FileReader| reader.path.to_string_lossy().to_string());
+        engine.register_fn("close", |reader: &mut FileReader| { reader.reader.lock().unwrap().take(); });
+        engine.register_fn("to_string", |reader: &mut FileReader| format!("FileReader(\"{}\")", reader.path.display()));
+        engine.register_fn("to_debug", |reader: &mut FileReader| format!("FileReader(\"{}\")", reader.path.display()));
+
+        engine.register_fn("tail", |path: &str, count: i64| -> Result<Array, Box<EvalAltResult>> {
+            Ok(Self::tail(Path::new(path), count.max(0) as usize)?.into_iter().map(Dynamic::from).collect())
+        });
+        engine.register_fn("follow", |context: NativeCallContext, path: &str, callback: FnPtr| -> Result<i64, Box<EvalAltResult>> {
+            Self::follow_from_script(&context, path, callback, Map::new())
+        });
+        engine.register_fn("follow", |context: NativeCallContext, path: &str, callback: FnPtr, options: Map| -> Result<i64, Box<EvalAltResult>> {
+            Self::follow_from_script(&context, path, callback, options)
+        });
+
+        println!("🟣 ReaderKit: All streaming file reader functions registered");
+    }
+
+    /// Last `count` lines, read backwards from the end so huge files stay cheap
+    pub fn tail(path: &Path, count: usize) -> Result<Vec<String>, String> {
+        const CHUNK: u64 = 64 * 1024;
+        let mut file = File::open(path).map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+        let length = file.metadata().map_err(|e| e.to_string())?.len();
+        if count == 0 || length == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Collect chunks from the end until they hold more than `count` line breaks
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut start = length;
+        let ignore_final_newline = {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::Start(length - 1)).and_then(|_| file.read_exact(&mut last)).map_err(|e| e.to_string())?;
+            last[0] == b'\n'
+        };
+        let needed = count + usize::from(ignore_final_newline);
+        while start > 0 && buffer.iter().filter(|&&b| b == b'\n').count() < needed {
+            let read_from = start.saturating_sub(CHUNK);
+            let mut chunk = vec![0u8; (start - read_from) as usize];
+            file.seek(SeekFrom::Start(read_from)).and_then(|_| file.read_exact(&mut chunk)).map_err(|e| e.to_string())?;
+            chunk.extend_from_slice(&buffer);
+            buffer = chunk;
+            start = read_from;
+        }
+
+        let text = String::from_utf8_lossy(&buffer);
+        let mut lines: Vec<String> = text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line).to_string()).collect();
+        if ignore_final_newline {
+            lines.pop();
+        }
+        let skip = lines.len().saturating_sub(count);
+        Ok(lines.split_off(skip))
+    }
+
+    fn follow_from_script(context: &NativeCallContext, path: &str, callback: FnPtr, options: Map) -> Result<i64, Box<EvalAltResult>> {
+        let options = FollowOptions::from_map(options)?;
+        let mut on_line = |line: String| -> Result<bool, Box<EvalAltResult>> {
+            // Returning false from the callback stops following
+            let result = callback.call_within_context::<Dynamic>(context, (line,))?;
+            Ok(result.as_bool().unwrap_or(true))
+        };
+        Self::follow(Path::new(path), &options, &mut on_line)
+    }
+
+    /// Deliver new lines as they are appended, like `tail -f`. Handles truncation and rotation
+    /// by starting over at the beginning of the new file. Returns the number of lines delivered.
+    fn follow<E: From<String>>(path: &Path, options: &FollowOptions, on_line: &mut dyn FnMut(String) -> Result<bool, E>) -> Result<i64, E> {
+        let started = Instant::now();
+        let open = |from_end: bool| -> Result<(BufReader<File>, u64), String> {
+            let mut file = File::open(path).map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+            let position = if from_end { file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())? } else { 0 };
+            Ok((BufReader::new(file), position))
+        };
+        let (mut reader, mut position) = open(!options.from_start)?;
+        let mut identity = Self::identity(path);
+        let mut partial: Vec<u8> = Vec::new();
+        let mut delivered: i64 = 0;
+
+        loop {
+            // Checked before every read so a writer that never pauses can't keep us here
+            if options.max_lines.is_some_and(|max| delivered as usize >= max) || started.elapsed() >= options.timeout {
+                return Ok(delivered);
+            }
+
+            let mut bytes = Vec::new();
+            let read = reader.read_until(b'\n', &mut bytes).map_err(|e| e.to_string())?;
+            if read > 0 {
+                position += read as u64;
+                partial.extend_from_slice(&bytes);
+                // Wait for the writer to finish the line
+                if partial.last() != Some(&b'\n') {
+                    continue;
+                }
+                let line = String::from_utf8_lossy(&partial).trim_end_matches(['\n', '\r']).to_string();
+                partial.clear();
+                delivered += 1;
+                if !on_line(line)? {
+                    return Ok(delivered);
+                }
+                continue;
+            }
+
+            std::thread::sleep(options.poll_interval);
+
+            // Truncated in place, or replaced by a new file (log rotation)
+            let current = Self::identity(path);
+            let truncated = fs::metadata(path).map(|m| m.len() < position).unwrap_or(false);
+            if current.is_some() && (truncated || current != identity) {
+                (reader, position) = open(false)?;
+                identity = current;
+                partial.clear();
+            }
+        }
+    }
+
+    /// Identifies the file behind a path, to notice when it is replaced
+    #[cfg(unix)]
+    fn identity(path: &Path) -> Option<(u64, u64)> {
+        use std::os::unix::fs::MetadataExt;
+        fs::metadata(path).ok().map(|m| (m.dev(), m.ino()))
+    }
+
+    #[cfg(windows)]
+    fn identity(path: &Path) -> Option<(u64, u64)> {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::Storage::FileSystem::{GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION};
+
+        // Volume serial and file index; the creation time is no use here because file system
+        // tunneling gives a file created under a just-renamed name the old creation time
+        let file = File::open(path).ok()?;
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+            return None;
+        }
+        Some((info.dwVolumeSerialNumber as u64, ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64))
+    }
+}
+
+impl FileReader {
+    fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open '{}': {}", path.display(), e))?;
+        Ok(Self { path: path.to_path_buf(), reader: Arc::new(Mutex::new(Some(BufReader::new(file)))) })
+    }
+
+    fn with_reader<T>(&self, f: impl FnOnce(&mut BufReader<File>) -> std::io::Result<T>) -> Result<T, String> {
+        let mut guard = self.reader.lock().unwrap();
+        let reader = guard.as_mut().ok_or_else(|| format!("FileReader for '{}' is closed", self.path.display()))?;
+        f(reader).map_err(|e| format!("Failed to read '{}': {}", self.path.display(), e))
+    }
+
+    /// Next line without its line ending, or None at the end of the file
+    fn read_line(&self) -> Result<Option<String>, String> {
+        self.with_reader(|reader| {
+            let mut bytes = Vec::new();
+            if reader.read_until(b'\n', &mut bytes)? == 0 {
+                return Ok(None);
+            }
+            Ok(Some(String::from_utf8_lossy(&bytes).trim_end_matches(['\n', '\r']).to_string()))
+        })
+    }
+
+    /// Move to a byte offset; negative offsets count back from the end
+    fn seek(&self, offset: i64) -> Result<i64, String> {
+        let target = if offset < 0 { SeekFrom::End(offset) } else { SeekFrom::Start(offset as u64) };
+        self.with_reader(|reader| reader.seek(target)).map(|position| position as i64)
+    }
+
+    fn position(&self) -> Result<i64, String> {
+        self.with_reader(|reader| reader.stream_position()).map(|position| position as i64)
+    }
+}
+
+impl IntoIterator for FileReader {
+    type Item = Dynamic;
+    type IntoIter = Box<dyn Iterator<Item = Dynamic>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(std::iter::from_fn(move || match self.read_line() {
+            Ok(line) => line.map(Dynamic::from),
+            Err(e) => {
+                println!("❌ {}", e);
+                None
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_reader_and_tail() {
+        let path = std::env::temp_dir().join(format!("snaprun_reader_{}.log", uuid::Uuid::new_v4()));
+        let content: String = (1..=5000).map(|i| format!("line {}\r\n", i)).collect();
+        fs::write(&path, &content).unwrap();
+
+        assert_eq!(ReaderKit::tail(&path, 3).unwrap(), vec!["line 4998", "line 4999", "line 5000"]);
+        assert_eq!(ReaderKit::tail(&path, 10_000).unwrap().len(), 5000);
+
+        let mut engine = Engine::new();
+        ReaderKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("path", path.to_string_lossy().to_string());
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let r = open_reader(path);
+            let first = r.read_line();
+            let next = r.read_lines(2);
+            let count = 0;
+            for line in r.lines() { count += 1; }
+            let at_end = r.read_line();
+            r.seek(-11);
+            first + "|" + next + "|" + count + "|" + (at_end == ()) + "|" + r.read_line() + "|" + tail(path, 1)
+        "#).unwrap();
+        assert_eq!(result, "line 1|[\"line 2\", \"line 3\"]|4997|true|line 5000|[\"line 5000\"]");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_follow_new_lines() {
+        let path = std::env::temp_dir().join(format!("snaprun_follow_{}.log", uuid::Uuid::new_v4()));
+        fs::write(&path, "old line\n").unwrap();
+
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            let mut file = fs::OpenOptions::new().append(true).open(&writer_path).unwrap();
+            for i in 1..=3 {
+                std::thread::sleep(Duration::from_millis(30));
+                write!(file, "new {}", i).unwrap();
+                file.flush().unwrap();
+                std::thread::sleep(Duration::from_millis(30));
+                writeln!(file).unwrap();
+            }
+        });
+
+        let options = FollowOptions { timeout: Duration::from_secs(5), max_lines: Some(3), from_start: false, poll_interval: Duration::from_millis(10) };
+        let mut lines = Vec::new();
+        let delivered = ReaderKit::follow::<String>(&path, &options, &mut |line| {
+            lines.push(line);
+            Ok(true)
+        }).unwrap();
+        writer.join().unwrap();
+        assert_eq!(delivered, 3);
+        assert_eq!(lines, vec!["new 1", "new 2", "new 3"]);
+
+        // A callback returning false stops straight away, and the timeout ends an idle follow
+        let options = FollowOptions { timeout: Duration::from_millis(50), max_lines: None, from_start: true, poll_interval: Duration::from_millis(10) };
+        assert_eq!(ReaderKit::follow::<String>(&path, &options, &mut |_| Ok(false)).unwrap(), 1);
+        let started = Instant::now();
+        let options = FollowOptions { from_start: false, ..options };
+        assert_eq!(ReaderKit::follow::<String>(&path, &options, &mut |_| Ok(true)).unwrap(), 0);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        // The timeout also applies while there are still lines to read
+        let options = FollowOptions { timeout: Duration::ZERO, from_start: true, ..options };
+        assert_eq!(ReaderKit::follow::<String>(&path, &options, &mut |_| Ok(true)).unwrap(), 0);
+        let _ = fs::remove_file(&path);
+    }
+}
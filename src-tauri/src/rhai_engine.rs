@@ -1,15 +1,63 @@
-use rhai::{Engine, Scope, EvalAltResult, Array};
-use std::path::PathBuf;
+use rhai::{Engine, Scope, EvalAltResult, Array, Dynamic, Map, NativeCallContext};
+use std::path::{Path, PathBuf};
 use crate::kits::ui_kit::Kit;
 use crate::fs_kit::FileSystemKit;
 use crate::process_kit::ProcessKit;
-use crate::logging::{LogSource, get_logger};
+use crate::http_kit::HttpKit;
+use crate::datetime_kit::DateTimeKit;
+use crate::string_kit::StringKit;
+use crate::encoding_kit::EncodingKit;
+use crate::env_kit::{self, EnvKit};
+use crate::template_kit::TemplateKit;
+use crate::data_format::DataFormatKit;
+use crate::markup_kit::MarkupKit;
+use crate::archive_kit::ArchiveKit;
+use crate::package_kit::PackageKit;
+use crate::hash_kit::HashKit;
+use crate::sync_kit::SyncKit;
+use crate::reader_kit::ReaderKit;
+use crate::journal::{self, JournalKit};
+use crate::dry_run;
+use crate::store_kit::StoreKit;
+use crate::db_kit::DbKit;
+use crate::secrets_kit::SecretsKit;
+use crate::scaffold_kit::ScaffoldKit;
+use crate::script_manager::ScriptManager;
+use crate::logging::{LogSource, get_logger, redact};
 use std::sync::{Arc, Mutex};
 
+/// Default limit for nested `run_script` calls
+pub const DEFAULT_MAX_SCRIPT_DEPTH: usize = 8;
+
+/// Shared state for scripts calling other scripts through `run_script`
+#[derive(Clone)]
+struct ScriptCallState {
+    script_manager: Arc<Mutex<Option<ScriptManager>>>,
+    script_id: Arc<Mutex<String>>,
+    depth: Arc<Mutex<usize>>,
+    max_depth: Arc<Mutex<usize>>,
+}
+
+impl ScriptCallState {
+    fn new() -> Self {
+        Self {
+            script_manager: Arc::new(Mutex::new(None)),
+            script_id: Arc::new(Mutex::new("unknown_script".to_string())),
+            depth: Arc::new(Mutex::new(0)),
+            max_depth: Arc::new(Mutex::new(DEFAULT_MAX_SCRIPT_DEPTH)),
+        }
+    }
+}
+
 /// Rhai script runner that handles script execution and Kit integration
 pub struct RhaiScriptRunner {
     engine: Engine,
     current_script_name: Arc<Mutex<String>>,
+    script_calls: ScriptCallState,
+    kit: Option<Arc<Mutex<Kit>>>,
+    dry_run: Arc<Mutex<bool>>,
+    journal: Arc<Mutex<bool>>,
+    script_dir: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl RhaiScriptRunner {
@@ -29,21 +77,84 @@ impl RhaiScriptRunner {
         // Register file system functions
         FileSystemKit::register_functions(&mut engine);
         
+        // Register hashing and duplicate detection functions (progress shown in the UI)
+        HashKit::register_functions(&mut engine, Some(kit_shared.clone()));
+        
+        // Register directory sync functions
+        SyncKit::register_functions(&mut engine);
+        
+        // Register streaming file reader functions (open_reader, tail, follow)
+        ReaderKit::register_functions(&mut engine);
+        
         // Register process execution functions
         ProcessKit::register_functions(&mut engine);
         
+        // Register environment variable functions (run-scoped, inherited by child processes)
+        EnvKit::register_functions(&mut engine);
+        
+        // Register zip and tar archive functions
+        ArchiveKit::register_functions(&mut engine);
+        
+        // Register script package functions (install, upgrade, uninstall, list)
+        PackageKit::register_functions(&mut engine);
+        
+        // Register HTTP client functions
+        HttpKit::register_functions(&mut engine);
+        
         // Register advanced mathematical functions
         Self::register_math_functions(&mut engine);
         
+        // Register date and time functions
+        DateTimeKit::register_functions(&mut engine);
+        
+        // Register regex and string formatting functions
+        StringKit::register_functions(&mut engine);
+        
+        // Register base64, hex, text encoding and line ending functions
+        EncodingKit::register_functions(&mut engine);
+        
+        // Register template rendering functions
+        TemplateKit::register_functions(&mut engine);
+        
+        // Register JSON, CSV, TOML, YAML and INI functions
+        DataFormatKit::register_functions(&mut engine);
+        
+        // Register HTML and XML parsing functions
+        MarkupKit::register_functions(&mut engine);
+        
         // Register logging and console functions
         let script_name_shared = Arc::new(Mutex::new("unknown_script".to_string()));
         Self::register_logging_functions(&mut engine, script_name_shared.clone());
         
+        // Register script composition functions (run_script)
+        let script_calls = ScriptCallState::new();
+        Self::register_script_functions(&mut engine, script_calls.clone(), script_name_shared.clone());
+        
+        // Register operation journal functions (start_journal, undo_run)
+        JournalKit::register_functions(&mut engine, script_name_shared.clone());
+        
+        // Register persistent key-value store functions
+        StoreKit::register_functions(&mut engine, script_calls.script_id.clone());
+        
+        // Register embedded SQLite database functions
+        DbKit::register_functions(&mut engine, script_calls.script_id.clone());
+        
+        // Register encrypted secrets vault functions (prompts use the UI)
+        SecretsKit::register_functions(&mut engine, Some(kit_shared.clone()));
+        
+        // Register script template functions (new scripts join the loaded ones; can open the editor)
+        ScaffoldKit::register_functions(&mut engine, script_calls.script_manager.clone(), Some(kit_shared.clone()));
+        
         println!("🟣 RhaiScriptRunner: Engine initialized with Kit integration, FileSystem, Process execution, and Advanced Mathematics");
         
         Self { 
             engine,
             current_script_name: script_name_shared,
+            script_calls,
+            kit: Some(kit_shared),
+            dry_run: Arc::new(Mutex::new(false)),
+            journal: Arc::new(Mutex::new(false)),
+            script_dir: Arc::new(Mutex::new(None)),
         }
     }
     
@@ -55,24 +166,189 @@ impl RhaiScriptRunner {
         // Register file system functions even in basic mode
         FileSystemKit::register_functions(&mut engine);
         
+        // Register hashing and duplicate detection functions even in basic mode
+        HashKit::register_functions(&mut engine, None);
+        
+        // Register directory sync functions even in basic mode
+        SyncKit::register_functions(&mut engine);
+        
+        // Register streaming file reader functions even in basic mode
+        ReaderKit::register_functions(&mut engine);
+        
         // Register process execution functions even in basic mode
         ProcessKit::register_functions(&mut engine);
         
+        // Register environment variable functions even in basic mode
+        EnvKit::register_functions(&mut engine);
+        
+        // Register zip and tar archive functions even in basic mode
+        ArchiveKit::register_functions(&mut engine);
+        
+        // Register script package functions even in basic mode
+        PackageKit::register_functions(&mut engine);
+        
+        // Register HTTP client functions even in basic mode
+        HttpKit::register_functions(&mut engine);
+        
         // Register advanced mathematical functions even in basic mode
         Self::register_math_functions(&mut engine);
         
+        // Register date and time functions even in basic mode
+        DateTimeKit::register_functions(&mut engine);
+        
+        // Register regex and string formatting functions even in basic mode
+        StringKit::register_functions(&mut engine);
+        
+        // Register base64, hex, text encoding and line ending functions even in basic mode
+        EncodingKit::register_functions(&mut engine);
+        
+        // Register template rendering functions even in basic mode
+        TemplateKit::register_functions(&mut engine);
+        
+        // Register JSON, CSV, TOML, YAML and INI functions even in basic mode
+        DataFormatKit::register_functions(&mut engine);
+        
+        // Register HTML and XML parsing functions even in basic mode
+        MarkupKit::register_functions(&mut engine);
+        
         // Register logging and console functions
         let script_name_shared = Arc::new(Mutex::new("unknown_script".to_string()));
         Self::register_logging_functions(&mut engine, script_name_shared.clone());
         
+        // Register script composition functions (run_script)
+        let script_calls = ScriptCallState::new();
+        Self::register_script_functions(&mut engine, script_calls.clone(), script_name_shared.clone());
+        
+        // Register operation journal functions (start_journal, undo_run)
+        JournalKit::register_functions(&mut engine, script_name_shared.clone());
+        
+        // Register persistent key-value store functions
+        StoreKit::register_functions(&mut engine, script_calls.script_id.clone());
+        
+        // Register embedded SQLite database functions
+        DbKit::register_functions(&mut engine, script_calls.script_id.clone());
+        
+        // Register secrets vault functions even in basic mode (passphrase from the environment)
+        SecretsKit::register_functions(&mut engine, None);
+        
+        // Register script template functions even in basic mode (without the editor)
+        ScaffoldKit::register_functions(&mut engine, script_calls.script_manager.clone(), None);
+        
         println!("🟣 RhaiScriptRunner: Basic engine initialized with FileSystem, Process execution, and Advanced Mathematics (no Kit)");
         
         Self { 
             engine,
             current_script_name: script_name_shared,
+            script_calls,
+            kit: None,
+            dry_run: Arc::new(Mutex::new(false)),
+            journal: Arc::new(Mutex::new(false)),
+            script_dir: Arc::new(Mutex::new(None)),
         }
     }
     
+    /// Make discovered scripts available to `run_script`
+    pub fn set_script_manager(&self, script_manager: ScriptManager) {
+        *self.script_calls.script_manager.lock().unwrap() = Some(script_manager);
+    }
+    
+    /// Set the ID of the script about to run (used to namespace per-script data)
+    pub fn set_script_id(&self, script_id: &str) {
+        *self.script_calls.script_id.lock().unwrap() = script_id.to_string();
+    }
+    
+    /// Set the maximum nesting depth for `run_script` calls
+    pub fn set_max_script_depth(&self, max_depth: usize) {
+        *self.script_calls.max_depth.lock().unwrap() = max_depth;
+    }
+    
+    /// Journal every run so it can be undone, as if the script called `start_journal()`
+    pub fn set_journal(&self, journal: bool) {
+        *self.journal.lock().unwrap() = journal;
+    }
+    
+    /// Set the file of the script about to run; its folder's `.env` file is loaded at start
+    pub fn set_script_path(&self, script_path: &Path) {
+        *self.script_dir.lock().unwrap() = script_path.parent().map(Path::to_path_buf);
+    }
+    
+    /// Record mutating file and process calls instead of executing them, then show a report
+    pub fn set_dry_run(&self, dry_run: bool) {
+        *self.dry_run.lock().unwrap() = dry_run;
+    }
+    
+    /// Register functions that let scripts run other discovered scripts
+    fn register_script_functions(engine: &mut Engine, state: ScriptCallState, script_name: Arc<Mutex<String>>) {
+        // Lets scripts skip work that makes no sense without real side effects
+        engine.register_fn("is_dry_run", dry_run::is_active);
+        
+        {
+            let state = state.clone();
+            let script_name = script_name.clone();
+            engine.register_fn("run_script", move |context: NativeCallContext, script_id: &str, args: Map| -> Result<Dynamic, Box<EvalAltResult>> {
+                Self::run_child_script(&context, &state, &script_name, script_id, args)
+            });
+        }
+        
+        {
+            let state = state.clone();
+            let script_name = script_name.clone();
+            engine.register_fn("run_script", move |context: NativeCallContext, script_id: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+                Self::run_child_script(&context, &state, &script_name, script_id, Map::new())
+            });
+        }
+    }
+    
+    /// Run another script in a child scope on the same engine (and therefore the same Kit)
+    fn run_child_script(
+        context: &NativeCallContext,
+        state: &ScriptCallState,
+        script_name: &Arc<Mutex<String>>,
+        script_id: &str,
+        args: Map,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        // Look up the script and release the manager lock before running it,
+        // since the child may call run_script itself
+        let (child_name, child_path) = {
+            let manager_guard = state.script_manager.lock().unwrap();
+            let manager = manager_guard.as_ref()
+                .ok_or_else(|| "run_script: no scripts have been loaded".to_string())?;
+            let info = manager.get_script_by_id(script_id)
+                .ok_or_else(|| format!("run_script: script '{}' not found", script_id))?;
+            (info.name.clone(), info.file_path.clone())
+        };
+        
+        {
+            let max_depth = *state.max_depth.lock().unwrap();
+            let mut depth = state.depth.lock().unwrap();
+            if *depth >= max_depth {
+                return Err(format!("run_script: maximum nesting depth of {} exceeded while calling '{}'", max_depth, script_id).into());
+            }
+            *depth += 1;
+        }
+        
+        let parent_name = std::mem::replace(&mut *script_name.lock().unwrap(), child_name.clone());
+        let parent_id = std::mem::replace(&mut *state.script_id.lock().unwrap(), script_id.to_string());
+        
+        let result = std::fs::read_to_string(&child_path)
+            .map_err(|e| -> Box<EvalAltResult> { format!("run_script: failed to read script '{}': {}", script_id, e).into() })
+            .and_then(|content| {
+                if let Some(logger) = get_logger() {
+                    logger.info_script(LogSource::Rhai(child_name.clone()), &format!("Called from '{}'", parent_name), &child_name);
+                }
+                
+                let mut scope = Scope::new();
+                scope.push("args", args);
+                context.engine().eval_with_scope::<Dynamic>(&mut scope, &content)
+            });
+        
+        *script_name.lock().unwrap() = parent_name;
+        *state.script_id.lock().unwrap() = parent_id;
+        *state.depth.lock().unwrap() -= 1;
+        
+        result
+    }
+    
     /// Register logging and console functions with the Rhai engine
     fn register_logging_functions(engine: &mut Engine, script_name: Arc<Mutex<String>>) {
         // Register print function that logs to our logging system
@@ -83,7 +359,7 @@ impl RhaiScriptRunner {
                 if let Some(logger) = get_logger() {
                     logger.info_script(LogSource::Rhai(script_name.clone()), message, &script_name);
                 } else {
-                    println!("📜 {}: {}", script_name, message);
+                    println!("📜 {}: {}", script_name, redact(message));
                 }
                 message.to_string() // Return the message
             });
@@ -97,7 +373,7 @@ impl RhaiScriptRunner {
                 if let Some(logger) = get_logger() {
                     logger.info_script(LogSource::Rhai(script_name.clone()), message, &script_name);
                 } else {
-                    println!("📜 {}: {}", script_name, message);
+                    println!("📜 {}: {}", script_name, redact(message));
                 }
                 message.to_string() // Return the message
             });
@@ -163,7 +439,7 @@ impl RhaiScriptRunner {
                 if let Some(logger) = get_logger() {
                     logger.info_script(LogSource::Rhai(script_name.clone()), message, &script_name);
                 } else {
-                    println!("ℹ️ 📜 {}: {}", script_name, message);
+                    println!("ℹ️ 📜 {}: {}", script_name, redact(message));
                 }
             });
         }
@@ -176,7 +452,7 @@ impl RhaiScriptRunner {
                 if let Some(logger) = get_logger() {
                     logger.info_script(LogSource::Rhai(script_name.clone()), message, &script_name);
                 } else {
-                    println!("📜 {}: {}", script_name, message);
+                    println!("📜 {}: {}", script_name, redact(message));
                 }
                 message.to_string() // Return the message
             });
@@ -190,7 +466,7 @@ impl RhaiScriptRunner {
                 if let Some(logger) = get_logger() {
                     logger.info_script(LogSource::Rhai(script_name.clone()), message, &script_name);
                 } else {
-                    println!("📜 {}: {}", script_name, message);
+                    println!("📜 {}: {}", script_name, redact(message));
                 }
             });
         }
@@ -541,6 +817,15 @@ impl RhaiScriptRunner {
             });
         }
         
+        // Register ask_password function - input is hidden and never logged
+        {
+            let kit_clone = kit.clone();
+            engine.register_fn("ask_password", move |message: &str| -> String {
+                let mut kit_guard = kit_clone.lock().expect("Failed to lock Kit");
+                kit_guard.ask_masked_input_sync(message).unwrap_or_default()
+            });
+        }
+        
         // Register ask_select function - handle Rhai array conversion
         {
             let kit_clone = kit.clone();
@@ -819,32 +1104,19 @@ impl RhaiScriptRunner {
             });
         }
 
-        // Register JSON functions
+        // Register progress display
         {
             let kit_clone = kit.clone();
-            engine.register_fn("parse_json", move |json_str: &str| -> rhai::Dynamic {
+            engine.register_fn("show_progress", move |title: &str, current: i64, total: i64| -> bool {
                 let kit_guard = kit_clone.lock().expect("Failed to lock Kit");
-                match kit_guard.parse_json(json_str) {
-                    Ok(data) => data,
-                    Err(e) => {
-                        eprintln!("JSON parse error: {}", e);
-                        rhai::Dynamic::UNIT
-                    }
-                }
+                kit_guard.show_progress(title, current, total, "").is_ok()
             });
         }
-
         {
             let kit_clone = kit.clone();
-            engine.register_fn("to_json", move |data: rhai::Dynamic| -> String {
+            engine.register_fn("show_progress", move |title: &str, current: i64, total: i64, detail: &str| -> bool {
                 let kit_guard = kit_clone.lock().expect("Failed to lock Kit");
-                match kit_guard.to_json(data) {
-                    Ok(json_str) => json_str,
-                    Err(e) => {
-                        eprintln!("JSON serialization error: {}", e);
-                        "{}".to_string()
-                    }
-                }
+                kit_guard.show_progress(title, current, total, detail).is_ok()
             });
         }
 
@@ -882,8 +1154,26 @@ impl RhaiScriptRunner {
             println!("🟣 RhaiScriptRunner: Executing script: {}", script_name);
         }
 
-        // Execute the script
-        match self.engine.eval_with_scope::<()>(&mut scope, script_content) {
+        let dry_run = *self.dry_run.lock().unwrap();
+        if dry_run {
+            dry_run::begin();
+        }
+        env_kit::begin(self.script_dir.lock().unwrap().as_deref(), crate::settings::current().env);
+        if *self.journal.lock().unwrap() && !dry_run {
+            if let Err(e) = journal::start(&journal::journal_dir(), script_name) {
+                eprintln!("❌ RhaiScriptRunner: Failed to start journal: {}", e);
+            }
+        }
+        
+        // Execute the script, closing any journal it started once it ends
+        let result = self.engine.eval_with_scope::<()>(&mut scope, script_content);
+        journal::finish();
+        env_kit::end();
+        
+        if dry_run {
+            self.show_dry_run_report(script_name, result.as_ref().map(|_| ()).map_err(|e| e.to_string()));
+        }
+        match result {
             Ok(_) => {
                 if let Some(logger) = get_logger() {
                     logger.info_script(LogSource::Rhai(script_name.to_string()), "Script execution completed successfully", script_name);
@@ -910,6 +1200,29 @@ impl RhaiScriptRunner {
         }
     }
     
+    /// Show what a dry run would have changed (console only in basic mode)
+    fn show_dry_run_report(&self, script_name: &str, outcome: Result<(), String>) {
+        let actions = dry_run::end();
+        if let Some(logger) = get_logger() {
+            logger.info_script(LogSource::Rhai(script_name.to_string()), &format!("Dry run recorded {} action(s)", actions.len()), script_name);
+        }
+        
+        match &self.kit {
+            Some(kit) => {
+                let html = dry_run::report_html(script_name, &actions, outcome);
+                if let Err(e) = kit.lock().unwrap().render_html("Dry Run Report", &html) {
+                    eprintln!("❌ RhaiScriptRunner: Failed to show dry run report: {}", e);
+                }
+            },
+            None => {
+                println!("🟡 Dry run of {}: {} action(s)", script_name, actions.len());
+                for action in &actions {
+                    println!("🟡   {} {}", action.function, action.detail);
+                }
+            }
+        }
+    }
+    
     /// Load and execute a script from file
     pub fn run_script_file(&self, file_path: PathBuf) -> Result<(), String> {
         let script_name = file_path.file_stem()
@@ -918,6 +1231,7 @@ impl RhaiScriptRunner {
             
         let script_content = std::fs::read_to_string(&file_path)
             .map_err(|e| format!("Failed to read script file: {}", e))?;
+        self.set_script_path(&file_path);
             
         self.run_script_with_name(&script_content, script_name)
             .map_err(|e| format!("Script execution error: {}", e))
@@ -1086,4 +1400,59 @@ mod tests {
         let mut scope = Scope::new();
         assert!(engine.eval_with_scope::<()>(&mut scope, script).is_ok());
     }
+
+    fn script_manager_with(dir: &std::path::Path, scripts: &[(&str, &str)]) -> ScriptManager {
+        use crate::script_manager::{ScriptInfo, ScriptType};
+
+        std::fs::create_dir_all(dir).unwrap();
+        let mut manager = ScriptManager::new(PathBuf::new());
+        for (id, content) in scripts {
+            let file_path = dir.join(format!("{}.rhai", id));
+            std::fs::write(&file_path, content).unwrap();
+            manager.scripts.push(ScriptInfo {
+                id: id.to_string(),
+                name: id.to_string(),
+                description: String::new(),
+                file_path,
+                category: "Test".to_string(),
+                script_type: ScriptType::Rhai,
+                read_only: false,
+            });
+        }
+        manager
+    }
+
+    #[test]
+    fn test_run_script_returns_child_result() {
+        let dir = std::env::temp_dir().join("snaprun_test_run_script");
+        let runner = RhaiScriptRunner::new_basic();
+        runner.set_script_manager(script_manager_with(&dir, &[
+            ("double", "args.value * 2"),
+            ("no_args", "args.len()"),
+        ]));
+
+        let result = runner.engine.eval::<i64>(r#"run_script("double", #{ value: 21 })"#).unwrap();
+        assert_eq!(result, 42);
+        let result = runner.engine.eval::<i64>(r#"run_script("no_args")"#).unwrap();
+        assert_eq!(result, 0);
+        assert!(runner.engine.eval::<Dynamic>(r#"run_script("missing")"#).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_run_script_depth_limit() {
+        let dir = std::env::temp_dir().join("snaprun_test_run_script_depth");
+        let runner = RhaiScriptRunner::new_basic();
+        runner.set_max_script_depth(3);
+        runner.set_script_manager(script_manager_with(&dir, &[
+            ("recurse", r#"run_script("recurse")"#),
+        ]));
+
+        let err = runner.engine.eval::<Dynamic>(r#"run_script("recurse")"#).unwrap_err();
+        assert!(err.to_string().contains("maximum nesting depth"));
+        assert_eq!(*runner.script_calls.depth.lock().unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map};
+use crate::dry_run;
+use crate::fs_kit::{candidate_name, FileSystemKit};
+use crate::journal;
+use crate::kits::ui_kit::Kit;
+use crate::script_manager::{ScriptInfo, ScriptManager, ScriptType};
+use crate::template_kit::TemplateKit;
+
+/// Header lines that describe a template; they are replaced in the new script
+const METADATA_PREFIXES: &[&str] = &["// @name:", "// @description:", "// @template:"];
+
+/// A script template: `<name>.rhai` in a templates folder, rendered with TemplateKit placeholders
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptTemplate {
+    pub name: String,
+    /// From the template's `// @template:` line
+    pub summary: String,
+    pub path: PathBuf,
+}
+
+/// New scripts from templates for Rhai scripts, added to the scripts of the current run
+pub struct ScaffoldKit;
+
+impl ScaffoldKit {
+    /// Register the scaffolding functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine, script_manager: Arc<Mutex<Option<ScriptManager>>>, kit: Option<Arc<Mutex<Kit>>>) {
+        println!("🟣 ScaffoldKit: Registering scaffolding functions");
+
+        {
+            let script_manager = script_manager.clone();
+            engine.register_fn("list_script_templates", move || -> Result<Array, Box<EvalAltResult>> {
+                let dirs = Self::template_dirs(&script_manager)?;
+                Ok(list_templates(&dirs).into_iter().map(|template| {
+                    let mut item_map = Map::new();
+                    item_map.insert("name".into(), Dynamic::from(template.name));
+                    item_map.insert("summary".into(), Dynamic::from(template.summary));
+                    item_map.insert("path".into(), Dynamic::from(template.path.to_string_lossy().to_string()));
+                    Dynamic::from(item_map)
+                }).collect())
+            });
+        }
+
+        {
+            let script_manager = script_manager.clone();
+            let kit = kit.clone();
+            engine.register_fn("create_script_from_template", move |template: &str, name: &str, vars: Map| -> Result<Map, Box<EvalAltResult>> {
+                Self::create_from_script(&script_manager, kit.as_ref(), template, name, vars, Map::new())
+            });
+        }
+
+        engine.register_fn("create_script_from_template", move |template: &str, name: &str, vars: Map, options: Map| -> Result<Map, Box<EvalAltResult>> {
+            Self::create_from_script(&script_manager, kit.as_ref(), template, name, vars, options)
+        });
+
+        println!("🟣 ScaffoldKit: All scaffolding functions registered");
+    }
+
+    fn template_dirs(script_manager: &Arc<Mutex<Option<ScriptManager>>>) -> Result<Vec<PathBuf>, String> {
+        let guard = script_manager.lock().unwrap();
+        let manager = guard.as_ref().ok_or("No scripts have been loaded")?;
+        Ok(manager.template_dirs())
+    }
+
+    fn create_from_script(
+        script_manager: &Arc<Mutex<Option<ScriptManager>>>,
+        kit: Option<&Arc<Mutex<Kit>>>,
+        template: &str,
+        name: &str,
+        vars: Map,
+        options: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        let mut open = false;
+        let mut root = None;
+        for (key, value) in options {
+            match key.as_str() {
+                "open" => open = value.as_bool().map_err(|_| "Option 'open' must be true or false".to_string())?,
+                "root" => root = Some(value.into_string().map_err(|_| "Option 'root' must be a root label".to_string())?),
+                other => return Err(format!("Unknown option '{}'", other).into()),
+            }
+        }
+
+        // Release the manager before the editor opens; it waits for the user
+        let (script, dry) = {
+            let mut guard = script_manager.lock().unwrap();
+            let manager = guard.as_mut().ok_or("No scripts have been loaded")?;
+            let dirs = manager.template_dirs();
+            let planned = plan_script_from_template(manager, &dirs, template, name, vars, root.as_deref())?;
+            // A dry run reports the script it would create, so the caller can carry on with it
+            if dry_run::intercept("create_script_from_template", || format!("{} from '{}'", planned.info.file_path.display(), template)) {
+                (planned.info, true)
+            } else {
+                (write_planned_script(manager, planned)?, false)
+            }
+        };
+        if open && !dry {
+            let kit = kit.ok_or("Opening the editor needs the app UI")?;
+            kit.lock().expect("Failed to lock Kit").editor_sync(Some(script.file_path.to_string_lossy().to_string()));
+        }
+
+        let mut item_map = Map::new();
+        item_map.insert("id".into(), Dynamic::from(script.id));
+        item_map.insert("name".into(), Dynamic::from(script.name));
+        item_map.insert("description".into(), Dynamic::from(script.description));
+        item_map.insert("path".into(), Dynamic::from(script.file_path.to_string_lossy().to_string()));
+        item_map.insert("category".into(), Dynamic::from(script.category));
+        Ok(item_map)
+    }
+}
+
+/// User templates live in Documents/SnapRun/Templates
+pub fn user_templates_dir() -> PathBuf {
+    match dirs::document_dir() {
+        Some(docs_dir) => docs_dir.join("SnapRun").join("Templates"),
+        None => PathBuf::from("./user_data/Templates"),
+    }
+}
+
+/// Templates by name; an earlier folder wins over a later one with the same template
+pub fn list_templates(dirs: &[PathBuf]) -> Vec<ScriptTemplate> {
+    let mut templates: Vec<ScriptTemplate> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else { continue };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let Some(name) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else { continue };
+            if path.extension().is_none_or(|ext| ext != "rhai") || templates.iter().any(|t| t.name.eq_ignore_ascii_case(&name)) {
+                continue;
+            }
+            let summary = fs::read_to_string(&path).ok()
+                .and_then(|text| text.lines().find_map(|line| line.trim().strip_prefix("// @template:").map(|s| s.trim().to_string())))
+                .unwrap_or_default();
+            templates.push(ScriptTemplate { name, summary, path });
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// A rendered script that has not been written yet
+pub struct PlannedScript {
+    pub info: ScriptInfo,
+    content: String,
+    template: String,
+}
+
+/// Render `template` into a new script called `name` in a writable root (the highest-priority one,
+/// or the root labelled `root`), then add it to `manager` so it can run straight away.
+/// Placeholders use TemplateKit syntax; `name`, `description`, `file_name` and `date` are always set.
+pub fn create_script_from_template(manager: &mut ScriptManager, template_dirs: &[PathBuf], template: &str, name: &str, vars: Map, root: Option<&str>) -> Result<ScriptInfo, String> {
+    let planned = plan_script_from_template(manager, template_dirs, template, name, vars, root)?;
+    write_planned_script(manager, planned)
+}
+
+/// Everything `create_script_from_template` does short of writing the file
+pub fn plan_script_from_template(manager: &ScriptManager, template_dirs: &[PathBuf], template: &str, name: &str, vars: Map, root: Option<&str>) -> Result<PlannedScript, String> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(['\n', '\r']) {
+        return Err("Script name must be a single non-empty line".to_string());
+    }
+    let template = list_templates(template_dirs).into_iter()
+        .find(|t| t.name.eq_ignore_ascii_case(template))
+        .ok_or_else(|| format!("Template '{}' not found", template))?;
+    let target_root = match root {
+        Some(label) => manager.roots.iter().find(|r| r.label.eq_ignore_ascii_case(label))
+            .ok_or_else(|| format!("Script root '{}' not found", label))?,
+        None => manager.writable_root().ok_or("No writable script root")?,
+    };
+    if target_root.read_only || !target_root.enabled || target_root.scripts.is_some() {
+        return Err(format!("Script root '{}' is read-only", target_root.label));
+    }
+    let (dir, label) = (target_root.path.clone(), target_root.label.clone());
+
+    // A file name no root uses yet, so the new script neither shadows nor is shadowed
+    let slug = file_slug(name);
+    let taken = |file_name: &str| {
+        let stem = file_name.trim_end_matches(".rhai").to_lowercase();
+        dir.join(file_name).exists()
+            || manager.scripts.iter().chain(manager.shadowed.iter().map(|s| &s.script))
+                .any(|script| script.file_path.file_stem().is_some_and(|s| s.to_string_lossy().to_lowercase() == stem))
+    };
+    let file_name = (1..).map(|attempt| candidate_name(&format!("{}.rhai", slug), attempt))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_default();
+    let path = dir.join(&file_name);
+
+    let description = vars.get("description")
+        .and_then(|value| value.clone().into_string().ok())
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| format!("Created from the {} template", template.name));
+    // Like the name, it goes into a `//` header comment, where a line break would start script code
+    if description.contains(['\n', '\r']) {
+        return Err("Script description must be a single line".to_string());
+    }
+    let mut data = vars;
+    data.insert("name".into(), Dynamic::from(name.to_string()));
+    data.insert("description".into(), Dynamic::from(description.clone()));
+    data.insert("file_name".into(), Dynamic::from(file_name.clone()));
+    data.insert("date".into(), Dynamic::from(chrono::Local::now().format("%Y-%m-%d").to_string()));
+
+    let mut options = Map::new();
+    options.insert("autoescape".into(), Dynamic::FALSE);
+    options.insert("strict".into(), Dynamic::TRUE);
+    let body = TemplateKit::render_file(&template.path, data, options)?;
+    let content = with_metadata(&body, name, &description);
+
+    let info = ScriptInfo {
+        id: ScriptManager::script_id(&label, file_name.trim_end_matches(".rhai")),
+        name: name.to_string(),
+        description,
+        file_path: path,
+        category: label,
+        script_type: ScriptType::Rhai,
+        read_only: false,
+    };
+    Ok(PlannedScript { info, content, template: template.name })
+}
+
+fn write_planned_script(manager: &mut ScriptManager, planned: PlannedScript) -> Result<ScriptInfo, String> {
+    let path = &planned.info.file_path;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    }
+    journal::write(path, || FileSystemKit::write_atomic(path, planned.content.as_bytes(), true))
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    println!("🟣 ScaffoldKit: Created {} from template '{}'", path.display(), planned.template);
+    manager.add_script(path, &planned.info.category)
+}
+
+/// Lowercase words joined by '_', e.g. "Deploy Site!" -> "deploy_site"
+fn file_slug(name: &str) -> String {
+    let slug = name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if slug.is_empty() { "script".to_string() } else { slug }
+}
+
+/// Replace the leading metadata comments with `@name` and `@description` headers
+fn with_metadata(body: &str, name: &str, description: &str) -> String {
+    let mut lines = body.lines().peekable();
+    while lines.peek().is_some_and(|line| METADATA_PREFIXES.iter().any(|prefix| line.trim_start().starts_with(prefix))) {
+        lines.next();
+    }
+    let mut content = format!("// @name: {}\n// @description: {}\n", name, description);
+    for line in lines {
+        content.push_str(line);
+        content.push('\n');
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::ScriptRoot;
+
+    #[test]
+    fn test_create_from_template() {
+        let base = std::env::temp_dir().join(format!("snaprun_scaffold_{}", uuid::Uuid::new_v4()));
+        let (user_templates, bundled_templates, scripts) = (base.join("user"), base.join("bundled"), base.join("scripts"));
+        for dir in [&user_templates, &bundled_templates, &scripts] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(bundled_templates.join("prompt.rhai"), "// @template: Ask and show\n// @name: {{ name }}\nlet answer = ask_input(\"{{ question }}\");\n").unwrap();
+        fs::write(bundled_templates.join("blank.rhai"), "// @template: Bundled blank\n").unwrap();
+        fs::write(user_templates.join("Blank.rhai"), "// @template: My blank\n// {{ name }} ({{ file_name }}), {{ date }}\n").unwrap();
+        let dirs = vec![user_templates.clone(), bundled_templates.clone()];
+
+        let summaries: Vec<String> = list_templates(&dirs).iter().map(|t| format!("{}: {}", t.name, t.summary)).collect();
+        assert_eq!(summaries, vec!["Blank: My blank", "prompt: Ask and show"]);
+
+        let mut manager = ScriptManager::new(PathBuf::new());
+        manager.load_roots(vec![
+            ScriptRoot { label: "Team".to_string(), path: base.join("team"), read_only: true, priority: 90, ..Default::default() },
+            ScriptRoot { label: "Custom".to_string(), path: scripts.clone(), ..Default::default() },
+        ]);
+
+        let mut vars = Map::new();
+        vars.insert("question".into(), Dynamic::from("Which branch?".to_string()));
+        // Planning gives the same script without writing it (what dry runs report)
+        let planned = plan_script_from_template(&manager, &dirs, "prompt", "Deploy Site!", vars.clone(), None).unwrap();
+        assert!(!planned.info.file_path.exists());
+        let first = create_script_from_template(&mut manager, &dirs, "prompt", "Deploy Site!", vars.clone(), None).unwrap();
+        assert_eq!(first.file_path, scripts.join("deploy_site.rhai"));
+        assert_eq!(first.id, "rhai_custom_deploy_site");
+        assert_eq!(first.description, "Created from the prompt template");
+        assert_eq!((&planned.info.id, &planned.info.file_path, &planned.info.name), (&first.id, &first.file_path, &first.name));
+        let text = fs::read_to_string(&first.file_path).unwrap();
+        assert_eq!(text, "// @name: Deploy Site!\n// @description: Created from the prompt template\nlet answer = ask_input(\"Which branch?\");\n");
+
+        // Same name again gets the next free file name and is registered right away
+        let second = create_script_from_template(&mut manager, &dirs, "blank", "deploy site", Map::new(), None).unwrap();
+        assert_eq!(second.file_path, scripts.join("deploy_site_2.rhai"));
+        assert!(fs::read_to_string(&second.file_path).unwrap().contains("// deploy site (deploy_site_2.rhai), "));
+        assert!(manager.get_script_by_id("rhai_custom_deploy_site_2").is_some());
+
+        let mut vars = Map::new();
+        vars.insert("description".into(), Dynamic::from("Deploys\nremove_dir_all(\"/\");".to_string()));
+        assert!(create_script_from_template(&mut manager, &dirs, "blank", "Sneaky", vars, None).is_err());
+        assert!(!scripts.join("sneaky.rhai").exists());
+
+        // Missing placeholders and read-only roots are errors
+        assert!(create_script_from_template(&mut manager, &dirs, "prompt", "x", Map::new(), None).unwrap_err().contains("question"));
+        assert!(create_script_from_template(&mut manager, &dirs, "blank", "x", Map::new(), Some("Team")).unwrap_err().contains("read-only"));
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::env;
+use crate::settings::{ScriptRoot, ScriptSettings, BUILT_IN_ROOT_PRIORITY, LEGACY_ROOT_PRIORITY, USER_ROOT_PRIORITY};
 
 /// Information about a Rhai script
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +13,17 @@ pub struct ScriptInfo {
     pub file_path: PathBuf,
     pub category: String,
     pub script_type: ScriptType,
+    /// Loaded from a read-only root
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+/// A script hidden by a same-named script in a higher-priority root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowedScript {
+    pub script: ScriptInfo,
+    /// Id of the script that is used instead
+    pub shadowed_by: String,
 }
 
 /// Type of script - Rust (built-in) or Rhai (user)
@@ -24,107 +36,192 @@ pub enum ScriptType {
 /// Script manager for discovering and managing Rhai scripts
 pub struct ScriptManager {
     pub scripts: Vec<ScriptInfo>,
+    /// Roots in the order they were searched, highest priority first
+    pub roots: Vec<ScriptRoot>,
+    pub shadowed: Vec<ShadowedScript>,
     project_root: PathBuf,
 }
 
 impl ScriptManager {
-    /// Get the user scripts directory path with environment variable support
+    /// Get the user scripts directory from settings (SNAPRUN_SCRIPTS overrides it; SNAPRUN_HOME is a fallback)
     pub fn get_user_scripts_path() -> PathBuf {
-        // Try SNAPRUN_SCRIPTS environment variable first
-        if let Ok(scripts_path) = env::var("SNAPRUN_SCRIPTS") {
-            println!("🟣 Using SNAPRUN_SCRIPTS: {}", scripts_path);
-            return PathBuf::from(scripts_path);
-        }
-        
-        // Default to Documents/SnapRun/Scripts (user-friendly location)
-        if let Some(docs_dir) = dirs::document_dir() {
-            let scripts_path = docs_dir.join("SnapRun").join("Scripts");
-            println!("🟣 Using Documents folder: {}", scripts_path.display());
-            return scripts_path;
-        }
-        
-        // Try SNAPRUN_HOME environment variable as fallback
-        if let Ok(home_path) = env::var("SNAPRUN_HOME") {
-            let scripts_path = PathBuf::from(home_path).join("Scripts");
-            println!("🟣 Using SNAPRUN_HOME: {}", scripts_path.display());
-            return scripts_path;
-        }
-        
-        // Final fallback to project directory
-        let fallback_path = PathBuf::from("./user_scripts");
-        println!("🟣 Using project fallback: {}", fallback_path.display());
-        fallback_path
+        crate::settings::current().scripts.user_scripts_dir()
+    }
+
+    /// Get the data directory for a script (or a shared namespace such as "global")
+    pub fn get_script_data_path(script_id: &str) -> PathBuf {
+        let base = match dirs::document_dir() {
+            Some(docs_dir) => docs_dir.join("SnapRun").join("Data"),
+            None => PathBuf::from("./user_data"),
+        };
+
+        let folder: String = script_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+            .collect();
+
+        base.join(folder)
     }
 
     /// Create a new script manager
     pub fn new(project_root: PathBuf) -> Self {
         Self {
             scripts: Vec::new(),
+            roots: Vec::new(),
+            shadowed: Vec::new(),
             project_root,
         }
     }
 
-    /// Load all scripts from the user_scripts directory
+    /// Load all scripts from the built-in, user, legacy and configured roots
     pub fn load_scripts(&mut self) -> Result<(), String> {
         println!("🟣 ScriptManager: Loading scripts from {:?}", self.project_root);
         
-        self.scripts.clear();
-        
-        // Get user scripts path (with environment variable support)
-        let user_scripts_path = Self::get_user_scripts_path();
-        
-        // Load built-in Rhai scripts from app installation
-        let app_scripts_dir = self.project_root.join("user_scripts").join("built_in_scripts");
-        if app_scripts_dir.exists() {
-            self.load_scripts_from_directory(&app_scripts_dir, "Built-in", ScriptType::Rhai)?;
-        }
-        
-        // Load user custom scripts directly from Documents folder
-        if user_scripts_path.exists() {
-            self.load_scripts_from_directory(&user_scripts_path, "Custom", ScriptType::Rhai)?;
-        } else {
+        let script_settings = crate::settings::current().scripts;
+        let user_scripts_path = script_settings.user_scripts_dir();
+        if !user_scripts_path.exists() {
             // Create the directory if it doesn't exist
             if let Err(e) = fs::create_dir_all(&user_scripts_path) {
                 println!("⚠️ Could not create user scripts directory: {}", e);
             }
         }
         
-        // Also check legacy custom_scripts folder for backward compatibility
-        let legacy_custom_dir = if user_scripts_path.parent().is_some() {
-            user_scripts_path.parent().unwrap().join("custom_scripts")
-        } else {
-            self.project_root.join("user_scripts").join("custom_scripts")
+        let roots = self.script_roots(&script_settings, &crate::package_kit::packages_dir());
+        self.load_roots(roots);
+        
+        println!("🟣 ScriptManager: Loaded {} scripts total from {} roots ({} shadowed)", self.scripts.len(), self.roots.len(), self.shadowed.len());
+        Ok(())
+    }
+    
+    /// The folders SnapRun always loads, the configured roots and the packages installed in
+    /// `packages_dir`, highest priority first. Equal priorities keep this order: user, legacy,
+    /// built-in, configured roots as listed, then packages by name.
+    pub fn script_roots(&self, settings: &ScriptSettings, packages_dir: &Path) -> Vec<ScriptRoot> {
+        let user_dir = settings.user_scripts_dir();
+        // Legacy custom_scripts folder, kept for backward compatibility
+        let legacy_dir = match user_dir.parent() {
+            Some(parent) => parent.join("custom_scripts"),
+            None => self.project_root.join("user_scripts").join("custom_scripts"),
+        };
+        let implicit = |label: &str, path: PathBuf, priority: i32, read_only: bool| ScriptRoot {
+            label: label.to_string(),
+            path,
+            priority,
+            read_only,
+            enabled: true,
+            scripts: None,
         };
         
-        if legacy_custom_dir.exists() && legacy_custom_dir != user_scripts_path {
-            self.load_scripts_from_directory(&legacy_custom_dir, "Legacy", ScriptType::Rhai)?;
-        }
+        let mut roots = vec![
+            implicit("Custom", user_dir, USER_ROOT_PRIORITY, false),
+            implicit("Legacy", legacy_dir, LEGACY_ROOT_PRIORITY, false),
+            implicit("Built-in", self.project_root.join("user_scripts").join("built_in_scripts"), BUILT_IN_ROOT_PRIORITY, true),
+        ];
+        roots.extend(settings.roots.iter().cloned());
+        roots.extend(crate::package_kit::package_roots(packages_dir));
+        roots.sort_by_key(|root| std::cmp::Reverse(root.priority));
         
-        println!("🟣 ScriptManager: Loaded {} scripts total from {:?}", self.scripts.len(), user_scripts_path);
-        Ok(())
+        // The same folder listed twice is only loaded for its first entry
+        let mut seen = HashSet::new();
+        roots.retain(|root| seen.insert(root.path.clone()));
+        roots
     }
     
-    /// Load scripts from a specific directory
+    /// Load every enabled root in order. A script whose file name was already found in an
+    /// earlier root is shadowed rather than loaded.
+    pub fn load_roots(&mut self, roots: Vec<ScriptRoot>) {
+        self.scripts.clear();
+        self.shadowed.clear();
+        
+        // Lowercased file stem -> index of the script that owns the name
+        let mut owners: HashMap<String, usize> = HashMap::new();
+        for root in roots.iter().filter(|root| root.enabled) {
+            if !root.path.is_dir() {
+                if root.label != "Legacy" {
+                    println!("⚠️ Script folder {} ({}) does not exist", root.path.display(), root.label);
+                }
+                continue;
+            }
+            let found = match &root.scripts {
+                Some(files) => Ok(self.load_script_files(&root.path, files, &root.label)),
+                None => self.load_scripts_from_directory(&root.path, &root.label, ScriptType::Rhai),
+            };
+            let found = match found {
+                Ok(found) => found,
+                Err(e) => {
+                    println!("⚠️ ScriptManager: Skipping root '{}': {}", root.label, e);
+                    continue;
+                }
+            };
+            for mut script in found {
+                script.read_only = root.read_only;
+                let key = script_key(&script.file_path);
+                match owners.get(&key) {
+                    Some(&owner) => {
+                        let winner = &self.scripts[owner];
+                        println!("⚠️ ScriptManager: {} is shadowed by {} ({})", script.file_path.display(), winner.file_path.display(), winner.category);
+                        self.shadowed.push(ShadowedScript { script, shadowed_by: winner.id.clone() });
+                    }
+                    None => {
+                        owners.insert(key, self.scripts.len());
+                        self.scripts.push(script);
+                    }
+                }
+            }
+        }
+        self.roots = roots;
+    }
+    
+    /// The highest-priority enabled root that scripts may be written to
+    pub fn writable_root(&self) -> Option<&ScriptRoot> {
+        self.roots.iter().find(|root| root.enabled && !root.read_only)
+    }
+    
+    /// Folders holding script templates: the user's own first, then the bundled ones
+    pub fn template_dirs(&self) -> Vec<PathBuf> {
+        vec![
+            crate::scaffold_kit::user_templates_dir(),
+            self.project_root.join("user_scripts").join("templates"),
+        ]
+    }
+    
+    /// Add a script file created while the app runs, without reloading every root
+    pub fn add_script(&mut self, file_path: &Path, root_label: &str) -> Result<ScriptInfo, String> {
+        let root = self.roots.iter().find(|root| root.label == root_label)
+            .ok_or_else(|| format!("Script root '{}' not found", root_label))?;
+        let mut script_info = self.create_script_info(file_path, &root.label, ScriptType::Rhai)?;
+        script_info.read_only = root.read_only;
+        self.scripts.retain(|script| script.id != script_info.id);
+        self.scripts.push(script_info.clone());
+        Ok(script_info)
+    }
+    
+    /// Load scripts from a specific directory, sorted by file name
     fn load_scripts_from_directory(
-        &mut self,
+        &self,
         dir: &Path,
         category: &str,
         script_type: ScriptType,
-    ) -> Result<(), String> {
+    ) -> Result<Vec<ScriptInfo>, String> {
         let entries = fs::read_dir(dir)
             .map_err(|e| format!("Failed to read directory {:?}: {}", dir, e))?;
 
+        let mut paths = Vec::new();
         for entry in entries {
             let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-            let path = entry.path();
+            paths.push(entry.path());
+        }
+        paths.sort();
 
+        let mut scripts = Vec::new();
+        for path in paths {
             // Only process .rhai files
             if let Some(extension) = path.extension() {
                 if extension == "rhai" {
                     match self.create_script_info(&path, category, script_type.clone()) {
                         Ok(script_info) => {
                             println!("🟣 ScriptManager: Found script: {}", script_info.name);
-                            self.scripts.push(script_info);
+                            scripts.push(script_info);
                         }
                         Err(e) => {
                             eprintln!("Warning: Failed to process script {:?}: {}", path, e);
@@ -134,10 +231,31 @@ impl ScriptManager {
             }
         }
 
-        Ok(())
+        Ok(scripts)
+    }
+
+    /// Load the listed scripts of a package, skipping any that fail
+    fn load_script_files(&self, dir: &Path, files: &[PathBuf], category: &str) -> Vec<ScriptInfo> {
+        files.iter()
+            .filter_map(|file| match self.create_script_info(&dir.join(file), category, ScriptType::Rhai) {
+                Ok(script_info) => Some(script_info),
+                Err(e) => {
+                    eprintln!("Warning: Failed to process script {:?}: {}", dir.join(file), e);
+                    None
+                }
+            })
+            .collect()
     }
 
     /// Create script info from file path
+    /// Generate a unique ID from the root label and file name
+    pub fn script_id(category: &str, file_stem: &str) -> String {
+        format!("rhai_{}_{}",
+            category.to_lowercase().replace(" ", "_"),
+            file_stem.replace(" ", "_")
+        )
+    }
+
     fn create_script_info(
         &self,
         file_path: &Path,
@@ -149,11 +267,7 @@ impl ScriptManager {
             .and_then(|s| s.to_str())
             .ok_or("Invalid file name")?;
 
-        // Generate a unique ID from the file path
-        let id = format!("rhai_{}_{}",
-            category.to_lowercase().replace(" ", "_"),
-            file_name.replace(" ", "_")
-        );
+        let id = Self::script_id(category, file_name);
 
         // Try to extract metadata from the script file
         let (name, description) = self.extract_script_metadata(file_path)?;
@@ -165,6 +279,7 @@ impl ScriptManager {
             file_path: file_path.to_path_buf(),
             category: category.to_string(),
             script_type,
+            read_only: false,
         })
     }
 
@@ -230,6 +345,11 @@ impl ScriptManager {
     }
 }
 
+/// Scripts with the same file name (ignoring case) shadow each other across roots
+fn script_key(file_path: &Path) -> String {
+    file_path.file_stem().map(|stem| stem.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,4 +389,45 @@ mod tests {
         // Clean up
         let _ = fs::remove_file(&test_file);
     }
+
+    #[test]
+    fn test_root_priority_and_shadowing() {
+        let base = std::env::temp_dir().join(format!("snaprun_roots_{}", uuid::Uuid::new_v4()));
+        let root = |label: &str, priority: i32, read_only: bool| {
+            let path = base.join(label);
+            fs::create_dir_all(&path).unwrap();
+            ScriptRoot { label: label.to_string(), path, priority, read_only, enabled: true, scripts: None }
+        };
+        let personal = root("Personal", 100, false);
+        let team = root("Team", 50, true);
+        let repo = root("Repo", 50, false);
+        fs::write(personal.path.join("deploy.rhai"), "1").unwrap();
+        fs::write(team.path.join("Deploy.rhai"), "2").unwrap();
+        fs::write(team.path.join("report.rhai"), "3").unwrap();
+        fs::write(repo.path.join("report.rhai"), "4").unwrap();
+        fs::write(repo.path.join("lint.rhai"), "5").unwrap();
+
+        let mut manager = ScriptManager::new(PathBuf::new());
+        let mut settings = ScriptSettings { user_dir: Some(base.join("missing")), ..Default::default() };
+        settings.roots = vec![repo.clone(), team.clone(), personal.clone()];
+        let roots = manager.script_roots(&settings, &base.join("packages"));
+        let labels: Vec<&str> = roots.iter().map(|root| root.label.as_str()).collect();
+        assert_eq!(labels, vec!["Custom", "Personal", "Repo", "Team", "Legacy", "Built-in"]);
+
+        manager.load_roots(roots);
+        let ids: Vec<&str> = manager.scripts.iter().map(|script| script.id.as_str()).collect();
+        assert_eq!(ids, vec!["rhai_personal_deploy", "rhai_repo_lint", "rhai_repo_report"]);
+        let shadowed: Vec<(&str, &str)> = manager.shadowed.iter().map(|s| (s.script.id.as_str(), s.shadowed_by.as_str())).collect();
+        assert_eq!(shadowed, vec![("rhai_team_Deploy", "rhai_personal_deploy"), ("rhai_team_report", "rhai_repo_report")]);
+        assert_eq!(manager.writable_root().unwrap().label, "Custom");
+
+        // Disabling a root brings the shadowed scripts back
+        let mut roots = manager.roots.clone();
+        roots.iter_mut().filter(|root| root.label == "Repo").for_each(|root| root.enabled = false);
+        manager.load_roots(roots);
+        let report = manager.scripts.iter().find(|script| script.id == "rhai_team_report").unwrap();
+        assert!(report.read_only);
+        assert_eq!(manager.shadowed.len(), 1);
+        let _ = fs::remove_dir_all(&base);
+    }
 }
@@ -0,0 +1,554 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rhai::{Engine, Array, Dynamic, EvalAltResult, Map};
+use serde::{Deserialize, Serialize};
+use crate::fs_kit::FileSystemKit;
+use crate::kits::ui_kit::Kit;
+use crate::logging;
+
+/// Unlocks passphrase vaults without a prompt (CI, headless runs)
+pub const PASSPHRASE_ENV: &str = "SNAPRUN_VAULT_PASSPHRASE";
+
+const VAULT_VERSION: u32 = 1;
+const CHECK_AAD: &[u8] = b"snaprun-vault-check";
+const MAX_NAME_LEN: usize = 128;
+
+type VaultKey = [u8; 32];
+
+/// Which passphrase a prompt is asking for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassphraseRequest {
+    Unlock,
+    New,
+    Confirm,
+}
+
+type PassphrasePrompt<'a> = &'a dyn Fn(PassphraseRequest) -> Result<String, String>;
+
+// Keys of vaults unlocked in this process, so the passphrase is asked once per session
+static UNLOCKED: OnceLock<Mutex<HashMap<PathBuf, VaultKey>>> = OnceLock::new();
+
+// Serializes read-modify-write of vault files between runners. Never held while a prompt is
+// open, so one runner waiting for a passphrase doesn't block the others.
+static VAULT_LOCK: Mutex<()> = Mutex::new(());
+
+fn unlocked() -> &'static Mutex<HashMap<PathBuf, VaultKey>> {
+    UNLOCKED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Where a vault's key comes from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    Keyring,
+    Passphrase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KdfParams {
+    algorithm: String,
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Sealed {
+    nonce: String,
+    data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SecretEntry {
+    #[serde(flatten)]
+    sealed: Sealed,
+    updated: String,
+}
+
+/// On-disk layout. Secret names are stored in clear text; values are encrypted
+/// with XChaCha20-Poly1305, bound to their name as associated data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    key_source: KeySource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    /// Names this vault's own keyring entry, so vaults never share or overwrite a key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keyring_id: Option<String>,
+    check: Sealed,
+    #[serde(default)]
+    secrets: BTreeMap<String, SecretEntry>,
+}
+
+/// An unlocked secrets vault
+pub struct Vault {
+    path: PathBuf,
+    key: VaultKey,
+    file: VaultFile,
+}
+
+impl Vault {
+    /// Open the vault at `path`, creating it when missing, and return it with VAULT_LOCK held.
+    /// A new vault uses `new_key`, falling back to a passphrase when the OS keyring is unavailable.
+    fn open(path: &Path, new_key: KeySource, prompt: PassphrasePrompt) -> Result<(MutexGuard<'static, ()>, Vault), String> {
+        loop {
+            let guard = VAULT_LOCK.lock().map_err(|e| e.to_string())?;
+            let Some(file) = Self::read(path)? else {
+                if new_key == KeySource::Keyring {
+                    let keyring_id = uuid::Uuid::new_v4().to_string();
+                    if let Some(key) = os_keyring::create_key(&keyring_id) {
+                        return Ok((guard, Self::create(path, KeySource::Keyring, None, Some(keyring_id), key)?));
+                    }
+                }
+                drop(guard);
+                let (kdf, key) = Self::new_passphrase_key(prompt)?;
+                let guard = VAULT_LOCK.lock().map_err(|e| e.to_string())?;
+                // Another runner may have created the vault while we were asking
+                if Self::read(path)?.is_some() {
+                    continue;
+                }
+                return Ok((guard, Self::create(path, KeySource::Passphrase, Some(kdf), None, key)?));
+            };
+            if let Some(key) = Self::cached_key(path, &file) {
+                return Ok((guard, Vault { path: path.to_path_buf(), key, file }));
+            }
+            // Ask without the lock, then read the file again under it
+            drop(guard);
+            Self::unlock(path, &file, prompt)?;
+        }
+    }
+
+    fn read(path: &Path) -> Result<Option<VaultFile>, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read secrets vault '{}': {}", path.display(), e)),
+        };
+        let file: VaultFile = serde_json::from_str(&text)
+            .map_err(|e| format!("Secrets vault '{}' is corrupt: {}", path.display(), e))?;
+        if file.version > VAULT_VERSION {
+            return Err(format!("Secrets vault version {} is newer than this SnapRun supports", file.version));
+        }
+        Ok(Some(file))
+    }
+
+    /// Ask for a new passphrase twice and derive the key for a new vault
+    fn new_passphrase_key(prompt: PassphrasePrompt) -> Result<(KdfParams, VaultKey), String> {
+        let passphrase = prompt(PassphraseRequest::New)?;
+        if prompt(PassphraseRequest::Confirm)? != passphrase {
+            return Err("The vault passphrases don't match".to_string());
+        }
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let defaults = Params::default();
+        let kdf = KdfParams {
+            algorithm: "argon2id".to_string(),
+            salt: STANDARD.encode(salt),
+            memory_kib: defaults.m_cost(),
+            iterations: defaults.t_cost(),
+            parallelism: defaults.p_cost(),
+        };
+        let key = derive_key(&kdf, &passphrase)?;
+        Ok((kdf, key))
+    }
+
+    fn create(
+        path: &Path,
+        key_source: KeySource,
+        kdf: Option<KdfParams>,
+        keyring_id: Option<String>,
+        key: VaultKey,
+    ) -> Result<Vault, String> {
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            key_source,
+            kdf,
+            keyring_id,
+            check: seal(&key, CHECK_AAD, CHECK_AAD)?,
+            secrets: BTreeMap::new(),
+        };
+        let vault = Vault { path: path.to_path_buf(), key, file };
+        vault.save()?;
+        restrict_permissions(path);
+        remember_key(path, key);
+        println!("🟣 SecretsKit: Created secrets vault at {} ({:?} key)", path.display(), key_source);
+        Ok(vault)
+    }
+
+    /// The key unlocked earlier this session, if it still opens `file`
+    fn cached_key(path: &Path, file: &VaultFile) -> Option<VaultKey> {
+        let key = *unlocked().lock().ok()?.get(path)?;
+        open_sealed(&key, CHECK_AAD, &file.check).ok().map(|_| key)
+    }
+
+    fn unlock(path: &Path, file: &VaultFile, prompt: PassphrasePrompt) -> Result<VaultKey, String> {
+        let key = match file.key_source {
+            KeySource::Keyring => {
+                let keyring_id = file.keyring_id.as_deref().ok_or("Secrets vault is missing its keyring id")?;
+                os_keyring::load_key(keyring_id)?
+            }
+            KeySource::Passphrase => {
+                let kdf = file.kdf.as_ref().ok_or("Secrets vault is missing its key derivation settings")?;
+                derive_key(kdf, &prompt(PassphraseRequest::Unlock)?)?
+            }
+        };
+        open_sealed(&key, CHECK_AAD, &file.check).map_err(|_| match file.key_source {
+            KeySource::Passphrase => "Wrong vault passphrase".to_string(),
+            KeySource::Keyring => "The OS keyring key does not match the secrets vault".to_string(),
+        })?;
+        remember_key(path, key);
+        Ok(key)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+        }
+        let json = serde_json::to_vec_pretty(&self.file).map_err(|e| e.to_string())?;
+        FileSystemKit::write_atomic(&self.path, &json, true)
+            .map_err(|e| format!("Failed to write secrets vault '{}': {}", self.path.display(), e))
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>, String> {
+        let Some(entry) = self.file.secrets.get(name) else { return Ok(None) };
+        let plain = open_sealed(&self.key, name.as_bytes(), &entry.sealed)
+            .map_err(|_| format!("Secret '{}' could not be decrypted; the vault may have been tampered with", name))?;
+        String::from_utf8(plain).map(Some).map_err(|_| format!("Secret '{}' is not valid UTF-8", name))
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let entry = SecretEntry {
+            sealed: seal(&self.key, name.as_bytes(), value.as_bytes())?,
+            updated: chrono::Local::now().to_rfc3339(),
+        };
+        self.file.secrets.insert(name.to_string(), entry);
+        self.save()
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<bool, String> {
+        if self.file.secrets.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save().map(|_| true)
+    }
+}
+
+fn remember_key(path: &Path, key: VaultKey) {
+    if let Ok(mut keys) = unlocked().lock() {
+        keys.insert(path.to_path_buf(), key);
+    }
+}
+
+fn derive_key(kdf: &KdfParams, passphrase: &str) -> Result<VaultKey, String> {
+    if kdf.algorithm != "argon2id" {
+        return Err(format!("Unsupported vault key derivation '{}'", kdf.algorithm));
+    }
+    let salt = STANDARD.decode(&kdf.salt).map_err(|_| "Secrets vault salt is corrupt".to_string())?;
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32)).map_err(|e| e.to_string())?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("Failed to derive the vault key: {}", e))?;
+    Ok(key)
+}
+
+fn seal(key: &VaultKey, aad: &[u8], plain: &[u8]) -> Result<Sealed, String> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let data = cipher.encrypt(&nonce, Payload { msg: plain, aad }).map_err(|_| "Encryption failed".to_string())?;
+    Ok(Sealed { nonce: STANDARD.encode(nonce), data: STANDARD.encode(data) })
+}
+
+fn open_sealed(key: &VaultKey, aad: &[u8], sealed: &Sealed) -> Result<Vec<u8>, ()> {
+    let nonce = STANDARD.decode(&sealed.nonce).map_err(|_| ())?;
+    let data = STANDARD.decode(&sealed.data).map_err(|_| ())?;
+    if nonce.len() != 24 {
+        return Err(());
+    }
+    XChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &data, aad })
+        .map_err(|_| ())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) {}
+
+#[cfg(any(windows, target_os = "macos"))]
+mod os_keyring {
+    //! The vault key lives in the Windows Credential Manager / macOS Keychain
+    use super::*;
+
+    const SERVICE: &str = "SnapRun";
+
+    fn entry(keyring_id: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(SERVICE, &format!("secrets-vault-key-{}", keyring_id))
+            .map_err(|e| format!("OS keyring unavailable: {}", e))
+    }
+
+    pub fn load_key(keyring_id: &str) -> Result<VaultKey, String> {
+        let encoded = entry(keyring_id)?.get_password().map_err(|e| format!("Failed to read the vault key from the OS keyring: {}", e))?;
+        STANDARD.decode(encoded).ok()
+            .and_then(|bytes| VaultKey::try_from(bytes.as_slice()).ok())
+            .ok_or_else(|| "The vault key in the OS keyring is corrupt".to_string())
+    }
+
+    /// Store a fresh random key under a new entry, or None when the keyring cannot be used.
+    /// An existing entry is never overwritten.
+    pub fn create_key(keyring_id: &str) -> Option<VaultKey> {
+        let entry = entry(keyring_id).ok()?;
+        if !matches!(entry.get_password(), Err(keyring::Error::NoEntry)) {
+            return None;
+        }
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        entry.set_password(&STANDARD.encode(key)).ok()?;
+        // Only trust the keyring if the key reads back intact
+        (load_key(keyring_id).ok()? == key).then_some(key)
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod os_keyring {
+    //! Linux desktops and CI use the passphrase-protected file instead
+    use super::*;
+
+    pub fn load_key(_keyring_id: &str) -> Result<VaultKey, String> {
+        Err("This vault is protected by an OS keyring, which is not available on this platform".to_string())
+    }
+
+    pub fn create_key(_keyring_id: &str) -> Option<VaultKey> {
+        None
+    }
+}
+
+/// Encrypted secrets for Rhai scripts. Values are never listed and are masked in logs once revealed.
+pub struct SecretsKit;
+
+impl SecretsKit {
+    /// Register the secrets functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine, ui: Option<Arc<Mutex<Kit>>>) {
+        println!("🟣 SecretsKit: Registering secrets vault functions");
+
+        {
+            let ui = ui.clone();
+            engine.register_fn("secret_get", move |name: &str| -> Result<String, Box<EvalAltResult>> {
+                let path = Self::vault_path();
+                Ok(Self::get(&path, name, Self::new_key_source(), &|request| Self::ask_passphrase(&ui, request))?)
+            });
+        }
+        {
+            let ui = ui.clone();
+            engine.register_fn("secret_set", move |name: &str| -> Result<bool, Box<EvalAltResult>> {
+                let kit = ui.as_ref().ok_or("secret_set(name) needs the UI to ask for the value; use secret_set(name, value)")?;
+                let value = kit.lock().map_err(|e| e.to_string())?
+                    .ask_masked_input_sync(&format!("Value for secret '{}'", name))?;
+                if value.is_empty() {
+                    return Err(format!("No value entered for secret '{}'", name).into());
+                }
+                let path = Self::vault_path();
+                Self::set(&path, name, &value, Self::new_key_source(), &|request| Self::ask_passphrase(&ui, request))?;
+                Ok(true)
+            });
+        }
+        {
+            let ui = ui.clone();
+            engine.register_fn("secret_set", move |name: &str, value: &str| -> Result<bool, Box<EvalAltResult>> {
+                let path = Self::vault_path();
+                Self::set(&path, name, value, Self::new_key_source(), &|request| Self::ask_passphrase(&ui, request))?;
+                Ok(true)
+            });
+        }
+        {
+            let ui = ui.clone();
+            engine.register_fn("secret_delete", move |name: &str| -> Result<bool, Box<EvalAltResult>> {
+                let path = Self::vault_path();
+                Ok(Self::delete(&path, name, Self::new_key_source(), &|request| Self::ask_passphrase(&ui, request))?)
+            });
+        }
+        engine.register_fn("secret_exists", |name: &str| -> Result<bool, Box<EvalAltResult>> {
+            Ok(Self::list(&Self::vault_path())?.iter().any(|(existing, _)| existing == name))
+        });
+        engine.register_fn("secret_list", || -> Result<Array, Box<EvalAltResult>> {
+            Ok(Self::list(&Self::vault_path())?.into_iter().map(|(name, updated)| {
+                let mut info = Map::new();
+                info.insert("name".into(), Dynamic::from(name));
+                info.insert("updated".into(), Dynamic::from(updated));
+                Dynamic::from_map(info)
+            }).collect())
+        });
+        engine.register_fn("secret_lock", || Self::lock(&Self::vault_path()));
+
+        println!("🟣 SecretsKit: All secrets vault functions registered");
+    }
+
+    pub fn vault_path() -> PathBuf {
+        match dirs::document_dir() {
+            Some(docs_dir) => docs_dir.join("SnapRun").join("secrets.vault"),
+            None => PathBuf::from("./user_data/secrets.vault"),
+        }
+    }
+
+    /// Reveal a secret; its value is redacted from all later log output
+    pub fn get(path: &Path, name: &str, new_key: KeySource, prompt: PassphrasePrompt) -> Result<String, String> {
+        Self::validate_name(name)?;
+        let missing = || format!("Secret '{}' is not set; store it with secret_set(\"{}\")", name, name);
+        if !Self::read_names(path)?.iter().any(|(existing, _)| existing == name) {
+            return Err(missing());
+        }
+        let (_guard, vault) = Vault::open(path, new_key, prompt)?;
+        let value = vault.get(name)?.ok_or_else(missing)?;
+        logging::register_secret(&value);
+        Ok(value)
+    }
+
+    pub fn set(path: &Path, name: &str, value: &str, new_key: KeySource, prompt: PassphrasePrompt) -> Result<(), String> {
+        Self::validate_name(name)?;
+        logging::register_secret(value);
+        let (_guard, mut vault) = Vault::open(path, new_key, prompt)?;
+        vault.set(name, value)
+    }
+
+    pub fn delete(path: &Path, name: &str, new_key: KeySource, prompt: PassphrasePrompt) -> Result<bool, String> {
+        if !Self::read_names(path)?.iter().any(|(existing, _)| existing == name) {
+            return Ok(false);
+        }
+        let (_guard, mut vault) = Vault::open(path, new_key, prompt)?;
+        vault.delete(name)
+    }
+
+    /// Names and last-update times; never needs the key and never reveals values
+    pub fn list(path: &Path) -> Result<Vec<(String, String)>, String> {
+        let _guard = VAULT_LOCK.lock().map_err(|e| e.to_string())?;
+        Self::read_names(path)
+    }
+
+    fn read_names(path: &Path) -> Result<Vec<(String, String)>, String> {
+        Ok(Vault::read(path)?
+            .map(|file| file.secrets.into_iter().map(|(name, entry)| (name, entry.updated)).collect())
+            .unwrap_or_default())
+    }
+
+    /// Forget the unlocked key so the next access asks for the passphrase again
+    pub fn lock(path: &Path) {
+        if let Ok(mut keys) = unlocked().lock() {
+            keys.remove(path);
+        }
+    }
+
+    fn validate_name(name: &str) -> Result<(), String> {
+        if name.trim().is_empty() || name.len() > MAX_NAME_LEN || name.chars().any(char::is_control) {
+            return Err(format!("Invalid secret name '{}'", name.escape_debug()));
+        }
+        Ok(())
+    }
+
+    /// New vaults use the OS keyring unless a passphrase is set in the environment
+    fn new_key_source() -> KeySource {
+        if std::env::var_os(PASSPHRASE_ENV).is_some() { KeySource::Passphrase } else { KeySource::Keyring }
+    }
+
+    /// The environment wins so CI never blocks on a prompt; otherwise ask through a masked input
+    fn ask_passphrase(ui: &Option<Arc<Mutex<Kit>>>, request: PassphraseRequest) -> Result<String, String> {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+            return Ok(passphrase);
+        }
+        let kit = ui.as_ref().ok_or_else(|| format!("Set {} to unlock the secrets vault", PASSPHRASE_ENV))?;
+        let message = match request {
+            PassphraseRequest::Unlock => "Secrets vault passphrase",
+            PassphraseRequest::New => "Choose a passphrase for the new secrets vault",
+            PassphraseRequest::Confirm => "Enter the new vault passphrase again",
+        };
+        let passphrase = kit.lock().map_err(|e| e.to_string())?.ask_masked_input_sync(message)?;
+        if passphrase.is_empty() {
+            return Err("No vault passphrase entered".to_string());
+        }
+        Ok(passphrase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_vault() -> PathBuf {
+        std::env::temp_dir().join(format!("snaprun_secrets_{}", uuid::Uuid::new_v4())).join("secrets.vault")
+    }
+
+    #[test]
+    fn test_set_get_list_delete() {
+        let path = test_vault();
+        let prompt = |_: PassphraseRequest| Ok("correct horse".to_string());
+
+        SecretsKit::set(&path, "api_token", "tok-123456", KeySource::Passphrase, &prompt).unwrap();
+        SecretsKit::set(&path, "db_password", "hunter22", KeySource::Passphrase, &prompt).unwrap();
+        assert_eq!(SecretsKit::get(&path, "api_token", KeySource::Passphrase, &prompt).unwrap(), "tok-123456");
+        assert!(SecretsKit::get(&path, "missing", KeySource::Passphrase, &prompt).unwrap_err().contains("not set"));
+
+        let names: Vec<String> = SecretsKit::list(&path).unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["api_token", "db_password"]);
+        let on_disk = fs::read_to_string(&path).unwrap();
+        assert!(on_disk.contains("\"passphrase\"") && !on_disk.contains("tok-123456"));
+
+        assert!(SecretsKit::delete(&path, "db_password", KeySource::Passphrase, &prompt).unwrap());
+        assert!(!SecretsKit::delete(&path, "db_password", KeySource::Passphrase, &prompt).unwrap());
+        assert!(logging::redact("token=tok-123456").ends_with("=***"));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_wrong_passphrase_and_tampering() {
+        let path = test_vault();
+        SecretsKit::set(&path, "key", "value-1", KeySource::Passphrase, &|_| Ok("right".to_string())).unwrap();
+        SecretsKit::lock(&path);
+        let err = SecretsKit::get(&path, "key", KeySource::Passphrase, &|_| Ok("wrong".to_string())).unwrap_err();
+        assert_eq!(err, "Wrong vault passphrase");
+
+        // Swapping ciphertexts between names is caught by the associated data
+        let mut file: VaultFile = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let sealed = file.secrets["key"].clone();
+        file.secrets.insert("other".to_string(), sealed);
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        let right = |_: PassphraseRequest| Ok("right".to_string());
+        assert_eq!(SecretsKit::get(&path, "key", KeySource::Passphrase, &right).unwrap(), "value-1");
+        assert!(SecretsKit::get(&path, "other", KeySource::Passphrase, &right).unwrap_err().contains("tampered"));
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_new_passphrase_is_confirmed_without_holding_the_lock() {
+        let path = test_vault();
+        let mismatch = |request: PassphraseRequest| Ok(if request == PassphraseRequest::Confirm { "typo" } else { "secret" }.to_string());
+        assert!(SecretsKit::set(&path, "key", "value", KeySource::Passphrase, &mismatch).unwrap_err().contains("don't match"));
+        assert!(!path.exists());
+
+        // Other runners can use the vault while this one waits for a passphrase
+        let other = path.clone();
+        let prompt = move |_: PassphraseRequest| {
+            let other = other.clone();
+            let (sender, receiver) = std::sync::mpsc::channel();
+            std::thread::spawn(move || sender.send(SecretsKit::list(&other).is_ok()));
+            assert_eq!(receiver.recv_timeout(std::time::Duration::from_secs(5)), Ok(true));
+            Ok("secret".to_string())
+        };
+        SecretsKit::set(&path, "key", "value", KeySource::Passphrase, &prompt).unwrap();
+        SecretsKit::lock(&path);
+        assert_eq!(SecretsKit::get(&path, "key", KeySource::Passphrase, &prompt).unwrap(), "value");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::fs_kit::FileSystemKit;
+
+/// Bumped whenever the file layout changes; older files are migrated on load
+pub const SETTINGS_VERSION: u32 = 1;
+
+pub const DEFAULT_SHOW_HOTKEY: &str = "CmdOrCtrl+Shift+J";
+
+/// Priorities of the built-in folders; configured roots default to DEFAULT_ROOT_PRIORITY
+pub const USER_ROOT_PRIORITY: i32 = 100;
+pub const DEFAULT_ROOT_PRIORITY: i32 = 50;
+pub const PACKAGE_ROOT_PRIORITY: i32 = 20;
+pub const LEGACY_ROOT_PRIORITY: i32 = 10;
+pub const BUILT_IN_ROOT_PRIORITY: i32 = 0;
+
+/// Labels used by the folders SnapRun always loads
+pub const RESERVED_ROOT_LABELS: &[&str] = &["Custom", "Legacy", "Built-in"];
+
+/// Package roots are labelled `pkg:<name>`, so configured roots can't use this prefix
+pub const PACKAGE_LABEL_PREFIX: &str = "pkg:";
+
+/// Environment variables that override a setting for this process only (never saved)
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("SNAPRUN_SCRIPTS", "scripts.user_dir"),
+    ("SNAPRUN_HOME", "scripts.user_dir"),
+    ("WIN_SCRIPT2_PATH", "scripts.bundled_dir"),
+    ("SNAPRUN_LOG_LEVEL", "logging.level"),
+    ("SNAPRUN_THEME", "theme"),
+];
+
+// Settings as stored on disk, loaded on first use
+static STORED: RwLock<Option<Settings>> = RwLock::new(None);
+
+/// Application settings, stored as `settings.json` or `settings.toml` in the config directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub version: u32,
+    pub theme: Theme,
+    pub scripts: ScriptSettings,
+    pub hotkeys: HotkeySettings,
+    pub logging: LoggingSettings,
+    pub sandbox: SandboxSettings,
+    /// Environment variables set for every script run
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptSettings {
+    /// Folder holding `built_in_scripts`; defaults to the `user_scripts` folder of the app
+    pub bundled_dir: Option<PathBuf>,
+    /// Folder for the user's own scripts; defaults to Documents/SnapRun/Scripts
+    pub user_dir: Option<PathBuf>,
+    /// More folders to load scripts from, e.g. a team share or a repo checkout
+    pub roots: Vec<ScriptRoot>,
+}
+
+/// A folder of scripts. When two roots hold a script with the same file name, the higher priority wins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptRoot {
+    /// Shown as the script category and used in script ids
+    pub label: String,
+    pub path: PathBuf,
+    pub priority: i32,
+    /// New scripts, templates and packages are never written here
+    pub read_only: bool,
+    pub enabled: bool,
+    /// Only these files, relative to `path`; set for installed packages, never saved
+    #[serde(skip)]
+    pub scripts: Option<Vec<PathBuf>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HotkeySettings {
+    /// Global shortcut that shows the window, e.g. "CmdOrCtrl+Shift+J"
+    pub show_window: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSettings {
+    pub level: LogLevelSetting,
+    /// Log files untouched for longer than this are deleted at startup
+    pub retention_days: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SandboxSettings {
+    /// Runs started without an explicit choice only record file and process changes
+    pub dry_run_by_default: bool,
+    /// Journal every run so it can be undone
+    pub journal_by_default: bool,
+    /// Maximum nesting of `run_script` calls
+    pub max_script_depth: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevelSetting {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    System,
+    Dark,
+    Light,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            theme: Theme::System,
+            scripts: ScriptSettings::default(),
+            hotkeys: HotkeySettings::default(),
+            logging: LoggingSettings::default(),
+            sandbox: SandboxSettings::default(),
+            env: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ScriptRoot {
+    fn default() -> Self {
+        Self { label: String::new(), path: PathBuf::new(), priority: DEFAULT_ROOT_PRIORITY, read_only: false, enabled: true, scripts: None }
+    }
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self { show_window: DEFAULT_SHOW_HOTKEY.to_string() }
+    }
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self { level: LogLevelSetting::Info, retention_days: 14 }
+    }
+}
+
+impl Default for SandboxSettings {
+    fn default() -> Self {
+        Self { dry_run_by_default: false, journal_by_default: false, max_script_depth: crate::rhai_engine::DEFAULT_MAX_SCRIPT_DEPTH }
+    }
+}
+
+impl LogLevelSetting {
+    pub fn to_log_level(self) -> crate::logging::LogLevel {
+        use crate::logging::LogLevel;
+        match self {
+            LogLevelSetting::Error => LogLevel::Error,
+            LogLevelSetting::Warn => LogLevel::Warn,
+            LogLevelSetting::Info => LogLevel::Info,
+            LogLevelSetting::Debug => LogLevel::Debug,
+            LogLevelSetting::Trace => LogLevel::Trace,
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(text.trim().to_ascii_lowercase())).ok()
+    }
+}
+
+impl Theme {
+    fn parse(text: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(text.trim().to_ascii_lowercase())).ok()
+    }
+}
+
+impl ScriptSettings {
+    pub fn user_scripts_dir(&self) -> PathBuf {
+        if let Some(dir) = &self.user_dir {
+            return dir.clone();
+        }
+        match dirs::document_dir() {
+            Some(docs_dir) => docs_dir.join("SnapRun").join("Scripts"),
+            None => PathBuf::from("./user_scripts"),
+        }
+    }
+
+    /// The app's `user_scripts` folder; in development it sits next to `src-tauri`
+    pub fn bundled_scripts_dir(&self) -> PathBuf {
+        if let Some(dir) = &self.bundled_dir {
+            return dir.clone();
+        }
+        let current_dir = std::env::current_dir().unwrap_or_default();
+        match current_dir.parent() {
+            Some(parent) if current_dir.file_name().and_then(|n| n.to_str()) == Some("src-tauri") => parent.join("user_scripts"),
+            _ => current_dir.join("user_scripts"),
+        }
+    }
+}
+
+impl Settings {
+    /// Check every value; the error names the offending setting
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != SETTINGS_VERSION {
+            return Err(format!("version: expected {}, found {}", SETTINGS_VERSION, self.version));
+        }
+        validate_hotkey(&self.hotkeys.show_window).map_err(|e| format!("hotkeys.show_window: {}", e))?;
+        if !(1..=3650).contains(&self.logging.retention_days) {
+            return Err("logging.retention_days: must be between 1 and 3650".to_string());
+        }
+        if !(1..=64).contains(&self.sandbox.max_script_depth) {
+            return Err("sandbox.max_script_depth: must be between 1 and 64".to_string());
+        }
+        let dirs = self.scripts.bundled_dir.iter().chain(&self.scripts.user_dir).chain(self.scripts.roots.iter().map(|root| &root.path));
+        if dirs.into_iter().any(|dir| dir.as_os_str().is_empty()) {
+            return Err("scripts: folder paths must not be empty".to_string());
+        }
+        let mut labels: Vec<String> = RESERVED_ROOT_LABELS.iter().map(|label| label.to_lowercase()).collect();
+        for root in &self.scripts.roots {
+            let label = root.label.trim();
+            if label.is_empty() {
+                return Err(format!("scripts.roots: '{}' needs a label", root.path.display()));
+            }
+            if labels.contains(&label.to_lowercase()) {
+                return Err(format!("scripts.roots: label '{}' is already used", label));
+            }
+            if label.to_lowercase().starts_with(PACKAGE_LABEL_PREFIX) {
+                return Err(format!("scripts.roots: label '{}' is reserved for packages", label));
+            }
+            labels.push(label.to_lowercase());
+        }
+        for (name, value) in &self.env {
+            if name.is_empty() || name.contains('=') || name.contains('\0') || value.contains('\0') {
+                return Err(format!("env: invalid variable '{}'", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply environment overrides; returns the `(variable, setting)` pairs that took effect
+    pub fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Vec<(String, String)> {
+        self.apply_env_with(lookup, dirs::document_dir().is_some())
+    }
+
+    /// SNAPRUN_HOME is only a fallback, as it always was: it applies when no scripts folder is
+    /// configured and there is no Documents folder to put Documents/SnapRun/Scripts in
+    fn apply_env_with(&mut self, lookup: impl Fn(&str) -> Option<String>, has_documents: bool) -> Vec<(String, String)> {
+        let mut applied = Vec::new();
+        let mut note = |variable: &str, setting: &str| applied.push((variable.to_string(), setting.to_string()));
+
+        if let Some(dir) = lookup("SNAPRUN_SCRIPTS").filter(|v| !v.is_empty()) {
+            self.scripts.user_dir = Some(PathBuf::from(dir));
+            note("SNAPRUN_SCRIPTS", "scripts.user_dir");
+        } else if let Some(home) = lookup("SNAPRUN_HOME").filter(|v| !v.is_empty() && !has_documents && self.scripts.user_dir.is_none()) {
+            self.scripts.user_dir = Some(PathBuf::from(home).join("Scripts"));
+            note("SNAPRUN_HOME", "scripts.user_dir");
+        }
+        if let Some(dir) = lookup("WIN_SCRIPT2_PATH").filter(|v| !v.is_empty()) {
+            self.scripts.bundled_dir = Some(PathBuf::from(dir));
+            note("WIN_SCRIPT2_PATH", "scripts.bundled_dir");
+        }
+        if let Some(level) = lookup("SNAPRUN_LOG_LEVEL") {
+            match LogLevelSetting::parse(&level) {
+                Some(level) => {
+                    self.logging.level = level;
+                    note("SNAPRUN_LOG_LEVEL", "logging.level");
+                }
+                None => println!("⚠️ Settings: ignoring SNAPRUN_LOG_LEVEL='{}'", level),
+            }
+        }
+        if let Some(theme) = lookup("SNAPRUN_THEME") {
+            match Theme::parse(&theme) {
+                Some(theme) => {
+                    self.theme = theme;
+                    note("SNAPRUN_THEME", "theme");
+                }
+                None => println!("⚠️ Settings: ignoring SNAPRUN_THEME='{}'", theme),
+            }
+        }
+        applied
+    }
+}
+
+/// Shortcut syntax accepted by the global shortcut plugin: modifiers joined with '+', then one key
+fn validate_hotkey(shortcut: &str) -> Result<(), String> {
+    const MODIFIERS: &[&str] = &["cmdorctrl", "commandorcontrol", "cmd", "command", "super", "meta", "ctrl", "control", "alt", "option", "shift"];
+    let parts: Vec<&str> = shortcut.split('+').map(str::trim).collect();
+    let (key, modifiers) = parts.split_last().ok_or("shortcut is empty")?;
+    if key.is_empty() || parts.iter().any(|part| part.is_empty()) {
+        return Err(format!("'{}' is not a valid shortcut", shortcut));
+    }
+    if MODIFIERS.contains(&key.to_ascii_lowercase().as_str()) {
+        return Err(format!("'{}' has no key after its modifiers", shortcut));
+    }
+    if let Some(unknown) = modifiers.iter().find(|m| !MODIFIERS.contains(&m.to_ascii_lowercase().as_str())) {
+        return Err(format!("unknown modifier '{}'", unknown));
+    }
+    Ok(())
+}
+
+pub fn settings_dir() -> PathBuf {
+    match dirs::config_dir() {
+        Some(config_dir) => config_dir.join("SnapRun"),
+        None => PathBuf::from("./user_data"),
+    }
+}
+
+/// `settings.toml` when the user created one, otherwise `settings.json`
+pub fn settings_path() -> PathBuf {
+    let dir = settings_dir();
+    let toml = dir.join("settings.toml");
+    if toml.is_file() { toml } else { dir.join("settings.json") }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+/// Read, migrate and validate a settings file; a missing file gives the defaults
+pub fn load_from(path: &Path) -> Result<Settings, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(format!("Failed to read '{}': {}", path.display(), e)),
+    };
+    let mut value: serde_json::Value = if is_toml(path) {
+        toml::from_str(&text).map_err(|e| format!("Invalid settings in '{}': {}", path.display(), e))?
+    } else {
+        serde_json::from_str(&text).map_err(|e| format!("Invalid settings in '{}': {}", path.display(), e))?
+    };
+    migrate(&mut value).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let settings: Settings = serde_json::from_value(value).map_err(|e| format!("Invalid settings in '{}': {}", path.display(), e))?;
+    settings.validate().map_err(|e| format!("Invalid settings in '{}': {}", path.display(), e))?;
+    Ok(settings)
+}
+
+/// Bring an older file up to SETTINGS_VERSION; files without a version are treated as version 1.
+/// Version 1 is the only one so far, so there is nothing to convert yet.
+fn migrate(value: &mut serde_json::Value) -> Result<(), String> {
+    let object = value.as_object_mut().ok_or("settings must be an object")?;
+    let version = object.get("version").and_then(|v| v.as_u64()).unwrap_or(1);
+    if version > SETTINGS_VERSION as u64 {
+        return Err(format!("settings version {} is newer than this SnapRun supports ({})", version, SETTINGS_VERSION));
+    }
+    object.insert("version".to_string(), SETTINGS_VERSION.into());
+    Ok(())
+}
+
+pub fn save_to(path: &Path, settings: &Settings) -> Result<(), String> {
+    settings.validate()?;
+    let text = if is_toml(path) {
+        toml::to_string_pretty(settings).map_err(|e| e.to_string())?
+    } else {
+        serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create '{}': {}", parent.display(), e))?;
+    }
+    FileSystemKit::write_atomic(path, text.as_bytes(), true).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))
+}
+
+/// Settings as saved, without environment overrides. An unreadable file falls back to the defaults.
+pub fn stored() -> Settings {
+    if let Some(settings) = STORED.read().ok().and_then(|stored| stored.clone()) {
+        return settings;
+    }
+    let path = settings_path();
+    let settings = load_from(&path).unwrap_or_else(|e| {
+        println!("⚠️ Settings: {}; using defaults", e);
+        Settings::default()
+    });
+    if let Ok(mut stored) = STORED.write() {
+        *stored = Some(settings.clone());
+    }
+    settings
+}
+
+/// Effective settings: the stored file plus environment overrides
+pub fn current() -> Settings {
+    effective(stored())
+}
+
+/// What `settings` would look like once saved, with environment overrides applied
+pub fn effective(mut settings: Settings) -> Settings {
+    settings.apply_env(|name| std::env::var(name).ok());
+    settings
+}
+
+/// Validate and save new settings, returning the effective result
+pub fn update(settings: Settings) -> Result<Settings, String> {
+    save_to(&settings_path(), &settings)?;
+    if let Ok(mut stored) = STORED.write() {
+        *stored = Some(settings);
+    }
+    Ok(current())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_save_and_validate() {
+        let dir = std::env::temp_dir().join(format!("snaprun_settings_{}", uuid::Uuid::new_v4()));
+        let json = dir.join("settings.json");
+        assert_eq!(load_from(&json).unwrap(), Settings::default());
+
+        let mut settings = Settings::default();
+        settings.logging.level = LogLevelSetting::Debug;
+        settings.scripts.roots.push(ScriptRoot { label: "Team".to_string(), path: dir.join("team"), read_only: true, ..Default::default() });
+        settings.env.insert("API_URL".to_string(), "https://example.test".to_string());
+        save_to(&json, &settings).unwrap();
+        assert_eq!(load_from(&json).unwrap(), settings);
+
+        // TOML works the same, and a missing version means the current one
+        let toml_path = dir.join("settings.toml");
+        fs::write(&toml_path, "theme = \"dark\"\n[sandbox]\ndry_run_by_default = true\n").unwrap();
+        let from_toml = load_from(&toml_path).unwrap();
+        assert!(from_toml.theme == Theme::Dark && from_toml.sandbox.dry_run_by_default);
+        save_to(&toml_path, &from_toml).unwrap();
+        assert_eq!(load_from(&toml_path).unwrap(), from_toml);
+
+        fs::write(&json, "{\"version\": 99}").unwrap();
+        assert!(load_from(&json).unwrap_err().contains("newer"));
+        fs::write(&json, "{\"logging\": {\"levle\": \"info\"}}").unwrap();
+        assert!(load_from(&json).unwrap_err().contains("levle"));
+
+        settings.hotkeys.show_window = "Ctrl+Shift".to_string();
+        assert!(settings.validate().unwrap_err().starts_with("hotkeys.show_window"));
+        settings.hotkeys.show_window = "Hyper+K".to_string();
+        assert!(settings.validate().is_err());
+        settings.hotkeys.show_window = "Alt+Space".to_string();
+        settings.sandbox.max_script_depth = 0;
+        assert!(settings.validate().unwrap_err().starts_with("sandbox.max_script_depth"));
+        settings.sandbox.max_script_depth = 8;
+        settings.scripts.roots.push(ScriptRoot { label: "built-in".to_string(), path: dir.join("x"), ..Default::default() });
+        assert!(settings.validate().unwrap_err().contains("already used"));
+        settings.scripts.roots[0].label = "Pkg:tools".to_string();
+        assert!(settings.validate().unwrap_err().contains("reserved for packages"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let vars: BTreeMap<&str, &str> = [("SNAPRUN_HOME", "/opt/snap"), ("WIN_SCRIPT2_PATH", "/app/user_scripts"), ("SNAPRUN_LOG_LEVEL", "TRACE"), ("SNAPRUN_THEME", "neon")].into();
+        let mut settings = Settings::default();
+        let applied = settings.apply_env_with(|name| vars.get(name).map(|v| v.to_string()), false);
+
+        assert_eq!(settings.scripts.user_scripts_dir(), PathBuf::from("/opt/snap").join("Scripts"));
+        assert_eq!(settings.scripts.bundled_scripts_dir(), PathBuf::from("/app/user_scripts"));
+        assert_eq!(settings.logging.level, LogLevelSetting::Trace);
+        assert_eq!(settings.theme, Theme::System);
+        let variables: Vec<&str> = applied.iter().map(|(variable, _)| variable.as_str()).collect();
+        assert_eq!(variables, vec!["SNAPRUN_HOME", "WIN_SCRIPT2_PATH", "SNAPRUN_LOG_LEVEL"]);
+
+        // With a Documents folder, SNAPRUN_HOME is ignored and the default location stays
+        let mut settings = Settings::default();
+        assert!(settings.apply_env_with(|name| vars.get(name).map(|v| v.to_string()), true).iter().all(|(variable, _)| variable != "SNAPRUN_HOME"));
+        assert_eq!(settings.scripts.user_dir, None);
+
+        // SNAPRUN_SCRIPTS wins over SNAPRUN_HOME
+        let mut settings = Settings::default();
+        settings.apply_env(|name| match name {
+            "SNAPRUN_SCRIPTS" => Some("/scripts".to_string()),
+            "SNAPRUN_HOME" => Some("/home".to_string()),
+            _ => None,
+        });
+        assert_eq!(settings.scripts.user_scripts_dir(), PathBuf::from("/scripts"));
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use rhai::{Engine, Array, Dynamic, EvalAltResult, FnPtr, NativeCallContext};
+use crate::data_format::{json_value_to_rhai, rhai_to_json_value};
+use crate::fs_kit::FileSystemKit;
+use crate::script_manager::ScriptManager;
+
+/// Namespace shared by all scripts
+pub const GLOBAL_NAMESPACE: &str = "global";
+
+/// How often store_update retries when another run changed the key in the meantime
+const MAX_UPDATE_ATTEMPTS: usize = 16;
+
+type StoreLocks = Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>;
+
+// One lock per store file, shared by every runner in the process
+static STORE_LOCKS: OnceLock<StoreLocks> = OnceLock::new();
+
+fn lock_for(path: &Path) -> Arc<Mutex<()>> {
+    let mut locks = STORE_LOCKS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap();
+    locks.entry(path.to_path_buf()).or_default().clone()
+}
+
+/// Persistent key-value storage for Rhai scripts, namespaced per script ID
+pub struct StoreKit;
+
+impl StoreKit {
+    /// Register all store functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine, script_id: Arc<Mutex<String>>) {
+        println!("🟣 StoreKit: Registering key-value store functions");
+
+        // Per-script namespace
+        {
+            let script_id = script_id.clone();
+            engine.register_fn("store_get", move |key: &str| -> Dynamic {
+                Self::get(&Self::current_store(&script_id), key, Dynamic::UNIT)
+            });
+        }
+        {
+            let script_id = script_id.clone();
+            engine.register_fn("store_get", move |key: &str, default: Dynamic| -> Dynamic {
+                Self::get(&Self::current_store(&script_id), key, default)
+            });
+        }
+        {
+            let script_id = script_id.clone();
+            engine.register_fn("store_set", move |key: &str, value: Dynamic| -> bool {
+                Self::set(&Self::current_store(&script_id), key, value)
+            });
+        }
+        {
+            let script_id = script_id.clone();
+            engine.register_fn("store_delete", move |key: &str| -> bool {
+                Self::delete(&Self::current_store(&script_id), key)
+            });
+        }
+        {
+            let script_id = script_id.clone();
+            engine.register_fn("store_keys", move || -> Array {
+                Self::keys(&Self::current_store(&script_id))
+            });
+        }
+        {
+            let script_id = script_id.clone();
+            engine.register_fn("store_update", move |context: NativeCallContext, key: &str, callback: FnPtr| -> Result<Dynamic, Box<EvalAltResult>> {
+                Self::update(&context, &Self::current_store(&script_id), key, callback)
+            });
+        }
+
+        // Global namespace shared between scripts
+        engine.register_fn("store_global_get", |key: &str| -> Dynamic {
+            Self::get(&Self::store_path(GLOBAL_NAMESPACE), key, Dynamic::UNIT)
+        });
+        engine.register_fn("store_global_get", |key: &str, default: Dynamic| -> Dynamic {
+            Self::get(&Self::store_path(GLOBAL_NAMESPACE), key, default)
+        });
+        engine.register_fn("store_global_set", |key: &str, value: Dynamic| -> bool {
+            Self::set(&Self::store_path(GLOBAL_NAMESPACE), key, value)
+        });
+        engine.register_fn("store_global_delete", |key: &str| -> bool {
+            Self::delete(&Self::store_path(GLOBAL_NAMESPACE), key)
+        });
+        engine.register_fn("store_global_keys", || -> Array {
+            Self::keys(&Self::store_path(GLOBAL_NAMESPACE))
+        });
+        engine.register_fn("store_global_update", |context: NativeCallContext, key: &str, callback: FnPtr| -> Result<Dynamic, Box<EvalAltResult>> {
+            Self::update(&context, &Self::store_path(GLOBAL_NAMESPACE), key, callback)
+        });
+
+        println!("🟣 StoreKit: All key-value store functions registered");
+    }
+
+    /// Path of the store file for a namespace
+    pub fn store_path(namespace: &str) -> PathBuf {
+        ScriptManager::get_script_data_path(namespace).join("store.json")
+    }
+
+    fn current_store(script_id: &Arc<Mutex<String>>) -> PathBuf {
+        Self::store_path(&script_id.lock().unwrap())
+    }
+
+    /// Read a value, returning `default` if the key is missing
+    pub fn get(store: &Path, key: &str, default: Dynamic) -> Dynamic {
+        let lock = lock_for(store);
+        let _guard = lock.lock().unwrap();
+
+        match Self::load(store) {
+            Ok(data) => match data.get(key) {
+                Some(value) => json_value_to_rhai(value.clone()).unwrap_or(default),
+                None => default,
+            },
+            Err(e) => {
+                println!("❌ Failed to read store '{}': {}", store.display(), e);
+                default
+            }
+        }
+    }
+
+    /// Write a value, replacing any existing one
+    pub fn set(store: &Path, key: &str, value: Dynamic) -> bool {
+        let value = match rhai_to_json_value(value) {
+            Ok(value) => value,
+            Err(e) => {
+                println!("❌ Failed to store key '{}': {}", key, e);
+                return false;
+            }
+        };
+
+        let lock = lock_for(store);
+        let _guard = lock.lock().unwrap();
+
+        let result = Self::load(store).and_then(|mut data| {
+            data.insert(key.to_string(), value);
+            Self::save(store, &data)
+        });
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                println!("❌ Failed to write store '{}': {}", store.display(), e);
+                false
+            }
+        }
+    }
+
+    /// Remove a key, returning whether it existed
+    pub fn delete(store: &Path, key: &str) -> bool {
+        let lock = lock_for(store);
+        let _guard = lock.lock().unwrap();
+
+        let result = Self::load(store).and_then(|mut data| {
+            if data.remove(key).is_some() {
+                Self::save(store, &data).map(|_| true)
+            } else {
+                Ok(false)
+            }
+        });
+        match result {
+            Ok(removed) => removed,
+            Err(e) => {
+                println!("❌ Failed to write store '{}': {}", store.display(), e);
+                false
+            }
+        }
+    }
+
+    /// List all keys in a store
+    pub fn keys(store: &Path) -> Array {
+        let lock = lock_for(store);
+        let _guard = lock.lock().unwrap();
+
+        match Self::load(store) {
+            Ok(data) => data.keys().map(|k| Dynamic::from(k.clone())).collect(),
+            Err(e) => {
+                println!("❌ Failed to read store '{}': {}", store.display(), e);
+                Array::new()
+            }
+        }
+    }
+
+    /// Replace a value with the result of `callback(old_value)`.
+    ///
+    /// The callback runs without holding the store lock, so it may use the store itself.
+    /// If another run changes the key meanwhile, the callback is run again on the new value.
+    pub fn update(context: &NativeCallContext, store: &Path, key: &str, callback: FnPtr) -> Result<Dynamic, Box<EvalAltResult>> {
+        let lock = lock_for(store);
+
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let old = {
+                let _guard = lock.lock().unwrap();
+                Self::load(store)?.get(key).cloned()
+            };
+
+            let old_value = match &old {
+                Some(value) => json_value_to_rhai(value.clone())?,
+                None => Dynamic::UNIT,
+            };
+            let new_value: Dynamic = callback.call_within_context(context, (old_value,))?;
+            let new_json = rhai_to_json_value(new_value.clone())?;
+
+            let _guard = lock.lock().unwrap();
+            let mut data = Self::load(store)?;
+            if data.get(key) == old.as_ref() {
+                data.insert(key.to_string(), new_json);
+                Self::save(store, &data)?;
+                return Ok(new_value);
+            }
+        }
+
+        Err(format!("store_update: key '{}' kept changing, gave up after {} attempts", key, MAX_UPDATE_ATTEMPTS).into())
+    }
+
+    fn load(store: &Path) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+        if !store.exists() {
+            return Ok(serde_json::Map::new());
+        }
+        let content = fs::read_to_string(store).map_err(|e| e.to_string())?;
+        if content.trim().is_empty() {
+            return Ok(serde_json::Map::new());
+        }
+        serde_json::from_str(&content).map_err(|e| format!("Invalid store file: {}", e))
+    }
+
+    /// Write the store to a temp file and rename it over the old one, so readers never see a partial file
+    fn save(store: &Path, data: &serde_json::Map<String, serde_json::Value>) -> Result<(), String> {
+        if let Some(parent) = store.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let content = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+        FileSystemKit::write_atomic(store, content.as_bytes(), true).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_store(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("snaprun_store_{}_{}", name, uuid::Uuid::new_v4()));
+        dir.join("store.json")
+    }
+
+    #[test]
+    fn test_set_get_delete_keys() {
+        let store = test_store("basic");
+
+        assert!(StoreKit::get(&store, "missing", Dynamic::UNIT).is_unit());
+        assert!(StoreKit::set(&store, "count", Dynamic::from(3_i64)));
+        assert!(StoreKit::set(&store, "name", Dynamic::from("todo".to_string())));
+        assert_eq!(StoreKit::get(&store, "count", Dynamic::UNIT).as_int().unwrap(), 3);
+        assert_eq!(StoreKit::keys(&store).len(), 2);
+
+        assert!(StoreKit::delete(&store, "count"));
+        assert!(!StoreKit::delete(&store, "count"));
+        assert_eq!(StoreKit::get(&store, "count", Dynamic::from(0_i64)).as_int().unwrap(), 0);
+
+        let _ = fs::remove_dir_all(store.parent().unwrap());
+    }
+
+    #[test]
+    fn test_update_from_script() {
+        let store = test_store("update");
+        let mut engine = Engine::new();
+        {
+            let store = store.clone();
+            engine.register_fn("bump", move |context: NativeCallContext, callback: FnPtr| -> Result<Dynamic, Box<EvalAltResult>> {
+                StoreKit::update(&context, &store, "hits", callback)
+            });
+        }
+
+        engine.run(r#"bump(|old| if old == () { 1 } else { old + 1 });"#).unwrap();
+        let result = engine.eval::<i64>(r#"bump(|old| old + 1)"#).unwrap();
+        assert_eq!(result, 2);
+        assert_eq!(StoreKit::get(&store, "hits", Dynamic::UNIT).as_int().unwrap(), 2);
+
+        let _ = fs::remove_dir_all(store.parent().unwrap());
+    }
+}
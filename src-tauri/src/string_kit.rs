@@ -0,0 +1,345 @@
+use regex::{Captures, Regex, RegexBuilder};
+use rhai::{Engine, Array, Dynamic, EvalAltResult, Map};
+
+/// Compiled regular expression exposed to Rhai as `Regex`
+#[derive(Debug, Clone)]
+pub struct ScriptRegex(pub Regex);
+
+/// Regular expressions and advanced string formatting for Rhai scripts
+pub struct StringKit;
+
+impl StringKit {
+    /// Register all regex and string formatting functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 StringKit: Registering regex and string formatting functions");
+
+        // One-off regex functions (text first, so they also work as methods)
+        engine.register_fn("regex_match", |text: &str, pattern: &str| -> Result<bool, Box<EvalAltResult>> {
+            Ok(Self::compile(pattern, "")?.is_match(text))
+        });
+        engine.register_fn("regex_find", |text: &str, pattern: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            let regex = Self::compile(pattern, "")?;
+            Ok(regex.captures(text).map(|c| Dynamic::from(Self::captures_to_map(&regex, text, &c))).unwrap_or(Dynamic::UNIT))
+        });
+        engine.register_fn("regex_find_all", |text: &str, pattern: &str| -> Result<Array, Box<EvalAltResult>> {
+            Ok(Self::find_all(&Self::compile(pattern, "")?, text))
+        });
+        engine.register_fn("regex_replace", |text: &str, pattern: &str, replacement: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::compile(pattern, "")?.replace_all(text, replacement).to_string())
+        });
+        engine.register_fn("regex_replace_first", |text: &str, pattern: &str, replacement: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::compile(pattern, "")?.replace(text, replacement).to_string())
+        });
+        engine.register_fn("regex_split", |text: &str, pattern: &str| -> Result<Array, Box<EvalAltResult>> {
+            Ok(Self::compile(pattern, "")?.split(text).map(|s| Dynamic::from(s.to_string())).collect())
+        });
+        engine.register_fn("regex_escape", |text: &str| regex::escape(text));
+
+        // Compiled Regex type
+        engine.register_type_with_name::<ScriptRegex>("Regex");
+        engine.register_fn("regex", |pattern: &str| -> Result<ScriptRegex, Box<EvalAltResult>> {
+            Ok(ScriptRegex(Self::compile(pattern, "")?))
+        });
+        engine.register_fn("regex", |pattern: &str, flags: &str| -> Result<ScriptRegex, Box<EvalAltResult>> {
+            Ok(ScriptRegex(Self::compile(pattern, flags)?))
+        });
+        engine.register_get("pattern", |re: &mut ScriptRegex| re.0.as_str().to_string());
+        engine.register_fn("is_match", |re: &mut ScriptRegex, text: &str| re.0.is_match(text));
+        engine.register_fn("find", |re: &mut ScriptRegex, text: &str| -> Dynamic {
+            re.0.captures(text).map(|c| Dynamic::from(Self::captures_to_map(&re.0, text, &c))).unwrap_or(Dynamic::UNIT)
+        });
+        engine.register_fn("find_all", |re: &mut ScriptRegex, text: &str| Self::find_all(&re.0, text));
+        engine.register_fn("replace", |re: &mut ScriptRegex, text: &str, replacement: &str| re.0.replace_all(text, replacement).to_string());
+        engine.register_fn("replace_first", |re: &mut ScriptRegex, text: &str, replacement: &str| re.0.replace(text, replacement).to_string());
+        engine.register_fn("split", |re: &mut ScriptRegex, text: &str| -> Array {
+            re.0.split(text).map(|s| Dynamic::from(s.to_string())).collect()
+        });
+        engine.register_fn("to_string", |re: &mut ScriptRegex| re.0.as_str().to_string());
+        engine.register_fn("to_debug", |re: &mut ScriptRegex| format!("Regex({})", re.0.as_str()));
+
+        // Multi-value formatting: format("{} is {:.2}", [a, b]) and format("{name}", #{ name: .. })
+        engine.register_fn("format", |template: &str, values: Array| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::format_template(template, &values, &Map::new())?)
+        });
+        engine.register_fn("format", |template: &str, values: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::format_template(template, &[], &values)?)
+        });
+
+        // Padding and alignment
+        engine.register_fn("pad_left", |text: &str, width: i64| Self::align(text, width, ' ', '>'));
+        engine.register_fn("pad_left", |text: &str, width: i64, fill: char| Self::align(text, width, fill, '>'));
+        engine.register_fn("pad_right", |text: &str, width: i64| Self::align(text, width, ' ', '<'));
+        engine.register_fn("pad_right", |text: &str, width: i64, fill: char| Self::align(text, width, fill, '<'));
+        engine.register_fn("pad_center", |text: &str, width: i64| Self::align(text, width, ' ', '^'));
+        engine.register_fn("pad_center", |text: &str, width: i64, fill: char| Self::align(text, width, fill, '^'));
+        engine.register_fn("truncate_text", |text: &str, max_chars: i64| Self::truncate(text, max_chars, ""));
+        engine.register_fn("truncate_text", |text: &str, max_chars: i64, suffix: &str| Self::truncate(text, max_chars, suffix));
+
+        println!("🟣 StringKit: All regex and string formatting functions registered");
+    }
+
+    /// Compile a pattern with optional flags: i (case-insensitive), m (multi-line), s (dot matches newline), x (verbose)
+    fn compile(pattern: &str, flags: &str) -> Result<Regex, String> {
+        let mut builder = RegexBuilder::new(pattern);
+        for flag in flags.chars() {
+            match flag {
+                'i' => { builder.case_insensitive(true); }
+                'm' => { builder.multi_line(true); }
+                's' => { builder.dot_matches_new_line(true); }
+                'x' => { builder.ignore_whitespace(true); }
+                other => return Err(format!("Unknown regex flag '{}'", other)),
+            }
+        }
+        builder.build().map_err(|e| format!("Invalid regex '{}': {}", pattern, e))
+    }
+
+    fn find_all(regex: &Regex, text: &str) -> Array {
+        regex.captures_iter(text)
+            .map(|c| Dynamic::from(Self::captures_to_map(regex, text, &c)))
+            .collect()
+    }
+
+    /// `#{ text, start, end, groups: [...], named: #{...} }` with character (not byte) offsets
+    fn captures_to_map(regex: &Regex, text: &str, captures: &Captures) -> Map {
+        let whole = captures.get(0).unwrap();
+        let char_offset = |byte: usize| text[..byte].chars().count() as i64;
+
+        let groups: Array = captures.iter()
+            .skip(1)
+            .map(|g| g.map(|m| Dynamic::from(m.as_str().to_string())).unwrap_or(Dynamic::UNIT))
+            .collect();
+
+        let mut named = Map::new();
+        for name in regex.capture_names().flatten() {
+            let value = captures.name(name)
+                .map(|m| Dynamic::from(m.as_str().to_string()))
+                .unwrap_or(Dynamic::UNIT);
+            named.insert(name.into(), value);
+        }
+
+        let mut map = Map::new();
+        map.insert("text".into(), Dynamic::from(whole.as_str().to_string()));
+        map.insert("start".into(), Dynamic::from(char_offset(whole.start())));
+        map.insert("end".into(), Dynamic::from(char_offset(whole.end())));
+        map.insert("groups".into(), Dynamic::from(groups));
+        map.insert("named".into(), Dynamic::from(named));
+        map
+    }
+
+    /// Format `{}`, `{0}`, `{name}` placeholders with an optional `:spec`.
+    /// Spec: `[[fill]align][+][0][width][.precision][type]` where align is `<`, `>` or `^`
+    /// and type is `x`, `X`, `o`, `b` or `e`. Use `{{` and `}}` for literal braces.
+    pub fn format_template(template: &str, positional: &[Dynamic], named: &Map) -> Result<String, String> {
+        let mut output = String::with_capacity(template.len());
+        let mut chars = template.chars().peekable();
+        let mut next_index = 0;
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    output.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    output.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => placeholder.push(c),
+                            None => return Err(format!("Unclosed placeholder in '{}'", template)),
+                        }
+                    }
+
+                    let (key, spec) = match placeholder.split_once(':') {
+                        Some((key, spec)) => (key.trim(), spec),
+                        None => (placeholder.trim(), ""),
+                    };
+
+                    let value = if key.is_empty() {
+                        let value = positional.get(next_index)
+                            .ok_or_else(|| format!("Not enough values for '{}'", template))?;
+                        next_index += 1;
+                        value
+                    } else if let Ok(index) = key.parse::<usize>() {
+                        positional.get(index)
+                            .ok_or_else(|| format!("No value at index {} for '{}'", index, template))?
+                    } else {
+                        named.get(key)
+                            .ok_or_else(|| format!("No value named '{}' for '{}'", key, template))?
+                    };
+
+                    output.push_str(&Self::format_value(value, spec)?);
+                }
+                '}' => return Err(format!("Unmatched '}}' in '{}'", template)),
+                c => output.push(c),
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn format_value(value: &Dynamic, spec: &str) -> Result<String, String> {
+        let mut chars: Vec<char> = spec.chars().collect();
+        let mut fill = ' ';
+        let mut align = None;
+
+        // Fill and alignment
+        if chars.len() >= 2 && matches!(chars[1], '<' | '>' | '^') {
+            fill = chars[0];
+            align = Some(chars[1]);
+            chars.drain(..2);
+        } else if !chars.is_empty() && matches!(chars[0], '<' | '>' | '^') {
+            align = Some(chars[0]);
+            chars.remove(0);
+        }
+
+        let mut rest: String = chars.into_iter().collect();
+        let plus = rest.starts_with('+');
+        if plus {
+            rest.remove(0);
+        }
+        let zero_pad = rest.starts_with('0') && align.is_none();
+        if zero_pad {
+            rest.remove(0);
+        }
+
+        let kind = match rest.chars().last() {
+            Some(c) if matches!(c, 'x' | 'X' | 'o' | 'b' | 'e') => {
+                rest.pop();
+                Some(c)
+            }
+            _ => None,
+        };
+
+        let (width, precision) = match rest.split_once('.') {
+            Some((w, p)) => (w, Some(p.parse::<usize>().map_err(|_| format!("Invalid precision in '{}'", spec))?)),
+            None => (rest.as_str(), None),
+        };
+        let width = if width.is_empty() { 0 } else { width.parse::<usize>().map_err(|_| format!("Invalid width in '{}'", spec))? };
+
+        let mut text = if let Ok(i) = value.as_int() {
+            match (kind, precision) {
+                (Some('x'), _) => format!("{:x}", i),
+                (Some('X'), _) => format!("{:X}", i),
+                (Some('o'), _) => format!("{:o}", i),
+                (Some('b'), _) => format!("{:b}", i),
+                (Some('e'), _) => format!("{:e}", i as f64),
+                (_, Some(p)) => format!("{:.*}", p, i as f64),
+                _ => i.to_string(),
+            }
+        } else if let Ok(f) = value.as_float() {
+            match (kind, precision) {
+                (Some('e'), Some(p)) => format!("{:.*e}", p, f),
+                (Some('e'), None) => format!("{:e}", f),
+                (_, Some(p)) => format!("{:.*}", p, f),
+                _ => f.to_string(),
+            }
+        } else {
+            let text = value.to_string();
+            match precision {
+                Some(p) => text.chars().take(p).collect(),
+                None => text,
+            }
+        };
+
+        let is_number = value.is_int() || value.is_float();
+        if plus && is_number && !text.starts_with('-') {
+            text.insert(0, '+');
+        }
+
+        if zero_pad && is_number {
+            let len = text.chars().count();
+            if len < width {
+                let sign_len = if text.starts_with('-') || text.starts_with('+') { 1 } else { 0 };
+                text.insert_str(sign_len, &"0".repeat(width - len));
+            }
+            return Ok(text);
+        }
+
+        let default_align = if is_number { '>' } else { '<' };
+        Ok(Self::align(&text, width as i64, fill, align.unwrap_or(default_align)))
+    }
+
+    /// Pad `text` to `width` characters; align is `<` (left), `>` (right) or `^` (center)
+    pub fn align(text: &str, width: i64, fill: char, align: char) -> String {
+        let len = text.chars().count() as i64;
+        if width <= len {
+            return text.to_string();
+        }
+        let padding = (width - len) as usize;
+        let (left, right) = match align {
+            '>' => (padding, 0),
+            '^' => (padding / 2, padding - padding / 2),
+            _ => (0, padding),
+        };
+        let fill = fill.to_string();
+        format!("{}{}{}", fill.repeat(left), text, fill.repeat(right))
+    }
+
+    fn truncate(text: &str, max_chars: i64, suffix: &str) -> String {
+        let max_chars = max_chars.max(0) as usize;
+        if text.chars().count() <= max_chars {
+            return text.to_string();
+        }
+        let keep = max_chars.saturating_sub(suffix.chars().count());
+        let mut result: String = text.chars().take(keep).collect();
+        result.push_str(suffix);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        StringKit::register_functions(&mut engine);
+        engine
+    }
+
+    #[test]
+    fn test_format_positional_and_named() {
+        let engine = engine();
+        assert_eq!(engine.eval::<String>(r#"format("{} is {:.2}", ["pi", 3.14159])"#).unwrap(), "pi is 3.14");
+        assert_eq!(engine.eval::<String>(r#"format("{1}-{0} {{ok}}", ["a", "b"])"#).unwrap(), "b-a {ok}");
+        assert_eq!(engine.eval::<String>(r#"format("{name:*^6}|{n:05}|{n:+}|{h:x}", #{ name: "ab", n: 42, h: 255 })"#).unwrap(), "**ab**|00042|+42|ff");
+        assert_eq!(engine.eval::<String>(r#"format("[{:<4}] [{:>4}] [{:06.1}]", ["ab", "cd", -2.25])"#).unwrap(), "[ab  ] [  cd] [-002.2]");
+        assert!(engine.eval::<String>(r#"format("{} {}", [1])"#).is_err());
+        assert!(engine.eval::<String>(r#"format("{:#x}", [255])"#).is_err());
+    }
+
+    #[test]
+    fn test_regex_functions() {
+        let engine = engine();
+        assert!(engine.eval::<bool>(r#"regex_match("order-123", "\\d+")"#).unwrap());
+        assert_eq!(
+            engine.eval::<String>(r#"regex_replace("2024-01-31", "(\\d+)-(\\d+)-(\\d+)", "$3/$2/$1")"#).unwrap(),
+            "31/01/2024"
+        );
+        assert_eq!(engine.eval::<Array>(r#"regex_split("a, b,c", ",\\s*")"#).unwrap().len(), 3);
+
+        let matches = engine.eval::<Array>(r#"regex_find_all("é=1 b=2", "(?P<key>\\w)=(\\d)")"#).unwrap();
+        assert_eq!(matches.len(), 2);
+        let second = matches[1].clone().cast::<Map>();
+        assert_eq!(second["start"].as_int().unwrap(), 4);
+        assert_eq!(second["named"].clone().cast::<Map>()["key"].clone().into_string().unwrap(), "b");
+        assert_eq!(second["groups"].clone().cast::<Array>()[1].clone().into_string().unwrap(), "2");
+    }
+
+    #[test]
+    fn test_compiled_regex_and_padding() {
+        let engine = engine();
+        let result = engine.eval::<String>(r#"
+            let re = regex("^hello", "i");
+            let found = re.find("HELLO world");
+            `${re.is_match("Hello")} ${found.text} ${pad_left("7", 3, '0')} [${pad_center("ab", 6)}] ${truncate_text("abcdefgh", 5, "...")}`
+        "#).unwrap();
+        assert_eq!(result, "true HELLO 007 [  ab  ] ab...");
+        assert!(engine.eval::<ScriptRegex>(r#"regex("(")"#).is_err());
+    }
+}
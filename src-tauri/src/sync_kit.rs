@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rhai::{Engine, Dynamic, EvalAltResult, Array, Map, FnPtr, NativeCallContext};
+use crate::dry_run;
+use crate::hash_kit::HashKit;
+
+/// One-way directory synchronisation (mirror / update) for Rhai scripts
+pub struct SyncKit;
+
+#[derive(Clone, Copy, PartialEq)]
+enum SyncMode {
+    /// Make the destination an exact copy of the source
+    Mirror,
+    /// Copy new files and files that are newer in the source; never delete unless asked to
+    Update,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CompareBy {
+    Size,
+    Mtime,
+    Hash,
+}
+
+struct SyncOptions {
+    mode: SyncMode,
+    compare: CompareBy,
+    delete_extraneous: bool,
+    dry_run: bool,
+    include_hidden: bool,
+    /// Matched against paths relative to the sync roots; excluded entries are neither copied nor deleted
+    exclude: Option<GlobSet>,
+    /// Called with #{ action, path, current, total } before each change is applied
+    on_progress: Option<FnPtr>,
+}
+
+impl SyncOptions {
+    fn from_map(options: Map) -> Result<Self, String> {
+        let mut parsed = Self {
+            mode: SyncMode::Update,
+            compare: CompareBy::Mtime,
+            delete_extraneous: false,
+            dry_run: false,
+            include_hidden: true,
+            exclude: None,
+            on_progress: None,
+        };
+        let mut delete_extraneous = None;
+
+        for (key, value) in options {
+            let flag = |value: &Dynamic| value.as_bool().map_err(|_| format!("Sync option '{}' must be true or false", key));
+            match key.as_str() {
+                "mode" => parsed.mode = match value.into_string().unwrap_or_default().as_str() {
+                    "mirror" => SyncMode::Mirror,
+                    "update" => SyncMode::Update,
+                    other => return Err(format!("Sync option 'mode' must be \"mirror\" or \"update\", got '{}'", other)),
+                },
+                "compare" => parsed.compare = match value.into_string().unwrap_or_default().as_str() {
+                    "size" => CompareBy::Size,
+                    "mtime" => CompareBy::Mtime,
+                    "hash" => CompareBy::Hash,
+                    other => return Err(format!("Sync option 'compare' must be \"size\", \"mtime\" or \"hash\", got '{}'", other)),
+                },
+                "delete_extraneous" => delete_extraneous = Some(flag(&value)?),
+                "dry_run" => parsed.dry_run = flag(&value)?,
+                "include_hidden" => parsed.include_hidden = flag(&value)?,
+                "exclude" => {
+                    let patterns: Vec<Dynamic> = if value.is_string() { vec![value] } else {
+                        value.try_cast::<Array>().ok_or("Sync option 'exclude' must be a glob string or an array of globs")?
+                    };
+                    let mut builder = GlobSetBuilder::new();
+                    for pattern in patterns {
+                        let pattern = pattern.into_string().map_err(|_| "Sync option 'exclude' must contain strings".to_string())?;
+                        builder.add(Glob::new(&pattern).map_err(|e| format!("Invalid glob '{}': {}", pattern, e))?);
+                    }
+                    parsed.exclude = Some(builder.build().map_err(|e| e.to_string())?);
+                }
+                "on_progress" => parsed.on_progress = Some(value.try_cast::<FnPtr>().ok_or("Sync option 'on_progress' must be a function")?),
+                other => return Err(format!("Unknown sync option '{}'", other)),
+            }
+        }
+
+        // Mirroring deletes extraneous files unless told otherwise; updating never does by default
+        parsed.delete_extraneous = delete_extraneous.unwrap_or(parsed.mode == SyncMode::Mirror);
+        Ok(parsed)
+    }
+}
+
+/// Called with (action, relative path, current, total) before each change is applied
+type SyncProgress<'a> = &'a mut dyn FnMut(&str, &str, usize, usize) -> Result<(), String>;
+
+#[derive(Clone, Copy)]
+enum EntryKind {
+    Dir,
+    File { size: u64, modified: i64 },
+}
+
+/// Changes needed to bring the destination in line with the source
+#[derive(Default)]
+struct SyncPlan {
+    create_dirs: Vec<String>,
+    copy: Vec<String>,
+    update: Vec<String>,
+    delete: Vec<String>,
+    unchanged: usize,
+    bytes: u64,
+}
+
+impl SyncKit {
+    /// Register the sync functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 SyncKit: Registering directory sync functions");
+
+        engine.register_fn("sync_dirs", |context: NativeCallContext, src: &str, dst: &str| -> Result<Map, Box<EvalAltResult>> {
+            Self::sync_from_script(&context, src, dst, Map::new())
+        });
+        engine.register_fn("sync_dirs", |context: NativeCallContext, src: &str, dst: &str, options: Map| -> Result<Map, Box<EvalAltResult>> {
+            Self::sync_from_script(&context, src, dst, options)
+        });
+
+        println!("🟣 SyncKit: All directory sync functions registered");
+    }
+
+    fn sync_from_script(context: &NativeCallContext, src: &str, dst: &str, options: Map) -> Result<Map, Box<EvalAltResult>> {
+        let mut options = SyncOptions::from_map(options)?;
+        // In a dry run of the whole script the sync only returns its plan
+        if dry_run::intercept("sync_dirs", || format!("{} → {}", src, dst)) {
+            options.dry_run = true;
+        }
+        let callback = options.on_progress.clone();
+        let mut progress = |action: &str, path: &str, current: usize, total: usize| -> Result<(), String> {
+            let Some(callback) = &callback else { return Ok(()) };
+            let mut event = Map::new();
+            event.insert("action".into(), Dynamic::from(action.to_string()));
+            event.insert("path".into(), Dynamic::from(path.to_string()));
+            event.insert("current".into(), Dynamic::from(current as i64));
+            event.insert("total".into(), Dynamic::from(total as i64));
+            callback.call_within_context::<Dynamic>(context, (event,)).map(|_| ()).map_err(|e| e.to_string())
+        };
+        Ok(Self::sync(Path::new(src), Path::new(dst), &options, &mut progress)?)
+    }
+
+    /// Build the plan and, unless this is a dry run, apply it. Per-file failures are reported, not thrown.
+    fn sync(src: &Path, dst: &Path, options: &SyncOptions, progress: SyncProgress) -> Result<Map, String> {
+        if !src.is_dir() {
+            return Err(format!("Sync source '{}' is not a directory", src.display()));
+        }
+        if dst.exists() && !dst.is_dir() {
+            return Err(format!("Sync destination '{}' is not a directory", dst.display()));
+        }
+
+        let source = Self::scan(src, options);
+        let target = if dst.is_dir() { Self::scan(dst, options) } else { BTreeMap::new() };
+        let plan = Self::plan(src, dst, &source, &target, options);
+        let mut errors = Array::new();
+
+        if !options.dry_run {
+            let mut record_error = |path: &str, error: String| {
+                let mut item = Map::new();
+                item.insert("path".into(), Dynamic::from(path.to_string()));
+                item.insert("error".into(), Dynamic::from(error));
+                errors.push(Dynamic::from(item));
+            };
+
+            let total = plan.copy.len() + plan.update.len() + plan.delete.len();
+            let mut current = 0;
+
+            // Deletions run first so a file replaced by a directory (or vice versa) is out of the way
+            for rel in &plan.delete {
+                current += 1;
+                progress("delete", rel, current, total)?;
+                if let Err(e) = Self::remove(&dst.join(rel)) {
+                    record_error(rel, e);
+                }
+            }
+
+            if let Err(e) = fs::create_dir_all(dst) {
+                return Err(format!("Failed to create '{}': {}", dst.display(), e));
+            }
+            for rel in &plan.create_dirs {
+                if let Err(e) = fs::create_dir_all(dst.join(rel)) {
+                    record_error(rel, e.to_string());
+                }
+            }
+
+            let changes = plan.copy.iter().map(|rel| ("copy", rel)).chain(plan.update.iter().map(|rel| ("update", rel)));
+            for (action, rel) in changes {
+                current += 1;
+                progress(action, rel, current, total)?;
+                if let Err(e) = Self::copy_file(&src.join(rel), &dst.join(rel)) {
+                    record_error(rel, e);
+                }
+            }
+        }
+
+        let list = |items: Vec<String>| Dynamic::from(items.into_iter().map(Dynamic::from).collect::<Array>());
+        let mut report = Map::new();
+        report.insert("dry_run".into(), Dynamic::from(options.dry_run));
+        report.insert("copied".into(), list(plan.copy));
+        report.insert("updated".into(), list(plan.update));
+        report.insert("deleted".into(), list(plan.delete));
+        report.insert("created_dirs".into(), list(plan.create_dirs));
+        report.insert("unchanged".into(), Dynamic::from(plan.unchanged as i64));
+        report.insert("bytes".into(), Dynamic::from(plan.bytes as i64));
+        report.insert("errors".into(), Dynamic::from(errors));
+        Ok(report)
+    }
+
+    /// Relative path (with `/` separators) -> entry for everything under `root`
+    fn scan(root: &Path, options: &SyncOptions) -> BTreeMap<String, EntryKind> {
+        let relative = |path: &Path| path.strip_prefix(root).unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let walker = walkdir::WalkDir::new(root).min_depth(1).follow_links(false).into_iter()
+            .filter_entry(|entry| {
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                (options.include_hidden || !hidden)
+                    && !options.exclude.as_ref().is_some_and(|set| set.is_match(relative(entry.path())))
+            });
+
+        let mut entries = BTreeMap::new();
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    println!("❌ Failed to read directory entry: {}", e);
+                    continue;
+                }
+            };
+            let rel = relative(entry.path());
+            if entry.file_type().is_dir() {
+                entries.insert(rel, EntryKind::Dir);
+            } else if entry.file_type().is_file() {
+                let metadata = entry.metadata().ok();
+                let modified = metadata.as_ref()
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                entries.insert(rel, EntryKind::File { size: metadata.map(|m| m.len()).unwrap_or(0), modified });
+            }
+            // Symbolic links are left alone on both sides
+        }
+        entries
+    }
+
+    fn plan(src: &Path, dst: &Path, source: &BTreeMap<String, EntryKind>, target: &BTreeMap<String, EntryKind>, options: &SyncOptions) -> SyncPlan {
+        let mut plan = SyncPlan::default();
+
+        for (rel, entry) in source {
+            match (entry, target.get(rel)) {
+                (EntryKind::Dir, None) => plan.create_dirs.push(rel.clone()),
+                (EntryKind::Dir, Some(EntryKind::Dir)) => {}
+                (EntryKind::File { size, .. }, None) => {
+                    plan.copy.push(rel.clone());
+                    plan.bytes += size;
+                }
+                (EntryKind::File { size, modified }, Some(EntryKind::File { size: dst_size, modified: dst_modified })) => {
+                    // Update mode never replaces a file that is newer in the destination
+                    let newer_in_destination = options.mode == SyncMode::Update && modified < dst_modified;
+                    let changed = !newer_in_destination && match options.compare {
+                        CompareBy::Size => size != dst_size,
+                        CompareBy::Mtime if options.mode == SyncMode::Update => size != dst_size && modified >= dst_modified || modified > dst_modified,
+                        CompareBy::Mtime => size != dst_size || modified != dst_modified,
+                        CompareBy::Hash => size != dst_size || Self::content_differs(&src.join(rel), &dst.join(rel)),
+                    };
+                    if changed {
+                        plan.update.push(rel.clone());
+                        plan.bytes += size;
+                    } else {
+                        plan.unchanged += 1;
+                    }
+                }
+                // A file replaced by a directory (or the other way round)
+                (EntryKind::Dir, Some(EntryKind::File { .. })) => {
+                    plan.delete.push(rel.clone());
+                    plan.create_dirs.push(rel.clone());
+                }
+                (EntryKind::File { size, .. }, Some(EntryKind::Dir)) => {
+                    plan.update.push(rel.clone());
+                    plan.bytes += size;
+                }
+            }
+        }
+
+        if options.delete_extraneous {
+            // Only the top-most extraneous directory is listed; its contents go with it
+            let mut deleted_dirs: HashSet<&str> = HashSet::new();
+            for (rel, entry) in target {
+                if source.contains_key(rel) {
+                    continue;
+                }
+                let inside_deleted = rel.match_indices('/').any(|(index, _)| deleted_dirs.contains(&rel[..index]));
+                if inside_deleted {
+                    continue;
+                }
+                if matches!(entry, EntryKind::Dir) {
+                    deleted_dirs.insert(rel);
+                }
+                plan.delete.push(rel.clone());
+            }
+        }
+
+        plan
+    }
+
+    fn content_differs(a: &Path, b: &Path) -> bool {
+        match (HashKit::hash_file(a, "blake3"), HashKit::hash_file(b, "blake3")) {
+            (Ok(a), Ok(b)) => a != b,
+            _ => true,
+        }
+    }
+
+    /// Copy a file (replacing a directory in the way) and keep its modification time
+    fn copy_file(from: &Path, to: &Path) -> Result<(), String> {
+        if to.is_dir() {
+            fs::remove_dir_all(to).map_err(|e| e.to_string())?;
+        }
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::copy(from, to).map_err(|e| e.to_string())?;
+        let modified = fs::metadata(from).map(|m| filetime::FileTime::from_last_modification_time(&m)).map_err(|e| e.to_string())?;
+        filetime::set_file_mtime(to, modified).map_err(|e| e.to_string())
+    }
+
+    fn remove(path: &Path) -> Result<(), String> {
+        let result = if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+        result.map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn write(path: PathBuf, content: &str, modified: i64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        filetime::set_file_mtime(&path, filetime::FileTime::from_unix_time(modified, 0)).unwrap();
+    }
+
+    #[test]
+    fn test_mirror_plan_then_apply() {
+        let dir = std::env::temp_dir().join(format!("snaprun_sync_{}", uuid::Uuid::new_v4()));
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        write(src.join("same.txt"), "same", 1_700_000_000);
+        write(src.join("changed.txt"), "new content", 1_700_000_100);
+        write(src.join("docs/new.md"), "# new", 1_700_000_000);
+        write(src.join("cache/skip.tmp"), "tmp", 1_700_000_000);
+        write(dst.join("same.txt"), "same", 1_700_000_000);
+        write(dst.join("changed.txt"), "old", 1_700_000_000);
+        write(dst.join("old/stale.txt"), "stale", 1_700_000_000);
+
+        let options = |dry_run: bool| {
+            let mut map = Map::new();
+            map.insert("mode".into(), Dynamic::from("mirror".to_string()));
+            map.insert("dry_run".into(), Dynamic::from(dry_run));
+            map.insert("exclude".into(), Dynamic::from("cache".to_string()));
+            SyncOptions::from_map(map).unwrap()
+        };
+        let names = |report: &Map, key: &str| report[key].clone().cast::<Array>().into_iter().map(|v| v.cast::<String>()).collect::<Vec<_>>();
+
+        let plan = SyncKit::sync(&src, &dst, &options(true), &mut |_, _, _, _| Ok(())).unwrap();
+        assert_eq!(names(&plan, "copied"), vec!["docs/new.md"]);
+        assert_eq!(names(&plan, "updated"), vec!["changed.txt"]);
+        assert_eq!(names(&plan, "deleted"), vec!["old"]);
+        assert_eq!(plan["unchanged"].as_int().unwrap(), 1);
+        assert!(dst.join("old/stale.txt").exists());
+
+        let mut actions = Vec::new();
+        let report = SyncKit::sync(&src, &dst, &options(false), &mut |action, path, _, _| {
+            actions.push(format!("{} {}", action, path));
+            Ok(())
+        }).unwrap();
+        assert!(report["errors"].clone().cast::<Array>().is_empty());
+        assert_eq!(actions, vec!["delete old", "copy docs/new.md", "update changed.txt"]);
+        assert_eq!(fs::read_to_string(dst.join("changed.txt")).unwrap(), "new content");
+        assert!(!dst.join("old").exists() && !dst.join("cache").exists());
+
+        let again = SyncKit::sync(&src, &dst, &options(true), &mut |_, _, _, _| Ok(())).unwrap();
+        assert_eq!(again["unchanged"].as_int().unwrap(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_update_mode_keeps_newer_and_extra_files() {
+        let dir = std::env::temp_dir().join(format!("snaprun_sync_update_{}", uuid::Uuid::new_v4()));
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        write(src.join("a.txt"), "source", 1_700_000_000);
+        write(dst.join("a.txt"), "edited in destination", 1_700_000_500);
+        write(dst.join("extra.txt"), "keep me", 1_700_000_000);
+        write(src.join("b.txt"), "new", 1_700_000_500);
+        write(dst.join("b.txt"), "old", 1_700_000_000);
+
+        let mut engine = Engine::new();
+        SyncKit::register_functions(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push("src", src.to_string_lossy().to_string());
+        scope.push("dst", dst.to_string_lossy().to_string());
+        let result = engine.eval_with_scope::<String>(&mut scope, r#"
+            let h = sync_dirs(src, dst, #{ mode: "update", compare: "hash", dry_run: true });
+            let r = sync_dirs(src, dst, #{ mode: "update" });
+            r.updated.len() + "|" + r.deleted.len() + "|" + h.updated
+        "#).unwrap();
+        assert_eq!(result, "1|0|[\"b.txt\"]");
+        assert_eq!(fs::read_to_string(dst.join("a.txt")).unwrap(), "edited in destination");
+        assert!(dst.join("extra.txt").exists());
+        assert!(engine.eval_with_scope::<Map>(&mut scope, r#"sync_dirs(src, dst, #{ mode: "copy" })"#).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
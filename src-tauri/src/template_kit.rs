@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use minijinja::{AutoEscape, Environment, Error, ErrorKind, UndefinedBehavior, Value};
+use rhai::{Engine, Dynamic, EvalAltResult, Map};
+use crate::data_format::rhai_to_json_value;
+use crate::script_manager::ScriptManager;
+
+/// Text templating (Jinja-style syntax) for generating HTML and files from Rhai scripts
+pub struct TemplateKit;
+
+/// Options accepted as the last argument of the render functions
+struct TemplateOptions {
+    /// Escape HTML in interpolated values (on by default; turn off for config or code files)
+    autoescape: bool,
+    /// Fail on undefined variables instead of rendering them as empty
+    strict: bool,
+}
+
+impl TemplateOptions {
+    fn from_map(options: Map) -> Result<Self, String> {
+        let mut parsed = Self { autoescape: true, strict: false };
+        for (key, value) in options {
+            let flag = value.as_bool().map_err(|_| format!("Template option '{}' must be true or false", key))?;
+            match key.as_str() {
+                "autoescape" => parsed.autoescape = flag,
+                "strict" => parsed.strict = flag,
+                other => return Err(format!("Unknown template option '{}'", other)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl TemplateKit {
+    /// Register all template functions with the Rhai engine
+    pub fn register_functions(engine: &mut Engine) {
+        println!("🟣 TemplateKit: Registering template functions");
+
+        engine.register_fn("render_template", |template: &str, data: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::render_string(template, data, Map::new())?)
+        });
+        engine.register_fn("render_template", |template: &str, data: Map, options: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::render_string(template, data, options)?)
+        });
+        engine.register_fn("render_template_file", |path: &str, data: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::render_file(Path::new(path), data, Map::new())?)
+        });
+        engine.register_fn("render_template_file", |path: &str, data: Map, options: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::render_file(Path::new(path), data, options)?)
+        });
+        engine.register_fn("render_template_to_file", |path: &str, data: Map, dest: &str| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::render_to_file(Path::new(path), data, dest, Map::new())?)
+        });
+        engine.register_fn("render_template_to_file", |path: &str, data: Map, dest: &str, options: Map| -> Result<String, Box<EvalAltResult>> {
+            Ok(Self::render_to_file(Path::new(path), data, dest, options)?)
+        });
+
+        println!("🟣 TemplateKit: All template functions registered");
+    }
+
+    /// Render a template string; partials are looked up in the user scripts folder
+    pub fn render_string(template: &str, data: Map, options: Map) -> Result<String, String> {
+        let search_dirs = vec![ScriptManager::get_user_scripts_path()];
+        Self::render(template, data, TemplateOptions::from_map(options)?, search_dirs)
+    }
+
+    /// Render a template file; partials are looked up next to it, then in the user scripts folder
+    pub fn render_file(path: &Path, data: Map, options: Map) -> Result<String, String> {
+        let template = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read template '{}': {}", path.display(), e))?;
+
+        let mut search_dirs = Vec::new();
+        if let Some(parent) = path.parent() {
+            search_dirs.push(parent.to_path_buf());
+        }
+        search_dirs.push(ScriptManager::get_user_scripts_path());
+
+        Self::render(&template, data, TemplateOptions::from_map(options)?, search_dirs)
+    }
+
+    /// Render a template file and write the result to `dest`, returning the destination path
+    pub fn render_to_file(path: &Path, data: Map, dest: &str, options: Map) -> Result<String, String> {
+        let output = Self::render_file(path, data, options)?;
+        if let Some(parent) = Path::new(dest).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory for '{}': {}", dest, e))?;
+            }
+        }
+        fs::write(dest, output).map_err(|e| format!("Failed to write '{}': {}", dest, e))?;
+        Ok(dest.to_string())
+    }
+
+    fn render(template: &str, data: Map, options: TemplateOptions, search_dirs: Vec<PathBuf>) -> Result<String, String> {
+        let env = Self::environment(options, search_dirs);
+        let context = Value::from_serialize(rhai_to_json_value(Dynamic::from(data))?);
+        env.render_str(template, context)
+            .map_err(|e| format!("Template error: {:#}", e))
+    }
+
+    fn environment(options: TemplateOptions, search_dirs: Vec<PathBuf>) -> Environment<'static> {
+        let mut env = Environment::new();
+
+        let autoescape = options.autoescape;
+        env.set_auto_escape_callback(move |_| if autoescape { AutoEscape::Html } else { AutoEscape::None });
+        if options.strict {
+            env.set_undefined_behavior(UndefinedBehavior::Strict);
+        }
+
+        // Partials for {% include %}, {% import %} and {% extends %}
+        env.set_loader(move |name| Self::load_partial(&search_dirs, name));
+
+        // Render Markdown to HTML, e.g. {{ notes | markdown }}. Raw HTML inside the Markdown is
+        // shown as text, so the result is safe to mark as such even for untrusted input.
+        env.add_filter("markdown", |text: String| {
+            use pulldown_cmark::Event;
+            let parser = pulldown_cmark::Parser::new_ext(&text, pulldown_cmark::Options::all())
+                .map(|event| match event {
+                    Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+                    other => other,
+                });
+            let mut html = String::new();
+            pulldown_cmark::html::push_html(&mut html, parser);
+            Value::from_safe_string(html)
+        });
+
+        env
+    }
+
+    /// Resolve a partial name relative to the search directories, refusing to leave them
+    fn load_partial(search_dirs: &[PathBuf], name: &str) -> Result<Option<String>, Error> {
+        let relative = Path::new(name);
+        let is_safe = relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !is_safe {
+            return Err(Error::new(ErrorKind::InvalidOperation, format!("Partial path '{}' must stay inside the template folders", name)));
+        }
+
+        for dir in search_dirs {
+            let candidate = dir.join(relative);
+            if candidate.is_file() {
+                return fs::read_to_string(&candidate)
+                    .map(Some)
+                    .map_err(|e| Error::new(ErrorKind::InvalidOperation, format!("Failed to read partial '{}': {}", candidate.display(), e)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        TemplateKit::register_functions(&mut engine);
+        engine
+    }
+
+    #[test]
+    fn test_loops_conditionals_filters_and_escaping() {
+        let html = engine().eval::<String>(r#"
+            render_template(
+                "{% for t in todos %}<li>{{ t.title | upper }}{% if t.done %} ✓{% endif %}</li>{% endfor %}",
+                #{ todos: [#{ title: "<b>ship</b>", done: true }, #{ title: "test", done: false }] }
+            )
+        "#).unwrap();
+        assert_eq!(html, "<li>&lt;B&gt;SHIP&lt;&#x2f;B&gt; ✓</li><li>TEST</li>");
+    }
+
+    #[test]
+    fn test_autoescape_off_and_strict() {
+        let engine = engine();
+        let text = engine.eval::<String>(r#"render_template("key = \"{{ v }}\"", #{ v: "<x>" }, #{ autoescape: false })"#).unwrap();
+        assert_eq!(text, "key = \"<x>\"");
+        assert!(engine.eval::<String>(r#"render_template("{{ missing }}", #{}, #{ strict: true })"#).is_err());
+    }
+
+    #[test]
+    fn test_markdown_escapes_raw_html() {
+        let html = engine().eval::<String>(r#"
+            render_template("{{ notes | markdown }}", #{ notes: "**hi** <img src=x onerror=alert(1)>\n\n<script>alert(2)</script>" })
+        "#).unwrap();
+        assert_eq!(html, "<p><strong>hi</strong> &lt;img src=x onerror=alert(1)&gt;</p>\n&lt;script&gt;alert(2)&lt;/script&gt;");
+    }
+
+    #[test]
+    fn test_file_templates_with_partials() {
+        let dir = std::env::temp_dir().join(format!("snaprun_templates_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("partials")).unwrap();
+        fs::write(dir.join("partials").join("header.html"), "<h1>{{ title }}</h1>").unwrap();
+        fs::write(dir.join("page.html"), "{% include \"partials/header.html\" %}<p>{{ body }}</p>").unwrap();
+        fs::write(dir.join("evil.html"), "{% include \"../secret.txt\" %}").unwrap();
+
+        let output_path = dir.join("out").join("page.html");
+        let mut scope = rhai::Scope::new();
+        scope.push("dir", dir.to_string_lossy().to_string());
+        scope.push("dest", output_path.to_string_lossy().to_string());
+
+        let engine = engine();
+        let html = engine.eval_with_scope::<String>(&mut scope, r#"
+            render_template_to_file(dir + "/page.html", #{ title: "Report", body: "a & b" }, dest);
+            render_template_file(dir + "/page.html", #{ title: "Report", body: "a & b" })
+        "#).unwrap();
+
+        assert_eq!(html, "<h1>Report</h1><p>a &amp; b</p>");
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), html);
+        assert!(engine.eval_with_scope::<String>(&mut scope, r#"render_template_file(dir + "/evil.html", #{})"#).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
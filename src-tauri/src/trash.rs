@@ -0,0 +1,180 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::fs_kit::{candidate_name, copy_tree};
+
+/// Move a file or directory to the platform trash.
+/// Returns where the item now lives so it can be restored later.
+pub fn move_to_trash(path: &Path) -> Result<PathBuf, String> {
+    move_to(path, None)
+}
+
+/// Put a trashed item back at its original location
+pub fn restore_from_trash(trashed: &Path, original: &Path) -> Result<(), String> {
+    restore_to(trashed, original, None)
+}
+
+/// `root` replaces the platform trash with a plain folder
+fn move_to(path: &Path, root: Option<&Path>) -> Result<PathBuf, String> {
+    let path = absolute(path)?;
+    if fs::symlink_metadata(&path).is_err() {
+        return Err(format!("'{}' does not exist", path.display()));
+    }
+    match root {
+        Some(root) => folder::move_into(&path, root),
+        None => platform::move_to_trash(&path),
+    }
+}
+
+fn restore_to(trashed: &Path, original: &Path, root: Option<&Path>) -> Result<(), String> {
+    if fs::symlink_metadata(original).is_ok() {
+        return Err(format!("'{}' already exists", original.display()));
+    }
+    match root {
+        Some(_) => folder::restore(trashed, original),
+        None => platform::restore(trashed, original),
+    }
+}
+
+fn absolute(path: &Path) -> Result<PathBuf, String> {
+    std::path::absolute(path).map_err(|e| format!("Invalid path '{}': {}", path.display(), e))
+}
+
+mod folder {
+    //! A trash that is just a folder: items are moved in under a free name and moved back out
+    use super::*;
+
+    pub fn move_into(path: &Path, root: &Path) -> Result<PathBuf, String> {
+        fs::create_dir_all(root).map_err(|e| format!("Failed to create '{}': {}", root.display(), e))?;
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let target = (1..)
+            .map(|attempt| root.join(candidate_name(&name, attempt)))
+            .find(|candidate| fs::symlink_metadata(candidate).is_err())
+            .expect("an unused name");
+        move_item(path, &target).map_err(|e| format!("Failed to move '{}' to the trash: {}", path.display(), e))?;
+        Ok(target)
+    }
+
+    pub fn restore(trashed: &Path, original: &Path) -> Result<(), String> {
+        if fs::symlink_metadata(trashed).is_err() {
+            return Err(format!("'{}' is no longer in the trash", original.display()));
+        }
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        move_item(trashed, original).map_err(|e| format!("Failed to restore '{}': {}", original.display(), e))
+    }
+
+    /// Rename, falling back to copy-and-delete when the trash is on another filesystem
+    fn move_item(from: &Path, to: &Path) -> io::Result<()> {
+        match fs::rename(from, to) {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+                copy_tree(from, to)?;
+                if from.is_dir() { fs::remove_dir_all(from) } else { fs::remove_file(from) }
+            }
+            other => other,
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    //! The trash crate can list the Recycle Bin and the freedesktop.org trash folders (including
+    //! the per-volume ones), so items are found again through their trash ID
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use trash::TrashItem;
+
+    pub fn move_to_trash(path: &Path) -> Result<PathBuf, String> {
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+        trash::delete(path).map_err(|e| format!("Failed to move '{}' to the trash: {}", path.display(), e))?;
+        // The newest item deleted from this path since we started is the one just trashed
+        let id = trash::os_limited::list().unwrap_or_default().into_iter()
+            .filter(|item| item.time_deleted >= started && item.original_path() == path)
+            .max_by_key(|item| item.time_deleted)
+            .map(|item| PathBuf::from(item.id));
+        // If the trash can't be listed, the original path still finds the item on restore
+        Ok(id.unwrap_or_else(|| path.to_path_buf()))
+    }
+
+    pub fn restore(trashed: &Path, original: &Path) -> Result<(), String> {
+        let mut items = trash::os_limited::list().map_err(|e| format!("Failed to list the trash: {}", e))?;
+        let item = match items.iter().position(|item| Path::new(&item.id) == trashed) {
+            Some(index) => Some(items.swap_remove(index)),
+            None => latest(items, original),
+        }.ok_or_else(|| format!("'{}' is no longer in the trash", original.display()))?;
+        if let Some(parent) = original.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        trash::os_limited::restore_all([item]).map_err(|e| format!("Failed to restore '{}': {}", original.display(), e))
+    }
+
+    fn latest(items: Vec<TrashItem>, original: &Path) -> Option<TrashItem> {
+        items.into_iter()
+            .filter(|item| item.original_path() == original)
+            .max_by_key(|item| item.time_deleted)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    //! The trash crate can't list the macOS Trash, so the item is found in ~/.Trash by name and
+    //! the time it was moved there; restoring moves it back like any trash folder
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    pub fn move_to_trash(path: &Path) -> Result<PathBuf, String> {
+        let trash_dir = dirs::home_dir().ok_or("Cannot locate the home directory for the trash")?.join(".Trash");
+        // ctime changes on rename; allow for its resolution
+        let started = SystemTime::now() - Duration::from_secs(1);
+        trash::delete(path).map_err(|e| format!("Failed to move '{}' to the trash: {}", path.display(), e))?;
+        // Finder may rename the item ("notes 2.txt", "notes 10.23.45.txt"), so match on the stem
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        fs::read_dir(&trash_dir).into_iter().flatten().flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&stem))
+            .filter_map(|entry| {
+                let metadata = fs::symlink_metadata(entry.path()).ok()?;
+                let changed = UNIX_EPOCH + Duration::new(metadata.ctime().max(0) as u64, metadata.ctime_nsec().max(0) as u32);
+                (changed >= started).then(|| (changed, entry.path()))
+            })
+            .max_by_key(|(changed, _)| *changed)
+            .map(|(_, trashed)| trashed)
+            .ok_or_else(|| format!("'{}' was moved to the trash, but it could not be located to restore it later", path.display()))
+    }
+
+    pub fn restore(trashed: &Path, original: &Path) -> Result<(), String> {
+        folder::restore(trashed, original)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_round_trip() {
+        let dir = std::env::temp_dir().join(format!("snaprun_trash_{}", uuid::Uuid::new_v4()));
+        let root = dir.join("trash");
+        fs::create_dir_all(dir.join("work")).unwrap();
+        let file = dir.join("work").join("my notes.txt");
+
+        fs::write(&file, "first").unwrap();
+        let first = move_to(&file, Some(&root)).unwrap();
+        fs::write(&file, "second").unwrap();
+        let second = move_to(&file, Some(&root)).unwrap();
+        assert_ne!(first, second);
+        assert!(!file.exists() && first.starts_with(&root));
+
+        fs::write(&file, "in the way").unwrap();
+        assert!(restore_to(&second, &file, Some(&root)).is_err());
+        fs::remove_file(&file).unwrap();
+        restore_to(&second, &file, Some(&root)).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "second");
+        fs::remove_file(&file).unwrap();
+        restore_to(&first, &file, Some(&root)).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "first");
+        assert!(restore_to(&first, &file, Some(&root)).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, OnceLock};
 use tauri::Emitter;
 use tokio::sync::oneshot;
@@ -12,6 +12,9 @@ pub struct UIRequest {
     pub message: String,
     pub options: Option<Vec<String>>,
     pub html_content: Option<String>,
+    /// Input requests only: hide what the user types and never log the answer
+    #[serde(default)]
+    pub masked: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,12 +32,24 @@ fn get_pending_requests() -> &'static PendingRequests {
     PENDING_REQUESTS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
 }
 
+// IDs of masked input requests, whose responses must not be echoed
+static MASKED_REQUESTS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn get_masked_requests() -> &'static Mutex<HashSet<String>> {
+    MASKED_REQUESTS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
 #[tauri::command]
 pub async fn ui_response(
     id: String,
     value: String,
 ) -> Result<(), String> {
-    println!("🟣 ui_response: Called with id: {}, value: {}", id, value);
+    let masked = get_masked_requests().lock().map(|mut ids| ids.remove(&id)).unwrap_or(false);
+    if masked {
+        println!("🟣 ui_response: Called with id: {}, value: <masked>", id);
+    } else {
+        println!("🟣 ui_response: Called with id: {}, value: {}", id, value);
+    }
     let mut pending = get_pending_requests().lock().map_err(|e| e.to_string())?;
     if let Some(sender) = pending.remove(&id) {
         println!("🟣 ui_response: Found sender for ID, sending response");
@@ -62,6 +77,15 @@ impl UIController {
     }
 
     pub async fn ask_input(&self, message: &str) -> Result<String, String> {
+        self.request_input(message, false).await
+    }
+
+    /// Ask for text input without showing or logging what is typed (passwords, tokens)
+    pub async fn ask_masked_input(&self, message: &str) -> Result<String, String> {
+        self.request_input(message, true).await
+    }
+
+    async fn request_input(&self, message: &str, masked: bool) -> Result<String, String> {
         println!("🟣 UIController: ask_input called with message: {}", message);
         let id = Uuid::new_v4().to_string();
         println!("🟣 UIController: Generated request ID: {}", id);
@@ -72,6 +96,9 @@ impl UIController {
             let mut pending = get_pending_requests().lock().map_err(|e| e.to_string())?;
             pending.insert(id.clone(), sender);
         }
+        if masked {
+            get_masked_requests().lock().map_err(|e| e.to_string())?.insert(id.clone());
+        }
 
         let request = UIRequest {
             id: id.clone(),
@@ -79,6 +106,7 @@ impl UIController {
             message: message.to_string(),
             options: None,
             html_content: None,
+            masked,
         };
 
         // Emit to frontend
@@ -90,7 +118,11 @@ impl UIController {
         // Wait for response
         println!("🟣 UIController: Waiting for response");
         let result = receiver.await.map_err(|_| "Request cancelled".to_string());
-        println!("🟣 UIController: Received response: {:?}", result);
+        if masked {
+            println!("🟣 UIController: Received masked response");
+        } else {
+            println!("🟣 UIController: Received response: {:?}", result);
+        }
         result
     }
 
@@ -117,6 +149,7 @@ impl UIController {
             message: message.to_string(),
             options: Some(options),
             html_content: None,
+            masked: false,
         };
 
         // Emit to frontend
@@ -137,6 +170,7 @@ impl UIController {
             message: message.to_string(),
             options: None,
             html_content: Some(html_content.to_string()),
+            masked: false,
         };
 
         // Emit to frontend
@@ -157,6 +191,7 @@ impl UIController {
             message: message.to_string(),
             options: None,
             html_content: Some(html_content.to_string()),
+            masked: false,
         };
 
         // Emit to frontend
@@ -184,6 +219,7 @@ impl UIController {
             message: title.to_string(),
             options: None,
             html_content: Some(editor_data.to_string()),
+            masked: false,
         };
 
         // Emit to frontend
@@ -205,6 +241,7 @@ impl UIController {
             message: title.to_string(),
             options: None,
             html_content: Some(editor_data.to_string()),
+            masked: false,
         };
 
         // Emit to frontend without waiting for response
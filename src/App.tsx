@@ -1,7 +1,8 @@
 import { Component, createSignal, Show, createEffect, onMount, onCleanup } from "solid-js";
 import "./App.css";
 import { UIController } from "./UIController";
-import { ScriptSearch } from "./components";
+import { ScriptSearch, SettingsPage, applyTheme } from "./components";
+import { UIService } from "./services/UIService";
 import { useKeyboardShortcuts } from "./hooks/useKeyboardShortcuts";
 import { useUIEvents } from "./hooks/useUIEvents";
 import { appLogger } from "./services/LoggingService";
@@ -23,6 +24,7 @@ const App: Component = () => {
   
   // State to control what to show
   const [showUIController, setShowUIController] = createSignal(false);
+  const [showSettings, setShowSettings] = createSignal(false);
 
   // Listen for reset-to-script-search event from backend
   onMount(() => {
@@ -68,6 +70,10 @@ const App: Component = () => {
     
     setupResetListener();
     setupAppControlListener();
+    
+    UIService.getSettings()
+      .then(info => applyTheme(info.effective.theme))
+      .catch(error => appLogger.error(`Failed to load settings: ${error}`));
   });
 
   // Automatically show UIController when a request is received
@@ -123,8 +129,12 @@ const App: Component = () => {
 
   return (
     <main class="container">
-      <Show when={!showUIController()}>
-        <ScriptSearch onScriptSelect={handleScriptSelect} />
+      <Show when={!showUIController() && !showSettings()}>
+        <ScriptSearch onScriptSelect={handleScriptSelect} onOpenSettings={() => setShowSettings(true)} />
+      </Show>
+
+      <Show when={showSettings() && !showUIController()}>
+        <SettingsPage onClose={() => setShowSettings(false)} />
       </Show>
 
       <Show when={showUIController()}>
@@ -10,6 +10,7 @@ export type UIRequest = {
   message: string;
   options?: string[];
   html_content?: string;
+  masked?: boolean;
 };
 
 type UIControllerProps = {
@@ -167,8 +168,9 @@ export const UIController: Component<UIControllerProps> = (props) => {
 
     let response = '';
     if (request.type === 'input') {
-      response = inputValue().trim();
-      console.log('🔵 UIController: Input response:', response);
+      // Passwords are sent exactly as typed and never logged
+      response = request.masked ? inputValue() : inputValue().trim();
+      console.log('🔵 UIController: Input response:', request.masked ? '<masked>' : response);
       // Don't send empty responses for input
       if (!response) {
         console.log('🟡 UIController: Empty input response, ignoring');
@@ -187,7 +189,7 @@ export const UIController: Component<UIControllerProps> = (props) => {
 
     try {
       console.log('🔵 UIController: Sending response via UIService');
-      await UIService.sendResponse(request.id, response);
+      await UIService.sendResponse(request.id, response, request.masked);
       console.log('🔵 UIController: Response sent successfully');
       
       // Don't call onComplete here - let the script continue its flow
@@ -284,7 +286,7 @@ export const UIController: Component<UIControllerProps> = (props) => {
             <div style="position: relative; display: flex; align-items: center;">
               <input
                 ref={inputRef}
-                type="text"
+                type={props.request?.masked ? 'password' : 'text'}
                 placeholder={props.request?.message || "Enter your response..."}
                 value={inputValue()}
                 onInput={(e) => setInputValue(e.currentTarget.value)}
@@ -5,6 +5,7 @@ import { scriptSearchLogger } from '../services/LoggingService';
 
 type ScriptSearchProps = {
   onScriptSelect: (script: string) => void;
+  onOpenSettings?: () => void;
 };
 
 export const ScriptSearch: Component<ScriptSearchProps> = (props) => {
@@ -106,11 +107,20 @@ export const ScriptSearch: Component<ScriptSearchProps> = (props) => {
       event.stopPropagation();
       event.stopImmediatePropagation();
       if (filtered.length > 0) {
-        handleScriptSelect(filtered[selectedIndex()]);
+        // Shift+Enter records file and process changes instead of making them
+        handleScriptSelect(filtered[selectedIndex()], event.shiftKey);
       }
       return;
     }
     
+    if (event.key === ',' && (event.ctrlKey || event.metaKey)) {
+      event.preventDefault();
+      event.stopPropagation();
+      event.stopImmediatePropagation();
+      props.onOpenSettings?.();
+      return;
+    }
+    
     if (event.key === 'Escape') {
       event.preventDefault();
       event.stopPropagation();
@@ -137,12 +147,13 @@ export const ScriptSearch: Component<ScriptSearchProps> = (props) => {
     document.removeEventListener('keydown', handleGlobalKeyDown, { capture: true });
   });
 
-  const handleScriptSelect = async (script: ScriptInfo) => {
+  const handleScriptSelect = async (script: ScriptInfo, dryRun = false) => {
     setIsLoading(true);
     try {
       await scriptSearchLogger.scriptStart(script.name);
       
-      const result = await invoke('run_rhai_script', { scriptId: script.id });
+      // Without Shift the sandbox default from settings decides
+      const result = await invoke('run_rhai_script', { scriptId: script.id, dryRun: dryRun || undefined });
       await scriptSearchLogger.scriptSuccess(script.name, result);
       
       // Notify parent to switch to UIController
@@ -240,7 +251,7 @@ export const ScriptSearch: Component<ScriptSearchProps> = (props) => {
           </Show>
         </div>
         <div style="margin-top: 8px;margin-left:22px; font-size: 12px; color: #858585;">
-          {rhaiScripts().length} Rhai scripts available • Use ↑↓ to navigate, Enter to select
+          {rhaiScripts().length} Rhai scripts available • Use ↑↓ to navigate, Enter to select, Shift+Enter for a dry run, Ctrl+, for settings
         </div>
       </div>
 
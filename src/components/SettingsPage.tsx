@@ -0,0 +1,349 @@
+import { Component, createSignal, For, Index, onMount, onCleanup, Show, JSX } from 'solid-js';
+import { UIService, Settings, SettingsInfo, ScriptRoot, ScriptRootsReport, InstalledPackage } from '../services/UIService';
+import { appLogger } from '../services/LoggingService';
+
+type SettingsPageProps = {
+  onClose: () => void;
+};
+
+type EnvRow = { name: string; value: string };
+
+/**
+ * Apply the theme setting to the page ('system' follows the OS)
+ */
+export const applyTheme = (theme: Settings['theme']) => {
+  document.documentElement.dataset.theme = theme;
+  document.documentElement.style.colorScheme = theme === 'system' ? 'light dark' : theme;
+};
+
+const inputStyle = `
+  flex: 1;
+  height: 32px;
+  background: #403c4a;
+  color: #cccccc;
+  padding: 4px 10px;
+  font-size: 14px;
+  outline: none;
+  border: 1px solid rgba(255, 255, 255, 0.08);
+`;
+
+const buttonStyle = `
+  background: rgba(0, 122, 204, 0.3);
+  color: #e1e1e1;
+  border: 1px solid #007acc;
+  padding: 6px 14px;
+  cursor: pointer;
+`;
+
+const Section: Component<{ title: string; hint?: string; children: JSX.Element }> = (props) => (
+  <div style="margin-bottom: 20px;">
+    <div style="font-weight: 600; color: #e1e1e1; margin-bottom: 4px;">{props.title}</div>
+    <Show when={props.hint}>
+      <div style="font-size: 12px; color: #858585; margin-bottom: 8px;">{props.hint}</div>
+    </Show>
+    {props.children}
+  </div>
+);
+
+const Row: Component<{ label: string; children: JSX.Element }> = (props) => (
+  <label style="display: flex; align-items: center; gap: 12px; margin-bottom: 6px;">
+    <span style="width: 200px; font-size: 13px;">{props.label}</span>
+    {props.children}
+  </label>
+);
+
+export const SettingsPage: Component<SettingsPageProps> = (props) => {
+  const [info, setInfo] = createSignal<SettingsInfo | null>(null);
+  const [settings, setSettings] = createSignal<Settings | null>(null);
+  const [envRows, setEnvRows] = createSignal<EnvRow[]>([]);
+  const [status, setStatus] = createSignal('');
+  const [rootsReport, setRootsReport] = createSignal<ScriptRootsReport | null>(null);
+  const [packages, setPackages] = createSignal<InstalledPackage[]>([]);
+  const [packageSource, setPackageSource] = createSignal('');
+
+  const loadRootsReport = async () => {
+    try {
+      setRootsReport(await UIService.listScriptRoots());
+    } catch (error) {
+      appLogger.warn(`Failed to list script roots: ${error}`);
+    }
+  };
+
+  onMount(async () => {
+    try {
+      const loaded = await UIService.getSettings();
+      setInfo(loaded);
+      setSettings(loaded.settings);
+      setEnvRows(Object.entries(loaded.settings.env).map(([name, value]) => ({ name, value })));
+    } catch (error) {
+      setStatus(`Failed to load settings: ${error}`);
+    }
+    await loadRootsReport();
+    await loadPackages();
+  });
+
+  const loadPackages = async () => {
+    try {
+      setPackages(await UIService.listPackages());
+    } catch (error) {
+      appLogger.warn(`Failed to list packages: ${error}`);
+    }
+  };
+
+  const installPackage = async (upgrade: boolean) => {
+    const source = packageSource().trim();
+    if (!source) return;
+    try {
+      const changes = await UIService.installPackage(source, upgrade);
+      setStatus(changes.map(c => c.previous ? `${c.name} ${c.previous} → ${c.version}` : `${c.name} ${c.version} installed`).join(', '));
+      setPackageSource('');
+      await loadPackages();
+      await loadRootsReport();
+    } catch (error) {
+      setStatus(`Package not installed: ${error}`);
+    }
+  };
+
+  const uninstallPackage = async (name: string) => {
+    try {
+      const version = await UIService.uninstallPackage(name);
+      setStatus(`${name} ${version} uninstalled`);
+      await loadPackages();
+      await loadRootsReport();
+    } catch (error) {
+      setStatus(`Not uninstalled: ${error}`);
+    }
+  };
+
+  const handleKeyDown = (event: KeyboardEvent) => {
+    if (event.key === 'Escape') {
+      event.preventDefault();
+      props.onClose();
+    } else if (event.target instanceof HTMLInputElement) {
+      // Keep global shortcuts (like Q for back) from swallowing typed characters
+      event.stopPropagation();
+    }
+  };
+  onMount(() => document.addEventListener('keydown', handleKeyDown, { capture: true }));
+  onCleanup(() => document.removeEventListener('keydown', handleKeyDown, { capture: true }));
+
+  // Immutable update of one settings section
+  const update = <K extends keyof Settings>(section: K, value: Partial<Settings[K]> | Settings[K]) => {
+    setSettings(prev => {
+      if (!prev) return prev;
+      const current = prev[section];
+      const next = typeof current === 'object' && current !== null && !Array.isArray(current)
+        ? { ...current, ...(value as object) }
+        : value;
+      return { ...prev, [section]: next } as Settings;
+    });
+  };
+
+  const overriddenBy = (setting: string) =>
+    info()?.overrides.find(override => override.setting === setting)?.variable;
+
+  const updateEnvRow = (index: number, field: keyof EnvRow, value: string) => {
+    setEnvRows(prev => prev.map((row, i) => (i === index ? { ...row, [field]: value } : row)));
+  };
+
+  const optionalPath = (value: string) => (value.trim() ? value.trim() : null);
+
+  const updateRoot = (index: number, value: Partial<ScriptRoot>) => {
+    const roots = settings()!.scripts.roots;
+    update('scripts', { roots: roots.map((root, i) => (i === index ? { ...root, ...value } : root)) });
+  };
+
+  const save = async () => {
+    const current = settings();
+    if (!current) return;
+    const env: Record<string, string> = {};
+    for (const row of envRows()) {
+      const name = row.name.trim();
+      if (name) env[name] = row.value;
+    }
+    try {
+      const effective = await UIService.updateSettings({ ...current, env });
+      applyTheme(effective.theme);
+      setStatus(`Saved to ${info()?.path ?? 'settings file'}`);
+      appLogger.info('Settings saved');
+      await loadRootsReport();
+    } catch (error) {
+      setStatus(`Not saved: ${error}`);
+    }
+  };
+
+  return (
+    <div class="glass-container" style="width: 100vw; height: 100vh; display: flex; flex-direction: column; color: #cccccc;">
+      <div data-tauri-drag-region style="padding: 14px 20px; font-size: 20px; background: #403c4a;">
+        Settings
+      </div>
+
+      <Show when={settings()} fallback={<div style="padding: 20px;">{status() || 'Loading...'}</div>}>
+        {(s) => (
+          <div style="flex: 1; overflow-y: auto; padding: 16px 20px;">
+            <Show when={info()?.overrides.length}>
+              <div style="font-size: 12px; color: #e5c07b; margin-bottom: 16px;">
+                Overridden by environment: {info()!.overrides.map(o => `${o.variable} → ${o.setting}`).join(', ')}
+              </div>
+            </Show>
+
+            <Section title="Script folders" hint="Leave empty for the defaults. When folders hold scripts with the same file name, the higher priority wins (user folder 100, legacy 10, built-in 0).">
+              <Row label={`User scripts${overriddenBy('scripts.user_dir') ? ' (overridden)' : ''}`}>
+                <input style={inputStyle} placeholder="Documents/SnapRun/Scripts" value={s().scripts.user_dir ?? ''}
+                  onInput={(e) => update('scripts', { user_dir: optionalPath(e.currentTarget.value) })} />
+              </Row>
+              <Row label={`Bundled scripts${overriddenBy('scripts.bundled_dir') ? ' (overridden)' : ''}`}>
+                <input style={inputStyle} placeholder="app user_scripts folder" value={s().scripts.bundled_dir ?? ''}
+                  onInput={(e) => update('scripts', { bundled_dir: optionalPath(e.currentTarget.value) })} />
+              </Row>
+              <Index each={s().scripts.roots}>
+                {(root, index) => (
+                  <div style="display: flex; align-items: center; gap: 8px; margin-bottom: 6px;">
+                    <input style={`${inputStyle} flex: 0 0 120px;`} placeholder="Label" value={root().label}
+                      onInput={(e) => updateRoot(index, { label: e.currentTarget.value })} />
+                    <input style={inputStyle} placeholder="Folder" value={root().path}
+                      onInput={(e) => updateRoot(index, { path: e.currentTarget.value })} />
+                    <input style={`${inputStyle} flex: 0 0 70px;`} type="number" title="Priority (higher wins)" value={root().priority}
+                      onInput={(e) => updateRoot(index, { priority: Number(e.currentTarget.value) })} />
+                    <label style="font-size: 12px;" title="Never write new scripts here">
+                      <input type="checkbox" checked={root().read_only}
+                        onChange={(e) => updateRoot(index, { read_only: e.currentTarget.checked })} /> read-only
+                    </label>
+                    <label style="font-size: 12px;">
+                      <input type="checkbox" checked={root().enabled}
+                        onChange={(e) => updateRoot(index, { enabled: e.currentTarget.checked })} /> on
+                    </label>
+                    <button style={buttonStyle} title="Remove"
+                      onClick={() => update('scripts', { roots: s().scripts.roots.filter((_, i) => i !== index) })}>✕</button>
+                  </div>
+                )}
+              </Index>
+              <button style={buttonStyle}
+                onClick={() => update('scripts', { roots: [...s().scripts.roots, { label: '', path: '', priority: 50, read_only: false, enabled: true }] })}>
+                Add folder
+              </button>
+
+              <Show when={rootsReport()}>
+                {(report) => (
+                  <div style="margin-top: 12px; font-size: 12px; color: #858585;">
+                    <For each={report().roots}>
+                      {(root) => (
+                        <div style={root.enabled && root.exists ? '' : 'opacity: 0.5;'}>
+                          {root.priority} · {root.label}{root.read_only ? ' (read-only)' : ''} · {root.exists ? `${root.scripts} scripts` : 'missing'} · {root.path}
+                        </div>
+                      )}
+                    </For>
+                    <For each={report().shadowed}>
+                      {(entry) => (
+                        <div style="color: #e5c07b;">
+                          {entry.script.file_path} is shadowed by {entry.shadowed_by}
+                        </div>
+                      )}
+                    </For>
+                  </div>
+                )}
+              </Show>
+            </Section>
+
+            <Section title="Packages" hint="Install from a package folder or .zip holding a snaprun.toml. Dependencies are taken from packages next to it.">
+              <For each={packages()}>
+                {(pkg) => (
+                  <Row label={`${pkg.manifest.name} ${pkg.manifest.version}`}>
+                    <span style="flex: 1; font-size: 12px; color: #858585;">
+                      {pkg.manifest.description || pkg.manifest.scripts.join(', ')}
+                    </span>
+                    <button style={buttonStyle} title="Uninstall" onClick={() => uninstallPackage(pkg.manifest.name)}>✕</button>
+                  </Row>
+                )}
+              </For>
+              <div style="display: flex; gap: 8px;">
+                <input style={inputStyle} placeholder="Path to package folder or .zip" value={packageSource()}
+                  onInput={(e) => setPackageSource(e.currentTarget.value)} />
+                <button style={buttonStyle} onClick={() => installPackage(false)}>Install</button>
+                <button style={buttonStyle} onClick={() => installPackage(true)}>Upgrade</button>
+              </div>
+            </Section>
+
+            <Section title="Hotkeys">
+              <Row label="Show window">
+                <input style={inputStyle} value={s().hotkeys.show_window}
+                  onInput={(e) => update('hotkeys', { show_window: e.currentTarget.value })} />
+              </Row>
+            </Section>
+
+            <Section title="Logging">
+              <Row label={`Level${overriddenBy('logging.level') ? ' (overridden)' : ''}`}>
+                <select style={inputStyle} value={s().logging.level}
+                  onChange={(e) => update('logging', { level: e.currentTarget.value as Settings['logging']['level'] })}>
+                  <For each={['error', 'warn', 'info', 'debug', 'trace']}>{(level) => <option value={level}>{level}</option>}</For>
+                </select>
+              </Row>
+              <Row label="Keep log files (days)">
+                <input style={inputStyle} type="number" min="1" value={s().logging.retention_days}
+                  onInput={(e) => update('logging', { retention_days: Number(e.currentTarget.value) })} />
+              </Row>
+            </Section>
+
+            <Section title="Sandbox defaults">
+              <Row label="Dry run by default">
+                <input type="checkbox" checked={s().sandbox.dry_run_by_default}
+                  onChange={(e) => update('sandbox', { dry_run_by_default: e.currentTarget.checked })} />
+              </Row>
+              <Row label="Journal every run">
+                <input type="checkbox" checked={s().sandbox.journal_by_default}
+                  onChange={(e) => update('sandbox', { journal_by_default: e.currentTarget.checked })} />
+              </Row>
+              <Row label="Max run_script depth">
+                <input style={inputStyle} type="number" min="1" max="64" value={s().sandbox.max_script_depth}
+                  onInput={(e) => update('sandbox', { max_script_depth: Number(e.currentTarget.value) })} />
+              </Row>
+            </Section>
+
+            <Section title="Theme">
+              <Row label={`Theme${overriddenBy('theme') ? ' (overridden)' : ''}`}>
+                <select style={inputStyle} value={s().theme}
+                  onChange={(e) => update('theme', e.currentTarget.value as Settings['theme'])}>
+                  <For each={['system', 'dark', 'light']}>{(theme) => <option value={theme}>{theme}</option>}</For>
+                </select>
+              </Row>
+            </Section>
+
+            <Section title="Environment overrides" hint="Set for every script run, before the script's own .env file and env_set calls.">
+              <Index each={envRows()}>
+                {(row, index) => (
+                  <div style="display: flex; gap: 8px; margin-bottom: 6px;">
+                    <input style={inputStyle} placeholder="NAME" value={row().name}
+                      onInput={(e) => updateEnvRow(index, 'name', e.currentTarget.value)} />
+                    <input style={inputStyle} placeholder="value" value={row().value}
+                      onInput={(e) => updateEnvRow(index, 'value', e.currentTarget.value)} />
+                    <button style={buttonStyle} title="Remove"
+                      onClick={() => setEnvRows(prev => prev.filter((_, i) => i !== index))}>✕</button>
+                  </div>
+                )}
+              </Index>
+              <button style={buttonStyle} onClick={() => setEnvRows(prev => [...prev, { name: '', value: '' }])}>
+                Add variable
+              </button>
+            </Section>
+          </div>
+        )}
+      </Show>
+
+      <div style="
+        padding: 12px 20px;
+        border-top: 1px solid rgba(255, 255, 255, 0.1);
+        background: rgba(255, 255, 255, 0.05);
+        display: flex; justify-content: space-between; align-items: center;
+        font-size: 12px; color: #858585;
+      ">
+        <div>
+          <Show when={status()} fallback={'Esc to go back'}>{status()}</Show>
+        </div>
+        <div style="display: flex; gap: 8px;">
+          <button style={buttonStyle} onClick={props.onClose}>Back</button>
+          <button style={buttonStyle} onClick={save}>Save</button>
+        </div>
+      </div>
+    </div>
+  );
+};
@@ -1 +1,2 @@
 export { ScriptSearch } from './ScriptSearch';
+export { SettingsPage, applyTheme } from './SettingsPage';
@@ -7,8 +7,8 @@ export class UIService {
   /**
    * Send UI response back to Rust backend
    */
-  static async sendResponse(id: string, value: string): Promise<void> {
-    console.log('🟡 UIService: sendResponse called with id:', id, 'value:', value);
+  static async sendResponse(id: string, value: string, masked = false): Promise<void> {
+    console.log('🟡 UIService: sendResponse called with id:', id, 'value:', masked ? '<masked>' : value);
     try {
       await invoke('ui_response', { id, value });
       console.log('🟡 UIService: Response sent successfully');
@@ -90,12 +90,12 @@ export class UIService {
   }
 
   /**
-   * Execute a Rhai script by its ID
+   * Execute a Rhai script by its ID (a dry run only reports file and process changes)
    */
-  static async runRhaiScript(scriptId: string): Promise<string> {
-    console.log('🟡 UIService: runRhaiScript() called with ID:', scriptId);
+  static async runRhaiScript(scriptId: string, dryRun?: boolean): Promise<string> {
+    console.log('🟡 UIService: runRhaiScript() called with ID:', scriptId, dryRun ? '(dry run)' : '');
     try {
-      const result = await invoke('run_rhai_script', { scriptId });
+      const result = await invoke('run_rhai_script', { scriptId, dryRun });
       console.log('🟡 UIService: Rhai script execution result:', result);
       return result as string;
     } catch (error) {
@@ -103,6 +103,48 @@ export class UIService {
       throw error;
     }
   }
+
+  /**
+   * Load settings: as saved, as effective after environment overrides, and which overrides apply
+   */
+  static async getSettings(): Promise<SettingsInfo> {
+    return await invoke('get_settings') as SettingsInfo;
+  }
+
+  /**
+   * Validate and save settings; resolves to the effective settings
+   */
+  static async updateSettings(settings: Settings): Promise<Settings> {
+    return await invoke('update_settings', { settings }) as Settings;
+  }
+
+  /**
+   * Script roots in priority order and the scripts hidden by same-named ones
+   */
+  static async listScriptRoots(): Promise<ScriptRootsReport> {
+    return await invoke('list_script_roots') as ScriptRootsReport;
+  }
+
+  /**
+   * Installed script packages
+   */
+  static async listPackages(): Promise<InstalledPackage[]> {
+    return await invoke('list_packages') as InstalledPackage[];
+  }
+
+  /**
+   * Install (or upgrade) a package from a folder or zip; resolves to every package written
+   */
+  static async installPackage(source: string, upgrade = false, force = false): Promise<PackageChange[]> {
+    return await invoke('install_package', { source, upgrade, force }) as PackageChange[];
+  }
+
+  /**
+   * Uninstall a package; resolves to the removed version
+   */
+  static async uninstallPackage(name: string): Promise<string> {
+    return await invoke('uninstall_package', { name }) as string;
+  }
 }
 
 // TypeScript interface for ScriptInfo
@@ -113,4 +155,70 @@ export interface ScriptInfo {
   file_path: string;
   category: string;
   script_type: 'Rhai';
+  read_only?: boolean;
+}
+
+export interface ScriptRoot {
+  label: string;
+  path: string;
+  priority: number;
+  read_only: boolean;
+  enabled: boolean;
+}
+
+// Contents of a package's snaprun.toml
+export interface PackageManifest {
+  name: string;
+  version: string;
+  description: string;
+  scripts: string[];
+  snaprun: string | null;
+  dependencies: Record<string, string>;
+  commands: string[];
+}
+
+export interface InstalledPackage {
+  manifest: PackageManifest;
+  path: string;
+}
+
+export interface PackageChange {
+  name: string;
+  version: string;
+  previous: string | null;
+}
+
+export interface ScriptRootsReport {
+  roots: (ScriptRoot & { exists: boolean; scripts: number })[];
+  shadowed: { script: ScriptInfo; shadowed_by: string }[];
+}
+
+// TypeScript mirror of the Rust Settings struct
+export interface Settings {
+  version: number;
+  theme: 'system' | 'dark' | 'light';
+  scripts: {
+    bundled_dir: string | null;
+    user_dir: string | null;
+    roots: ScriptRoot[];
+  };
+  hotkeys: { show_window: string };
+  logging: {
+    level: 'error' | 'warn' | 'info' | 'debug' | 'trace';
+    retention_days: number;
+  };
+  sandbox: {
+    dry_run_by_default: boolean;
+    journal_by_default: boolean;
+    max_script_depth: number;
+  };
+  env: Record<string, string>;
+}
+
+export interface SettingsInfo {
+  settings: Settings;
+  effective: Settings;
+  overrides: { variable: string; setting: string }[];
+  supported_overrides: string[];
+  path: string;
 }